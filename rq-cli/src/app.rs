@@ -1,26 +1,70 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Write;
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
+use chrono::{Local, Utc};
 use ratatui::{
     prelude::{Constraint, Direction, Layout},
     style::{Color, Style},
-    widgets::{Block, Borders},
 };
 use rq_core::{
-    parser::{HttpFile, HttpRequest, TemplateRequest},
-    request::Response,
+    auth::{
+        oauth2::{OAuth2Config, OAuth2Template},
+        TokenCache,
+    },
+    parser::{
+        parse_body, parse_request, scenario, variables::TemplateString, HttpRequest,
+        TemplateRequest,
+    },
+    request::{
+        header::{HeaderValue, AUTHORIZATION, USER_AGENT},
+        redact::Redaction,
+        Progress, Response, StatusCode,
+    },
 };
 use tokio::sync::mpsc::{channel, Receiver, Sender};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::{
+    browser,
     components::{
-        menu::Menu, message_dialog::MessageDialog, popup::Popup, response_panel::ResponsePanel,
-        variables::panel::VarsPanel, BlockComponent, HandleSuccess,
+        self,
+        body_editor::BodyEditor,
+        confirm_dialog::ConfirmDialog,
+        decoded::DecodedPanel,
+        headers::HeadersPanel,
+        history::HistoryPanel,
+        input::builder::{InputBuilder, InputType},
+        links::LinksPanel,
+        menu::{Entry, Menu},
+        message_dialog::MessageDialog,
+        messages::{self, LoggedMessage, MessagesPanel},
+        popup::Popup,
+        query::QueryPanel,
+        queue::QueuePanel,
+        response_panel::{self, ResponsePanel, SaveOption},
+        retry_dialog::{self, RetryDialog},
+        snippets::SnippetsPanel,
+        template_request,
+        variables::panel::VarsPanel,
+        BlockComponent, HandleSuccess,
     },
-    event::{Event, Message},
+    event::{Event, EventSender, Message},
+    favorites::{self, Favorites},
+    history, keymap,
+    queue::{self, Queued},
+    session::{self, Session},
+    snippets::Snippet,
+    workspace::{self, HostRule, Openers, RequestId, Workspace},
 };
 
 #[derive(Default)]
@@ -31,66 +75,511 @@ pub enum FocusState {
     VarsPanel,
 }
 
+/// Order of the request list.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum Sort {
+    // As written in the files
+    #[default]
+    File,
+    Method,
+    Url,
+    Name,
+    // Of the last response, requests not sent yet last
+    Status,
+}
+
+impl Sort {
+    fn next(self) -> Self {
+        match self {
+            Sort::File => Sort::Method,
+            Sort::Method => Sort::Url,
+            Sort::Url => Sort::Name,
+            Sort::Name => Sort::Status,
+            Sort::Status => Sort::File,
+        }
+    }
+
+    fn from_label(label: &str) -> Self {
+        std::iter::successors(Some(Sort::default()), |sort| Some(sort.next()))
+            .take(5)
+            .find(|sort| sort.label() == label)
+            .unwrap_or_default()
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Sort::File => "file",
+            Sort::Method => "method",
+            Sort::Url => "url",
+            Sort::Name => "name",
+            Sort::Status => "status",
+        }
+    }
+}
+
+/// Headers the request list is split by.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum Group {
+    #[default]
+    None,
+    // First `@tag` of each request
+    Tag,
+    File,
+}
+
+impl Group {
+    fn next(self) -> Self {
+        match self {
+            Group::None => Group::Tag,
+            Group::Tag => Group::File,
+            Group::File => Group::None,
+        }
+    }
+
+    fn from_label(label: &str) -> Self {
+        std::iter::successors(Some(Group::default()), |group| Some(group.next()))
+            .take(3)
+            .find(|group| group.label() == label)
+            .unwrap_or_default()
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Group::None => "none",
+            Group::Tag => "tag",
+            Group::File => "file",
+        }
+    }
+}
+
+type RequestResult = Result<Response, (String, Failure)>;
+
+// Why a request got no response
+pub(crate) enum Failure {
+    Other,
+    // Worth sending again as it is, see `is_transient`, and whether it can be
+    // without asking: it's idempotent, or it wasn't sent at all
+    Transient(bool),
+    // Its host, at the url probed, can't be reached, see `queue`
+    Offline(String),
+}
+
+struct Outgoing {
+    request: HttpRequest,
+    idx: usize,
+    // See `favorites::key`, for the history
+    key: String,
+    // Cache key and configuration of the OAuth2 token to inject
+    oauth2: Option<(String, OAuth2Config)>,
+}
+
 pub struct App {
-    res_rx: Receiver<(Response, usize)>,
-    req_tx: Sender<(HttpRequest, usize)>,
+    res_rx: Receiver<(RequestResult, usize)>,
+    req_tx: Sender<Outgoing>,
+    events: EventSender,
 
     request_menu: Menu<TemplateRequest>,
     vars_panel: VarsPanel,
     file_path: String,
+    // Reloaded by `:e` and `:env`
+    path: PathBuf,
+    // File of each request
+    sources: Vec<PathBuf>,
+    // Requests added from a snippet, or a link, but not to their file
+    unsaved: HashSet<usize>,
+    sort: Sort,
+    group: Group,
+    favorites: Favorites,
+    // Whether the list only shows favorites
+    only_favorites: bool,
+    // Variables edited in the variables panel, restored with the session
+    overrides: HashMap<String, String>,
 
     responses: Vec<ResponsePanel>,
     should_exit: bool,
     vars_visible: bool,
     focus: FocusState,
-    popups: VecDeque<Box<dyn BlockComponent>>,
+    // Stacked, the last one on top and getting the keys
+    popups: Vec<Box<dyn BlockComponent>>,
+    // Requests waiting for the in-flight one, i.e. the rest of a `@depends` chain
+    pending: VecDeque<usize>,
+    // Index of the `@auth` request, and when its session expires
+    auth_idx: Option<usize>,
+    session_expiry: Option<Instant>,
+    // Whether the current request already got a `401` and was retried
+    retried: bool,
+    // Prompt to send again a request that failed to connect, if shown
+    retry: Option<Retry>,
+    next_retry: u64,
+    // In a row, without a response in between
+    auto_retries: usize,
+    // Requests waiting for their host to be reachable, with `--offline-queue`
+    queue: Vec<Queued>,
+    // Whether the host of the first queued one is being probed
+    probing: bool,
+    environment: Option<String>,
+    oauth2: HashMap<String, OAuth2Template>,
+    hosts: Vec<HostRule>,
+    request_id: Option<RequestId>,
+    redaction: Option<Redaction>,
+    snippets: Vec<Snippet>,
+    // Info and warnings shown without blocking, by id
+    toasts: Vec<(u64, Message)>,
+    next_toast: u64,
+    // Shown so far, for `:messages`
+    messages: Vec<LoggedMessage>,
+    // Files being saved, bytes written and total
+    saves: Vec<(String, u64, u64)>,
+    // For `:open` and `:reveal`
+    last_saved: Option<PathBuf>,
+    openers: Openers,
+}
+
+struct Retry {
+    id: u64,
+    idx: usize,
+    // Of the automatic retry, if enabled
+    deadline: Option<Instant>,
+}
+
+// Automatic retries in a row, before waiting for a key press
+const MAX_AUTO_RETRIES: usize = 5;
+
+// How long info and warnings are shown
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+// Kept for `:messages`, dropping the oldest
+const MAX_MESSAGES: usize = 200;
+
+// Name, tags, dependencies, user agent and notes of `request`
+fn details(request: &TemplateRequest) -> String {
+    let mut details = format!("{} {}\n", request.method, request.url);
+    if let Some(name) = &request.name {
+        let _ = writeln!(details, "name: {name}");
+    }
+    if !request.tags.is_empty() {
+        let _ = writeln!(details, "tags: {}", request.tags.join(", "));
+    }
+    if !request.depends.is_empty() {
+        let _ = writeln!(details, "depends on: {}", request.depends.join(", "));
+    }
+    let user_agent = request
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_else(rq_core::request::user_agent);
+    let _ = writeln!(details, "user agent: {user_agent}");
+
+    if request.notes.is_empty() {
+        details.push_str("\nNo notes, add some with `# @note`");
+    } else {
+        details.push('\n');
+        details.push_str(&request.notes.join("\n"));
+    }
+
+    details
+}
+
+// Comments out the `nth` line of the header `name` of the request at `line`
+// (from 1), or back in if `enabled`
+fn toggle_header_line(
+    content: &str,
+    line: usize,
+    name: &str,
+    nth: usize,
+    enabled: bool,
+) -> Option<String> {
+    let mut lines = content.split_inclusive('\n').collect::<Vec<_>>();
+    let (target, current) = lines
+        .iter()
+        .enumerate()
+        .skip(line)
+        .take_while(|(_, line)| !line.trim().is_empty())
+        .filter(|(_, line)| {
+            let header = line.trim_start().trim_start_matches('#').trim_start();
+            // Query params continuing the url, i.e. `&redirect=https://...`
+            !header.starts_with(['?', '&'])
+                && header
+                    .split_once(':')
+                    .is_some_and(|(header, _)| header.trim().eq_ignore_ascii_case(name))
+        })
+        .nth(nth)?;
+
+    let toggled = if enabled {
+        let rest = current.trim_start().strip_prefix('#')?;
+        rest.strip_prefix(' ').unwrap_or(rest).to_owned()
+    } else {
+        format!("# {current}")
+    };
+    lines[target] = &toggled;
+
+    Some(lines.concat())
 }
 
 fn spawn_request_handler(
-    mut req_rx: Receiver<(HttpRequest, usize)>,
-    res_tx: Sender<(Response, usize)>,
+    mut req_rx: Receiver<Outgoing>,
+    res_tx: Sender<(RequestResult, usize)>,
+    events: EventSender,
+    workspace: PathBuf,
 ) {
     tokio::spawn(async move {
-        while let Some((req, i)) = req_rx.recv().await {
-            match rq_core::request::execute(req).await {
-                Ok(data) => res_tx.send((data, i)).await.unwrap(),
-                Err(e) => {
-                    Event::emit(Event::Message(Message::Error(e.to_string())));
-                }
-            };
+        let mut tokens = TokenCache::default();
+
+        while let Some(outgoing) = req_rx.recv().await {
+            let idx = outgoing.idx;
+            let (key, request, started_at) =
+                (outgoing.key.clone(), outgoing.request.clone(), Utc::now());
+            let result = execute(outgoing, &mut tokens, &events).await;
+
+            let entry = history::Entry::new(
+                &workspace,
+                key,
+                &request,
+                result.as_ref().map_err(|(e, _)| e.as_str()),
+                started_at,
+            );
+            if let Err(e) = history::append(&entry).await {
+                tracing::warn!(error = format!("{e:#}"), "history not recorded");
+            }
+
+            // The app was reloaded
+            if res_tx.send((result, idx)).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn execute(
+    outgoing: Outgoing,
+    tokens: &mut TokenCache,
+    events: &EventSender,
+) -> RequestResult {
+    let Outgoing {
+        mut request,
+        idx,
+        oauth2,
+        ..
+    } = outgoing;
+
+    if let Some((key, config)) = &oauth2 {
+        let token = match tokens.get(key) {
+            Some(token) => token.clone(),
+            None => {
+                let token = config
+                    .acquire(|url| {
+                        events.emit(Event::Message(Message::Info(format!(
+                            "Complete the authorization in your browser:\n{url}"
+                        ))));
+                        let _ = browser::open(url);
+                    })
+                    .await
+                    .map_err(|e| (e.to_string(), Failure::Other))?;
+                tokens.insert(key.clone(), token.clone());
+                token
+            }
+        };
+
+        let value = HeaderValue::from_str(&format!("Bearer {}", token.access_token))
+            .map_err(|e| (e.to_string(), Failure::Other))?;
+        request.headers.insert(AUTHORIZATION, value);
+    }
+
+    // Reported once per percent
+    let events = events.clone();
+    let percent = AtomicU64::new(0);
+    let progress: Progress = Arc::new(move |sent: u64, total: u64| {
+        let current = sent * 100 / total.max(1);
+        if percent.swap(current, Ordering::Relaxed) != current {
+            events.emit(Event::Upload((idx, sent, total)));
         }
     });
+
+    let url = request.full_url();
+    let idempotent = request.method.is_idempotent();
+    let response = match rq_core::request::execute_with_progress(request, progress).await {
+        Ok(response) => response,
+        Err(e) => {
+            let failure = if queue::enabled()
+                && rq_core::request::is_unreachable(&*e)
+                && !queue::reachable(&url).await
+            {
+                Failure::Offline(url)
+            } else if rq_core::request::is_transient(&*e) {
+                Failure::Transient(idempotent || rq_core::request::is_connect(&*e))
+            } else {
+                Failure::Other
+            };
+            return Err((e.to_string(), failure));
+        }
+    };
+
+    // The token was revoked, acquire a new one on next send
+    if let (Some((key, _)), StatusCode::UNAUTHORIZED) = (&oauth2, response.status) {
+        tokens.remove(key);
+    }
+
+    Ok(response)
 }
 
 impl App {
-    const KEYMAPS: &'static [(&'static str, &'static str); 2] =
-        &[("q", "exit"), ("v", "variables")];
+    const KEYMAPS: &'static [(&'static str, &'static str); 5] = &[
+        ("q", "exit"),
+        ("v", "variables"),
+        ("V", "show/hide variables"),
+        (":", "command"),
+        ("H", "history"),
+    ];
+    const LIST_KEYMAPS: &'static [(&'static str, &'static str); 12] = &[
+        ("s", "sort by"),
+        ("g", "group by"),
+        ("f", "star/unstar"),
+        ("F", "only starred"),
+        ("i", "details and notes"),
+        ("p", "query params, enable/disable and edit"),
+        ("R", "raw request"),
+        ("z/Z", "fold body/all bodies"),
+        ("U", "full urls"),
+        ("b", "edit body"),
+        ("h", "enable/disable headers"),
+        ("n", "new request from a snippet"),
+    ];
 
-    pub fn new(file_path: String, http_file: HttpFile) -> Self {
-        let (req_tx, req_rx) = channel::<(HttpRequest, usize)>(1);
-        let (res_tx, res_rx) = channel::<(Response, usize)>(1);
+    pub fn new(workspace: Workspace, events: EventSender) -> anyhow::Result<Self> {
+        let Workspace {
+            name: file_path,
+            path,
+            http_file,
+            sources,
+            environment,
+            oauth2,
+            hosts,
+            request_id,
+            redaction,
+            snippets,
+            openers,
+            ..
+        } = workspace;
 
-        spawn_request_handler(req_rx, res_tx);
+        let (req_tx, req_rx) = channel::<Outgoing>(1);
+        let (res_tx, res_rx) = channel::<(RequestResult, usize)>(1);
+
+        spawn_request_handler(req_rx, res_tx, events.clone(), path.clone());
 
         let responses = (0..http_file.requests.len())
             .map(|idx| ResponsePanel::default().with_idx(idx))
             .collect();
 
-        let request_menu = Menu::new(http_file.requests)
-            .with_confirm_callback(|_| Event::emit(Event::Focus(FocusState::ResponsePanel)));
+        let auth_idx = http_file.requests.iter().position(|r| r.auth.is_some());
+
+        let favorites = Favorites::load(&path)?;
 
-        App {
+        let mut request_menu = Menu::new(http_file.requests)
+            .with_numbers()
+            .with_confirm_callback(|_, events| {
+                events.emit(Event::Focus(FocusState::ResponsePanel))
+            });
+
+        for i in 0..request_menu.items().len() {
+            let starred = favorites.contains(request_menu.get(i));
+            request_menu.set_marked(i, starred);
+        }
+
+        let mut app = App {
             res_rx,
             req_tx,
+            events,
 
             request_menu,
             file_path,
+            path,
+            sources,
+            unsaved: HashSet::new(),
+            sort: Sort::default(),
+            group: Group::default(),
             vars_panel: VarsPanel::new(http_file.variables),
             responses,
             should_exit: false,
             vars_visible: true,
             focus: FocusState::default(),
-            popups: VecDeque::new(),
+            popups: Vec::new(),
+            pending: VecDeque::new(),
+            auth_idx,
+            session_expiry: None,
+            retried: false,
+            retry: None,
+            next_retry: 0,
+            auto_retries: 0,
+            queue: Vec::new(),
+            probing: false,
+            environment,
+            oauth2,
+            hosts,
+            request_id,
+            redaction,
+            snippets,
+            favorites,
+            only_favorites: false,
+            overrides: HashMap::new(),
+            toasts: Vec::new(),
+            next_toast: 0,
+            messages: Vec::new(),
+            saves: Vec::new(),
+            last_saved: None,
+            openers,
+        };
+
+        match Session::load(&app.path) {
+            Ok(Some(session)) => app.restore(session),
+            Ok(None) => (),
+            Err(e) => tracing::warn!(error = format!("{e:#}"), "session not restored"),
+        }
+
+        app.check_keymaps();
+
+        Ok(app)
+    }
+
+    fn restore(&mut self, session: Session) {
+        for (name, value) in session.variables {
+            match value.parse() {
+                Ok(parsed) => {
+                    self.vars_panel.update(name.clone(), parsed);
+                    self.overrides.insert(name, value);
+                }
+                Err(e) => tracing::warn!(name, error = e, "variable not restored"),
+            }
+        }
+
+        self.sort = Sort::from_label(&session.sort);
+        self.group = Group::from_label(&session.group);
+        self.only_favorites = session.only_favorites && !self.favorites.is_empty();
+        self.vars_visible = session.vars_visible;
+        self.request_menu.select(session.selected);
+        self.arrange();
+        self.request_menu.set_offset(session.scroll);
+    }
+
+    /// Saves the selection, list layout and edited variables, restored when
+    /// the same workspace is opened again.
+    pub fn save_session(&self) {
+        let session = Session {
+            selected: self.request_menu.idx(),
+            scroll: self.request_menu.offset(),
+            sort: self.sort.label().into(),
+            group: self.group.label().into(),
+            only_favorites: self.only_favorites,
+            vars_visible: self.vars_visible,
+            variables: self
+                .overrides
+                .iter()
+                .filter(|(name, _)| !session::is_secret(name))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        };
+
+        if let Err(e) = session.save(&self.path) {
+            tracing::warn!(error = format!("{e:#}"), "session not saved");
         }
     }
 
@@ -102,8 +591,8 @@ impl App {
             }
         }
 
-        if let Some(popup) = self.popups.front_mut() {
-            match popup.on_event(event)? {
+        if let Some(popup) = self.popups.last_mut() {
+            match popup.on_event(event, &self.events)? {
                 HandleSuccess::Consumed => {
                     return Ok(());
                 }
@@ -113,9 +602,11 @@ impl App {
 
         // Propagate event to siblings
         let event_result = match self.focus {
-            FocusState::RequestsList => self.request_menu.on_event(event),
-            FocusState::ResponsePanel => self.responses[self.request_menu.idx()].on_event(event),
-            FocusState::VarsPanel => self.vars_panel.on_event(event),
+            FocusState::RequestsList => self.request_menu.on_event(event, &self.events),
+            FocusState::ResponsePanel => {
+                self.responses[self.request_menu.idx()].on_event(event, &self.events)
+            }
+            FocusState::VarsPanel => self.vars_panel.on_event(event, &self.events),
         };
 
         match event_result {
@@ -127,11 +618,110 @@ impl App {
         };
 
         match event.code {
-            KeyCode::Char('q' | 'Q') => {
-                self.should_exit = true;
+            KeyCode::Char('q' | 'Q') => self.quit(),
+            KeyCode::Char('v') => {
+                self.vars_visible = true;
+                self.events.emit(Event::Focus(FocusState::VarsPanel));
+            }
+            KeyCode::Char('V') => {
+                self.vars_visible = !self.vars_visible;
+                if !self.vars_visible && matches!(self.focus, FocusState::VarsPanel) {
+                    self.focus = FocusState::RequestsList;
+                }
+            }
+            KeyCode::Char(':') => self
+                .events
+                .emit(Event::NewInput(InputBuilder::new(InputType::Command))),
+            KeyCode::Char('H') => self
+                .events
+                .spawn(async { Ok(Event::ShowHistory(history::read(1000).await?)) }),
+            KeyCode::Char('s') if matches!(self.focus, FocusState::RequestsList) => {
+                self.sort = self.sort.next();
+                self.arrange();
+            }
+            KeyCode::Char('g') if matches!(self.focus, FocusState::RequestsList) => {
+                self.group = self.group.next();
+                self.arrange();
+            }
+            KeyCode::Char('f') if matches!(self.focus, FocusState::RequestsList) => {
+                let idx = self.request_menu.idx();
+                self.favorites
+                    .toggle(self.request_menu.selected(), &self.events);
+                let starred = self.favorites.contains(self.request_menu.selected());
+                self.request_menu.set_marked(idx, starred);
+                self.arrange();
+            }
+            KeyCode::Char('i') if matches!(self.focus, FocusState::RequestsList) => {
+                self.events.emit(Event::Message(Message::Custom(
+                    "details".into(),
+                    details(self.request_menu.selected()),
+                )))
+            }
+            KeyCode::Char('p') if matches!(self.focus, FocusState::RequestsList) => {
+                let request = self.request_menu.selected();
+                if request.query.is_empty() {
+                    return Err(anyhow!("the request has no query params"));
+                }
+                let panel =
+                    QueryPanel::new(self.request_menu.idx(), request, self.vars_panel.vars());
+                self.popups
+                    .push(Box::new(Popup::new(panel).with_size(70, 50)));
+            }
+            KeyCode::Char('R') if matches!(self.focus, FocusState::RequestsList) => {
+                let vars = self.vars_panel.vars();
+                let mut request = self.request_menu.selected().fill(vars)?;
+                workspace::apply_host_rules(&self.hosts, &mut request, vars)?;
+                if !request.headers.contains_key(USER_AGENT) {
+                    let user_agent = HeaderValue::from_str(&rq_core::request::user_agent())?;
+                    request.headers.insert(USER_AGENT, user_agent);
+                }
+
+                self.events.emit(Event::Message(Message::Custom(
+                    "raw request".into(),
+                    request.to_http1(),
+                )))
+            }
+            KeyCode::Char('b') if matches!(self.focus, FocusState::RequestsList) => {
+                let request = self.request_menu.selected();
+                let json = request.headers.iter().any(|(name, value)| {
+                    name.eq_ignore_ascii_case("content-type") && value.to_string().contains("json")
+                });
+                let editor =
+                    BodyEditor::new(self.request_menu.idx(), &request.body.to_string(), json);
+                self.popups
+                    .push(Box::new(Popup::new(editor).with_size(70, 70)));
+            }
+            KeyCode::Char('z') if matches!(self.focus, FocusState::RequestsList) => {
+                self.request_menu.toggle_fold()
+            }
+            KeyCode::Char('Z') if matches!(self.focus, FocusState::RequestsList) => {
+                self.request_menu.collapse_all()
+            }
+            KeyCode::Char('U') if matches!(self.focus, FocusState::RequestsList) => {
+                template_request::toggle_full_urls()
+            }
+            KeyCode::Char('h') if matches!(self.focus, FocusState::RequestsList) => {
+                let request = self.request_menu.selected();
+                if request.headers.is_empty() {
+                    return Err(anyhow!("the request has no headers"));
+                }
+                let panel = HeadersPanel::new(self.request_menu.idx(), request);
+                self.popups
+                    .push(Box::new(Popup::new(panel).with_size(70, 50)));
             }
-            KeyCode::Char('v') => Event::emit(Event::Focus(FocusState::VarsPanel)),
-            KeyCode::Char('?') => Event::emit(Event::Message(Message::Custom(
+            KeyCode::Char('n') if matches!(self.focus, FocusState::RequestsList) => {
+                let panel = SnippetsPanel::new(self.snippets.clone());
+                self.popups
+                    .push(Box::new(Popup::new(panel).with_size(70, 60)));
+            }
+            KeyCode::Char('F') if matches!(self.focus, FocusState::RequestsList) => {
+                if !self.only_favorites && self.favorites.is_empty() {
+                    return Err(anyhow!("no starred requests, star one with 'f'"));
+                }
+                self.only_favorites = !self.only_favorites;
+                self.arrange();
+            }
+            KeyCode::Char('?') => self.events.emit(Event::Message(Message::Custom(
                 "keymaps".into(),
                 self.keymaps() + "\nPress any key to close",
             ))),
@@ -141,17 +731,347 @@ impl App {
         Ok(())
     }
 
+    // Lays out the request list by `sort` and `group`, indexes are unchanged
+    fn arrange(&mut self) {
+        let requests = self.request_menu.items();
+        let mut order = (0..requests.len())
+            .filter(|&i| !self.only_favorites || self.favorites.contains(&requests[i]))
+            .collect::<Vec<_>>();
+
+        match self.sort {
+            Sort::File => (),
+            Sort::Method => order.sort_by_key(|&i| requests[i].method.as_str()),
+            Sort::Url => order.sort_by_cached_key(|&i| requests[i].url.to_string()),
+            Sort::Name => order.sort_by_key(|&i| (requests[i].name.is_none(), &requests[i].name)),
+            Sort::Status => order.sort_by_key(|&i| {
+                let status = self.responses[i].status();
+                (status.is_none(), status.map(|s| s.as_u16()))
+            }),
+        }
+
+        let group_of = |i: usize| match self.group {
+            Group::None => None,
+            Group::Tag => Some(
+                requests[i]
+                    .tags
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| "untagged".into()),
+            ),
+            Group::File => Some(self.sources[i].display().to_string()),
+        };
+
+        // Groups in order of first appearance
+        let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+        for i in order {
+            let group = group_of(i);
+            match groups.iter_mut().find(|(name, _)| *name == group) {
+                Some((_, items)) => items.push(i),
+                None => groups.push((group, vec![i])),
+            }
+        }
+
+        let layout = groups
+            .into_iter()
+            .flat_map(|(name, items)| {
+                name.map(Entry::Group)
+                    .into_iter()
+                    .chain(items.into_iter().map(Entry::Item))
+            })
+            .collect();
+        self.request_menu.arrange(layout);
+    }
+
+    // Selects and sends the request of `entry`, if it's from this workspace
+    fn resend(&mut self, entry: &history::Entry) -> anyhow::Result<()> {
+        if self.path.canonicalize().ok().as_ref() != Some(&entry.workspace) {
+            return Err(anyhow!(
+                "the request was sent from {}",
+                entry.workspace.display()
+            ));
+        }
+
+        let idx = self
+            .request_menu
+            .items()
+            .iter()
+            .position(|request| favorites::key(request) == entry.request)
+            .ok_or_else(|| anyhow!("'{}' not found", entry.request))?;
+
+        self.request_menu.select(idx);
+        self.events.emit(Event::SendRequest(idx));
+        self.events.emit(Event::Focus(FocusState::ResponsePanel));
+        Ok(())
+    }
+
+    // Appends a `GET` request to `url` to the list, from the same file as the
+    // selected one, and selects it. With `persist` it's appended to the file too
+    fn add_request(&mut self, url: &str, persist: bool) -> anyhow::Result<()> {
+        self.add_snippet(&format!("GET {url}"), persist)
+            .map_err(|_| anyhow!("invalid url '{url}'"))
+    }
+
+    // Adds the request `snippet` after the others, and to the file of the
+    // selected one if `persist`
+    fn add_snippet(&mut self, snippet: &str, persist: bool) -> anyhow::Result<()> {
+        let mut request = parse_request(snippet).map_err(|e| anyhow!(e))?;
+        workspace::resolve_protos(&mut request, &self.source_path(self.request_menu.idx()));
+
+        let idx = self.request_menu.items().len();
+        let source = self.sources[self.request_menu.idx()].clone();
+        if persist {
+            let path = self.source_path(self.request_menu.idx());
+            let content =
+                fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("opening {}", path.display()))?;
+            file.write_all(format!("\n###\n\n{snippet}\n").as_bytes())
+                .with_context(|| format!("writing {}", path.display()))?;
+
+            // After the separator and the empty lines around it
+            request.line += content.matches('\n').count() + 3;
+            self.show_message(Message::Info(format!("Added to {}", path.display())));
+        } else {
+            self.unsaved.insert(idx);
+        }
+        self.request_menu.add(request);
+        self.responses.push(ResponsePanel::default().with_idx(idx));
+        self.sources.push(source);
+
+        self.arrange();
+        self.request_menu.select(idx);
+        self.events.emit(Event::Focus(FocusState::RequestsList));
+        Ok(())
+    }
+
+    // Sources of a manifest are relative to its directory
+    fn source_path(&self, idx: usize) -> PathBuf {
+        if self.path.is_dir() {
+            self.path.join(&self.sources[idx])
+        } else {
+            self.sources[idx].clone()
+        }
+    }
+
+    // Comments the header out, or back in, in the request and in its file
+    fn toggle_header(&mut self, idx: usize, header: usize) -> anyhow::Result<()> {
+        if self.unsaved.contains(&idx) {
+            return Err(anyhow!(
+                "the request isn't in a file, add it to the file to toggle its headers"
+            ));
+        }
+
+        let path = self.source_path(idx);
+        let request = self.request_menu.get(idx);
+        let enabled = request.disabled_headers.contains(&header);
+        let name = &request.headers[header].0;
+        // Duplicate headers are told apart by their rank
+        let nth = request.headers[..header]
+            .iter()
+            .filter(|(other, _)| other.eq_ignore_ascii_case(name))
+            .count();
+
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let content = toggle_header_line(&content, request.line, name, nth, enabled)
+            .ok_or_else(|| anyhow!("header '{name}' not found in {}", path.display()))?;
+        fs::write(&path, content).with_context(|| format!("writing {}", path.display()))?;
+
+        // Once in the file, for the request to match it
+        self.request_menu.get_mut(idx).toggle_header(header);
+        Ok(())
+    }
+
+    /// Runs a `:` command:
+    ///
+    /// - `<n>`: select the n-th request, or go to line n of the response
+    /// - `send [n]`: send the selected or the n-th request
+    /// - `save body|all <file>`: save the current response
+    /// - `env <name>`: reload the workspace with another environment
+    /// - `e <path>`: open another file or workspace
+    /// - `messages`: list the messages shown so far
+    /// - `queue`: list the requests waiting for their host, see `--offline-queue`
+    /// - `open`, `reveal`: open the last saved file, or show it in the file manager
+    /// - `q`: exit
+    fn run_command(&mut self, line: &str) -> anyhow::Result<()> {
+        let line = line.trim();
+        let (command, arg) = line
+            .split_once(char::is_whitespace)
+            .map(|(command, arg)| (command, arg.trim()))
+            .unwrap_or((line, ""));
+
+        let request_idx = |arg: &str, len: usize| match arg.parse::<usize>() {
+            Ok(n) if (1..=len).contains(&n) => Ok(n - 1),
+            _ => Err(anyhow!("no request {arg}")),
+        };
+
+        match command {
+            "" => (),
+            "q" | "quit" => self.quit(),
+            n if n.chars().all(|c| c.is_ascii_digit())
+                && matches!(self.focus, FocusState::ResponsePanel) =>
+            {
+                let n = n.parse().map_err(|_| anyhow!("no line {n}"))?;
+                self.responses[self.request_menu.idx()].goto_line(n)?;
+            }
+            n if n.chars().all(|c| c.is_ascii_digit()) => {
+                let idx = request_idx(n, self.request_menu.items().len())?;
+                self.request_menu.select(idx);
+            }
+            "send" => {
+                if !arg.is_empty() {
+                    let idx = request_idx(arg, self.request_menu.items().len())?;
+                    self.request_menu.select(idx);
+                }
+                self.events
+                    .emit(Event::SendRequest(self.request_menu.idx()));
+                self.events.emit(Event::Focus(FocusState::ResponsePanel));
+            }
+            "save" => {
+                let (option, file_name) = arg
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| anyhow!("usage: save body|all <file>"))?;
+                let option = match option {
+                    "body" => SaveOption::Body,
+                    "all" => SaveOption::All,
+                    _ => return Err(anyhow!("usage: save body|all <file>")),
+                };
+                self.events
+                    .emit(Event::Save((file_name.trim().to_owned(), option)));
+            }
+            "env" if arg.is_empty() => return Err(anyhow!("usage: env <name>")),
+            "env" => {
+                if !self.path.is_dir() {
+                    return Err(anyhow!("environments need an rq.toml workspace"));
+                }
+                self.reload(self.path.clone(), Some(arg))?;
+            }
+            "open" | "reveal" => {
+                let path = self
+                    .last_saved
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("no response saved yet"))?;
+                let result = match command {
+                    "open" => browser::open_file(path, self.openers.file.as_deref()),
+                    _ => browser::reveal(path, self.openers.folder.as_deref()),
+                };
+                result.with_context(|| format!("opening {}", path.display()))?;
+            }
+            "queue" if self.queue.is_empty() => return Err(anyhow!("no queued requests")),
+            "queue" => {
+                let panel = QueuePanel::new(self.queue.clone());
+                self.popups
+                    .push(Box::new(Popup::new(panel).with_size(70, 60)));
+            }
+            "messages" if self.messages.is_empty() => return Err(anyhow!("no messages yet")),
+            "messages" => {
+                let panel = MessagesPanel::new(self.messages.clone());
+                self.popups
+                    .push(Box::new(Popup::new(panel).with_size(70, 60)));
+            }
+            "e" | "edit" if arg.is_empty() => return Err(anyhow!("usage: e <path>")),
+            "e" | "edit" => {
+                let environment = self.environment.clone();
+                self.reload(PathBuf::from(arg), environment.as_deref())?;
+            }
+            _ => return Err(anyhow!("unknown command '{command}'")),
+        }
+
+        Ok(())
+    }
+
+    // Replaces the app with a fresh one, discarding responses and variable edits
+    fn reload(&mut self, path: PathBuf, environment: Option<&str>) -> anyhow::Result<()> {
+        let workspace = Workspace::load(&path, environment)?;
+        tracing::info!(path = %path.display(), environment, "reloading");
+        let stale = workspace.configure_client()?;
+        if !stale.is_empty() {
+            self.events.emit(Event::Message(Message::Warning(format!(
+                "{} only apply once rq is restarted",
+                stale.join(", ")
+            ))));
+        }
+        self.save_session();
+
+        let last_saved = self.last_saved.take();
+        let messages = std::mem::take(&mut self.messages);
+        let toasts = std::mem::take(&mut self.toasts);
+        let next_toast = self.next_toast;
+        let next_retry = self.next_retry;
+        let queue = std::mem::take(&mut self.queue);
+        let probing = self.probing;
+        *self = App::new(workspace, self.events.clone())?;
+        // Messages outlive the workspace, the timers of the toasts and
+        // retries too
+        self.messages = messages;
+        self.toasts = toasts;
+        self.next_toast = next_toast;
+        self.next_retry = next_retry;
+        self.last_saved = last_saved;
+        // Queued requests still in the list, wherever they moved, the probe
+        // going on in the background
+        self.queue = queue
+            .into_iter()
+            .filter_map(|mut queued| {
+                queued.idx = (0..self.request_menu.items().len())
+                    .find(|&i| favorites::key(self.request_menu.get(i)) == queued.key)?;
+                Some(queued)
+            })
+            .collect();
+        self.probing = probing;
+        Ok(())
+    }
+
     fn keymaps(&self) -> String {
-        let keymaps = match self.focus {
-            FocusState::RequestsList => Self::KEYMAPS.iter().chain(self.request_menu.keymaps()),
-            FocusState::ResponsePanel => Self::KEYMAPS.iter().chain(self.responses[0].keymaps()),
-            FocusState::VarsPanel => Self::KEYMAPS.iter().chain(self.vars_panel.keymaps()),
+        self.focus_keymaps(&self.focus)
+            .into_iter()
+            .fold(String::new(), |mut s, (k, v)| {
+                let _ = writeln!(s, "{k}: {v}");
+                s
+            })
+    }
+
+    // Global keys, then the ones of the panel with `focus`
+    fn focus_keymaps(&self, focus: &FocusState) -> Vec<(&'static str, &'static str)> {
+        let panel = match focus {
+            FocusState::RequestsList => self.request_menu.keymaps(),
+            FocusState::ResponsePanel => self
+                .responses
+                .first()
+                .map(|response| response.keymaps())
+                .unwrap_or_default(),
+            FocusState::VarsPanel => self.vars_panel.keymaps(),
+        };
+        let list: &[_] = match focus {
+            FocusState::RequestsList => Self::LIST_KEYMAPS,
+            _ => &[],
         };
 
-        keymaps.fold(String::new(), |mut s, (k, v)| {
-            let _ = writeln!(s, "{k}: {v}");
-            s
-        })
+        Self::KEYMAPS
+            .iter()
+            .chain(panel)
+            .chain(list)
+            .copied()
+            .collect()
+    }
+
+    // Panels get keys first, so a global key bound again by one is shadowed
+    fn check_keymaps(&self) {
+        for focus in [
+            FocusState::RequestsList,
+            FocusState::ResponsePanel,
+            FocusState::VarsPanel,
+        ] {
+            for (key, actions) in keymap::conflicts(&self.focus_keymaps(&focus)) {
+                tracing::warn!(key, actions = actions.join(", "), "conflicting keymaps");
+                self.events.emit(Event::Message(Message::Warning(format!(
+                    "'{key}' is bound to {}",
+                    actions.join(", ")
+                ))));
+            }
+        }
     }
 
     pub fn draw(&self, f: &mut crate::terminal::Frame<'_>) {
@@ -173,23 +1093,47 @@ impl App {
             ),
         };
 
-        // Create two chunks with equal screen space
+        // Create two chunks with equal screen space, one above the other when
+        // plain, for the screen to be read in order
+        let direction = if components::plain() {
+            Direction::Vertical
+        } else {
+            Direction::Horizontal
+        };
         let [mut list_chunk, response_chunk] = {
             let x = Layout::default()
-                .direction(Direction::Horizontal)
+                .direction(direction)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
                 .split(f.size());
 
             [x[0], x[1]]
         };
 
-        let list_block = Block::default()
-            .borders(Borders::ALL)
-            .title(format!(" {} ", self.file_path.as_str()))
+        let mut title = format!(" {} ", self.file_path);
+        if self.sort != Sort::File {
+            let _ = write!(title, "(by {}) ", self.sort.label());
+        }
+        if self.group != Group::None {
+            let _ = write!(title, "[{}] ", self.group.label());
+        }
+        if self.only_favorites {
+            title.push_str("★ ");
+        }
+
+        // Without borders, the focus is told by the title
+        let panel_block = |focused: bool| {
+            if focused && components::plain() {
+                components::block().title("[FOCUS]")
+            } else {
+                components::block()
+            }
+        };
+
+        let list_block = panel_block(matches!(self.focus, FocusState::RequestsList))
+            .title(title)
             .border_style(list_border_style);
 
-        let response_block = Block::default()
-            .borders(Borders::ALL)
+        let response_block = panel_block(matches!(self.focus, FocusState::ResponsePanel))
             .border_style(response_border_style);
 
         if self.vars_visible {
@@ -204,8 +1148,7 @@ impl App {
 
             list_chunk = new_list_chunk;
 
-            let var_block = Block::default()
-                .borders(Borders::ALL)
+            let var_block = panel_block(matches!(self.focus, FocusState::VarsPanel))
                 .border_style(vars_border_style);
 
             self.vars_panel.render(f, var_chunk, var_block);
@@ -215,15 +1158,263 @@ impl App {
         let response_panel = &self.responses[self.request_menu.idx()];
         response_panel.render(f, response_chunk, response_block);
 
-        if let Some(popup) = self.popups.front() {
-            popup.render(f, f.size(), Block::default().borders(Borders::ALL));
+        // Bottom to top, the ones below staying visible around
+        for popup in &self.popups {
+            popup.render(f, f.size(), components::block());
+        }
+
+        // Saves in progress below the toasts
+        let saves = self
+            .saves
+            .iter()
+            .map(|(file_name, written, total)| {
+                Message::Info(format!(
+                    "Saving {file_name}: {}%",
+                    written * 100 / (*total).max(1)
+                ))
+            })
+            .collect::<Vec<_>>();
+        let toasts = self
+            .toasts
+            .iter()
+            .map(|(_, toast)| toast)
+            .chain(&saves)
+            .collect::<Vec<_>>();
+        messages::render_toasts(f, f.size(), &toasts);
+    }
+
+    // Errors wait for a key press, info and warnings go away by themselves
+    fn show_message(&mut self, message: Message) {
+        if !matches!(message, Message::Custom(..)) {
+            if self.messages.len() == MAX_MESSAGES {
+                self.messages.remove(0);
+            }
+            self.messages.push(LoggedMessage {
+                time: Local::now().format("%H:%M:%S").to_string(),
+                message: message.clone(),
+            });
+        }
+
+        match message {
+            Message::Info(_) | Message::Warning(_) => {
+                let id = self.next_toast;
+                self.next_toast += 1;
+                self.toasts.push((id, message));
+                self.events.spawn(async move {
+                    tokio::time::sleep(TOAST_DURATION).await;
+                    Ok(Event::DismissToast(id))
+                });
+            }
+            Message::Error(_) | Message::Custom(..) => self
+                .popups
+                .push(Box::new(Popup::new(MessageDialog::new(message)))),
+        }
+    }
+
+    // Queues request `idx`, along with the rest of its `@depends` chain, until
+    // `url` can be reached again
+    fn enqueue(&mut self, idx: usize, url: String) {
+        let time = Local::now().format("%H:%M:%S").to_string();
+        for i in std::iter::once(idx).chain(self.pending.iter().copied()) {
+            if self.queue.iter().any(|queued| queued.idx == i) {
+                continue;
+            }
+            let template = self.request_menu.get(i);
+            self.queue.push(Queued {
+                idx: i,
+                key: favorites::key(template),
+                method: template.method.to_string(),
+                url: template.url.to_string(),
+                time: time.clone(),
+            });
+        }
+
+        self.show_message(Message::Warning(format!(
+            "{url} can't be reached, {} request(s) queued until it can, see :queue",
+            self.queue.len()
+        )));
+
+        if !self.probing {
+            self.probing = true;
+            self.events.spawn(async move {
+                queue::wait_reachable(url).await;
+                Ok(Event::Reachable)
+            });
+        }
+    }
+
+    // Offers to send request `idx` again, after a countdown if enabled and
+    // `auto`, the prompt being ticked every second to show it
+    fn prompt_retry(&mut self, idx: usize, error: String, auto: bool) {
+        let id = self.next_retry;
+        self.next_retry += 1;
+        let after =
+            retry_dialog::auto_retry().filter(|_| auto && self.auto_retries < MAX_AUTO_RETRIES);
+
+        self.retry = Some(Retry {
+            id,
+            idx,
+            deadline: after.map(|after| Instant::now() + after),
+        });
+        self.popups
+            .push(Box::new(Popup::new(RetryDialog::new(id, error, after))));
+
+        if let Some(after) = after {
+            let ticks = self.events.clone();
+            self.events.spawn(async move {
+                for _ in 1..after.as_secs() {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    ticks.emit(Event::RetryTick(id));
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(Event::RetryTick(id))
+            });
+        }
+    }
+
+    /// Waits for the next response from the request handler.
+    pub async fn next_response(&mut self) -> Option<(RequestResult, usize)> {
+        self.res_rx.recv().await
+    }
+
+    pub async fn on_response(&mut self, (res, i): (RequestResult, usize)) {
+        let res = match res {
+            Ok(res) => res,
+            Err((e, failure)) => {
+                tracing::warn!(idx = i, error = e, "request failed");
+                self.responses[i].set_failed();
+                match failure {
+                    Failure::Offline(url) => self.enqueue(i, url),
+                    Failure::Transient(auto) => self.prompt_retry(i, e, auto),
+                    Failure::Other => self.events.emit(Event::Message(Message::Error(e))),
+                }
+                self.pending.clear();
+                return;
+            }
+        };
+        self.auto_retries = 0;
+
+        for capture in &self.request_menu.get(i).captures {
+            match capture.extract(&res) {
+                Ok(value) => self
+                    .vars_panel
+                    .update(capture.name.clone(), TemplateString::raw(&value)),
+                Err(e) => self
+                    .events
+                    .emit(Event::Message(Message::Error(e.to_string()))),
+            }
+        }
+
+        if Some(i) == self.auth_idx {
+            let auth = self.request_menu.get(i).auth.as_ref().unwrap();
+            // Too far in the future to be represented, as never
+            self.session_expiry = auth
+                .expires_in(&res)
+                .and_then(|d| Instant::now().checked_add(d));
+        } else if let (Some(auth_idx), StatusCode::UNAUTHORIZED, false) =
+            (self.auth_idx, res.status, self.retried)
+        {
+            // Refresh the session and retry once
+            self.retried = true;
+            self.pending.push_front(i);
+            self.pending.push_front(auth_idx);
+        }
+
+        let warnings = res.protocol_warnings(self.request_menu.get(i).version);
+        self.responses[i].set_warnings(warnings);
+        self.responses[i].set_response(res);
+        if self.sort == Sort::Status {
+            self.arrange();
         }
+
+        if let Err(e) = self.send_next().await {
+            self.pending.clear();
+            self.events
+                .emit(Event::Message(Message::Error(e.to_string())));
+        }
+    }
+
+    // Whether the response of `idx` can be reused as a dependency
+    fn is_fresh(&self, idx: usize) -> bool {
+        let session_expired = Some(idx) == self.auth_idx
+            && self
+                .session_expiry
+                .is_some_and(|expiry| Instant::now() >= expiry);
+
+        self.responses[idx].has_response() && !session_expired
+    }
+
+    async fn send_next(&mut self) -> anyhow::Result<()> {
+        let Some(idx) = self.pending.pop_front() else {
+            return Ok(());
+        };
+
+        tracing::debug!(idx, pending = self.pending.len(), "sending request");
+
+        let template = self.request_menu.get(idx);
+        let mut missing = template
+            .missing_variables(self.vars_panel.vars(), &())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let filled = template.fill(self.vars_panel.vars());
+        self.request_menu.get_mut(idx).fill_error = filled.as_ref().err().cloned();
+        if !missing.is_empty() {
+            missing.sort();
+            let mut message = format!("undefined variables: {}", missing.join(", "));
+            if let Err(e) = &filled {
+                let _ = write!(message, "\n{e}");
+            }
+            return Err(anyhow!(message));
+        }
+        let mut request = filled?;
+        let template = self.request_menu.get(idx);
+        workspace::apply_host_rules(&self.hosts, &mut request, self.vars_panel.vars())?;
+        if let Some(request_id) = &self.request_id {
+            let value = request_id.apply(&mut request, self.vars_panel.vars())?;
+            self.responses[idx].set_request_id(request_id.header.to_string(), value);
+        }
+
+        let oauth2 = match &template.oauth2 {
+            Some(name) => {
+                let config = self
+                    .oauth2
+                    .get(name)
+                    .ok_or_else(|| anyhow!("oauth2 configuration '{name}' not found"))?
+                    .fill(self.vars_panel.vars())?;
+                let key = format!("{}/{name}", self.environment.as_deref().unwrap_or_default());
+
+                Some((key, config))
+            }
+            None => None,
+        };
+
+        let key = favorites::key(template);
+        self.responses[idx].set_loading();
+        self.req_tx
+            .send(Outgoing {
+                request,
+                idx,
+                key,
+                oauth2,
+            })
+            .await
+            .map_err(|e| anyhow!(e))
     }
 
-    pub fn update(&mut self) {
-        // Poll for request responses
-        if let Ok((res, i)) = self.res_rx.try_recv() {
-            self.responses[i].set_response(res);
+    // Exits, asking first if requests are still to be answered
+    fn quit(&mut self) {
+        // Sent or waiting to be, a request being both when sent again
+        let in_flight = (0..self.responses.len())
+            .filter(|i| self.responses[*i].is_loading() || self.pending.contains(i))
+            .count();
+
+        if in_flight == 0 {
+            self.should_exit = true;
+        } else {
+            self.popups.push(Box::new(Popup::new(ConfirmDialog::new(
+                format!("{in_flight} request(s) still in flight, exit anyway?"),
+                Event::Exit,
+            ))));
         }
     }
 
@@ -237,52 +1428,253 @@ impl App {
                 self.focus = e;
                 Ok(())
             }
+            Event::Exit => {
+                self.should_exit = true;
+                Ok(())
+            }
             Event::Key(e) => self.on_key_event(e).await,
-            Event::Other(_) => Ok(()),
-            Event::Save((file_name, option)) => match option {
-                crate::components::response_panel::SaveOption::All => {
-                    self.responses[self.request_menu.idx()].save_all(&file_name)
+            Event::Save((file_name, option)) => {
+                let panel = &self.responses[self.request_menu.idx()];
+                match option {
+                    crate::components::response_panel::SaveOption::All => {
+                        panel.save_all(file_name, self.redaction.as_ref(), &self.events)
+                    }
+                    crate::components::response_panel::SaveOption::Body => {
+                        panel.save_body(file_name, &self.events)
+                    }
                 }
-                crate::components::response_panel::SaveOption::Body => {
-                    self.responses[self.request_menu.idx()].save_body(&file_name)
+            }
+            Event::SaveProgress((file_name, written, total)) => {
+                match self.saves.iter_mut().find(|(name, ..)| *name == file_name) {
+                    Some(save) => *save = (file_name, written, total),
+                    None => self.saves.push((file_name, written, total)),
                 }
-            },
+                Ok(())
+            }
+            Event::SaveDone((file_name, result)) => {
+                self.saves.retain(|(name, ..)| *name != file_name);
+                match result {
+                    Ok(size) => {
+                        let size = response_panel::human_size(size as usize);
+                        self.show_message(Message::Info(format!(
+                            "Saved {size} to {file_name}, :open or :reveal it"
+                        )));
+                        self.last_saved = Some(PathBuf::from(file_name));
+                        Ok(())
+                    }
+                    Err(e) => Err(anyhow!("saving {file_name}: {e}")),
+                }
+            }
+            Event::OpenFile(path) => browser::open_file(&path, self.openers.file.as_deref())
+                .with_context(|| format!("opening {}", path.display())),
             Event::NewInput(builder) => {
-                self.popups.push_back(Box::new(Popup::new(builder.build())));
+                let builder =
+                    builder.with_variables(self.vars_panel.vars().keys().cloned().collect());
+                self.popups.push(Box::new(Popup::new(builder.build())));
                 Ok(())
             }
             Event::PopupDismiss | Event::InputConfirm | Event::InputCancel => {
-                self.popups.pop_front();
+                self.popups.pop();
                 Ok(())
             }
             Event::SendRequest(idx) => {
-                self.responses[idx].set_loading();
+                match scenario::execution_order(self.request_menu.items(), idx) {
+                    Ok(order) => {
+                        // Dependencies already sent are reused
+                        self.pending = order
+                            .into_iter()
+                            .filter(|&i| i == idx || !self.is_fresh(i))
+                            .collect();
+                        self.retried = false;
 
-                match self.request_menu.get(idx).fill(self.vars_panel.vars()) {
-                    Ok(request) => self
-                        .req_tx
-                        .send((request, idx))
-                        .await
-                        .map_err(|e| anyhow!(e)),
+                        if let Some(auth_idx) = self.auth_idx {
+                            if !self.is_fresh(auth_idx) && !self.pending.contains(&auth_idx) {
+                                self.pending.push_front(auth_idx);
+                            }
+                        }
 
+                        self.send_next().await.inspect_err(|_| self.pending.clear())
+                    }
                     Err(e) => Err(anyhow!(e)),
                 }
             }
             Event::Message(message) => {
-                self.popups
-                    .push_back(Box::new(Popup::new(MessageDialog::new(message))));
+                self.show_message(message);
+                Ok(())
+            }
+            Event::DismissToast(id) => {
+                self.toasts.retain(|(i, _)| *i != id);
+                Ok(())
+            }
+            Event::Reachable => {
+                self.probing = false;
+                if self.queue.is_empty() {
+                    Ok(())
+                } else {
+                    self.show_message(Message::Info(format!(
+                        "Reachable again, sending {} queued request(s)",
+                        self.queue.len()
+                    )));
+                    for queued in std::mem::take(&mut self.queue) {
+                        if !self.pending.contains(&queued.idx) {
+                            self.pending.push_back(queued.idx);
+                        }
+                    }
+                    // Or they're sent after the one in flight
+                    if self.responses.iter().any(ResponsePanel::is_loading) {
+                        Ok(())
+                    } else {
+                        self.send_next().await.inspect_err(|_| self.pending.clear())
+                    }
+                }
+            }
+            Event::Unqueue(idx) => {
+                self.queue.retain(|queued| queued.idx != idx);
+                Ok(())
+            }
+            Event::Retry((id, again)) => {
+                match self.retry.take() {
+                    Some(retry) if retry.id == id => {
+                        if again {
+                            self.auto_retries = 0;
+                            self.events.emit(Event::SendRequest(retry.idx));
+                        }
+                    }
+                    // Of an older prompt, replaced by the one of a later failure
+                    other => self.retry = other,
+                }
+                Ok(())
+            }
+            // Redraws the countdown, sending the request once it's over
+            Event::RetryTick(id) => {
+                let due = self.retry.as_ref().is_some_and(|retry| {
+                    retry.id == id
+                        && retry
+                            .deadline
+                            .is_some_and(|deadline| Instant::now() >= deadline)
+                });
+                if let (true, Some(retry)) = (due, self.retry.take()) {
+                    // Its prompt, taking the keys until then
+                    self.popups.pop();
+                    self.auto_retries += 1;
+                    self.events.emit(Event::SendRequest(retry.idx));
+                }
                 Ok(())
             }
             Event::UpdateVar((name, value)) => match value.parse() {
+                Ok(parsed) => {
+                    self.vars_panel.update(name.clone(), parsed);
+                    self.overrides.insert(name, value);
+                    Ok(())
+                }
+                Err(e) => Err(anyhow!(e)),
+            },
+            Event::SetEncoding(label) => {
+                self.responses[self.request_menu.idx()].set_encoding(&label)
+            }
+            Event::Search(query) => self.responses[self.request_menu.idx()].search(query),
+            Event::Command(line) => self.run_command(&line),
+            Event::ShowHistory(entries) => {
+                self.popups.push(Box::new(
+                    Popup::new(HistoryPanel::new(entries)).with_size(80, 60),
+                ));
+                Ok(())
+            }
+            Event::Resend(entry) => self.resend(&entry),
+            Event::ShowLinks(links) => {
+                self.popups.push(Box::new(
+                    Popup::new(LinksPanel::new(links)).with_size(80, 60),
+                ));
+                Ok(())
+            }
+            Event::Upload((idx, sent, total)) => {
+                self.responses[idx].set_upload(sent, total);
+                Ok(())
+            }
+            Event::ShowDecoded(tokens) => {
+                self.popups.push(Box::new(
+                    Popup::new(DecodedPanel::new(tokens)).with_size(80, 60),
+                ));
+                Ok(())
+            }
+            Event::NewRequest((url, persist)) => self.add_request(&url, persist),
+            Event::NewSnippet((snippet, persist)) => self.add_snippet(&snippet, persist),
+            Event::ToggleHeader((idx, header)) => self.toggle_header(idx, header),
+            Event::ToggleQuery((idx, param)) => {
+                self.request_menu.get_mut(idx).toggle_query(param);
+                Ok(())
+            }
+            Event::SetQueryValue((idx, param, value)) => match value.parse() {
                 Ok(value) => {
-                    self.vars_panel.update(name, value);
+                    let request = self.request_menu.get_mut(idx);
+                    request.set_query_value(param, value);
+
+                    let panel =
+                        QueryPanel::new(idx, request, self.vars_panel.vars()).with_selected(param);
+                    self.popups
+                        .push(Box::new(Popup::new(panel).with_size(70, 50)));
+                    Ok(())
+                }
+                Err(e) => Err(anyhow!(e)),
+            },
+            Event::SetBody((idx, body)) => match parse_body(&body) {
+                Ok(body) => {
+                    self.request_menu.get_mut(idx).body = body;
                     Ok(())
                 }
                 Err(e) => Err(anyhow!(e)),
             },
         };
         if let Err(e) = result {
-            Event::emit(Event::Message(Message::Error(e.to_string())));
+            tracing::warn!(error = %e, "event failed");
+            self.events
+                .emit(Event::Message(Message::Error(e.to_string())));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::toggle_header_line;
+
+    const CONTENT: &str = "\
+GET https://test.dev/search
+    ?q=1
+    &accept: x
+Accept: text/html
+X-Id: 1
+accept: application/json
+
+Accept: in the body
+";
+
+    #[test]
+    fn test_toggle_duplicate_header() {
+        assert_eq!(
+            toggle_header_line(CONTENT, 1, "Accept", 1, false).unwrap(),
+            CONTENT.replace("accept: application/json", "# accept: application/json")
+        );
+        assert_eq!(
+            toggle_header_line(CONTENT, 1, "accept", 0, false).unwrap(),
+            CONTENT.replace("Accept: text/html", "# Accept: text/html")
+        );
+        // Neither the query nor the body
+        assert!(toggle_header_line(CONTENT, 1, "Accept", 2, false).is_none());
+    }
+
+    #[test]
+    fn test_enable_header() {
+        let content = "GET https://test.dev\n# Accept: a\n#Accept: b\nX-Id: 1\n";
+
+        assert_eq!(
+            toggle_header_line(content, 1, "Accept", 0, true).unwrap(),
+            "GET https://test.dev\nAccept: a\n#Accept: b\nX-Id: 1\n"
+        );
+        assert_eq!(
+            toggle_header_line(content, 1, "Accept", 1, true).unwrap(),
+            "GET https://test.dev\n# Accept: a\nAccept: b\nX-Id: 1\n"
+        );
+        // Not commented out
+        assert!(toggle_header_line(content, 1, "X-Id", 0, true).is_none());
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    ffi::OsStr,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+#[cfg(target_os = "macos")]
+const OPENER: &[&str] = &["open"];
+#[cfg(windows)]
+const OPENER: &[&str] = &["cmd", "/C", "start", ""];
+#[cfg(not(any(target_os = "macos", windows)))]
+const OPENER: &[&str] = &["xdg-open"];
+
+/// Opens `url` with the system default handler, without waiting for it.
+pub fn open(url: &str) -> std::io::Result<()> {
+    spawn(OPENER, url)
+}
+
+/// Opens the file at `path` with `command`, or else the system default
+/// application.
+pub fn open_file(path: &Path, command: Option<&[String]>) -> std::io::Result<()> {
+    match command {
+        Some(command) => spawn(command, path),
+        None => spawn(OPENER, path),
+    }
+}
+
+/// Shows the file at `path` in the file manager with `command`, or else the
+/// system one, selected where it supports it.
+pub fn reveal(path: &Path, command: Option<&[String]>) -> std::io::Result<()> {
+    match command {
+        Some(command) => spawn(command, path),
+        None => reveal_default(path),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn reveal_default(path: &Path) -> std::io::Result<()> {
+    spawn(&["open", "-R"], path)
+}
+
+#[cfg(windows)]
+fn reveal_default(path: &Path) -> std::io::Result<()> {
+    // Taken as a single argument, without a space after the comma
+    let mut select = std::ffi::OsString::from("/select,");
+    select.push(path);
+    spawn(&["explorer"], select)
+}
+
+// No common way to select a file, its directory is opened instead
+#[cfg(not(any(target_os = "macos", windows)))]
+fn reveal_default(path: &Path) -> std::io::Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    spawn(OPENER, dir)
+}
+
+// Runs `command` with `arg` appended, without waiting for it
+fn spawn(command: &[impl AsRef<OsStr>], arg: impl AsRef<OsStr>) -> std::io::Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| std::io::Error::other("empty command"))?;
+
+    Command::new(program)
+        .args(args)
+        .arg(arg)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use std::cell::Cell;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::event::{Event, EventSender};
+
+use super::{BlockComponent, HandleResult, HandleSuccess};
+
+mod json;
+
+/// Multi-line editor of a request body, validating it live when it's JSON.
+pub struct BodyEditor {
+    // Index of the request
+    idx: usize,
+    lines: Vec<String>,
+    // Line and char index of the cursor
+    row: usize,
+    col: usize,
+    // First visible line
+    scroll: Cell<usize>,
+    json: bool,
+    // Line, column and message
+    error: Option<(usize, usize, String)>,
+}
+
+impl BodyEditor {
+    pub fn new(idx: usize, body: &str, json: bool) -> Self {
+        let mut editor = Self {
+            idx,
+            lines: body.lines().map(str::to_owned).collect(),
+            row: 0,
+            col: 0,
+            scroll: Cell::new(0),
+            json,
+            error: None,
+        };
+        if editor.lines.is_empty() {
+            editor.lines.push(String::new());
+        }
+        editor.validate();
+
+        editor
+    }
+
+    fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn validate(&mut self) {
+        if self.json {
+            self.error = json::validate(&self.text());
+        }
+    }
+
+    // Byte offset of the cursor in its line
+    fn offset(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.char_indices()
+            .nth(self.col)
+            .map_or(line.len(), |(i, _)| i)
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].chars().count()
+    }
+
+    fn insert(&mut self, c: char) {
+        let offset = self.offset();
+        self.lines[self.row].insert(offset, c);
+        self.col += 1;
+    }
+
+    fn newline(&mut self) {
+        let offset = self.offset();
+        let rest = self.lines[self.row].split_off(offset);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, rest);
+    }
+
+    fn backspace(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+            let offset = self.offset();
+            self.lines[self.row].remove(offset);
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let offset = self.offset();
+            self.lines[self.row].remove(offset);
+        } else if self.row + 1 < self.lines.len() {
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    fn format(&mut self) {
+        if self.error.is_some() {
+            return;
+        }
+
+        self.lines = json::format(&self.text())
+            .lines()
+            .map(str::to_owned)
+            .collect();
+        self.row = self.row.min(self.lines.len() - 1);
+        self.col = self.col.min(self.line_len());
+    }
+}
+
+impl BlockComponent for BodyEditor {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("Ctrl-s", "apply"),
+            ("Ctrl-f", "format JSON"),
+            ("Esc", "cancel"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key_event.code {
+            KeyCode::Char('s') if ctrl => {
+                events.emit(Event::PopupDismiss);
+                events.emit(Event::SetBody((self.idx, self.text())));
+                return Ok(HandleSuccess::Consumed);
+            }
+            KeyCode::Char('f') if ctrl && self.json => self.format(),
+            KeyCode::Esc => {
+                events.emit(Event::PopupDismiss);
+                return Ok(HandleSuccess::Consumed);
+            }
+            KeyCode::Char(c) if !ctrl => self.insert(c),
+            KeyCode::Tab => {
+                self.insert(' ');
+                self.insert(' ');
+            }
+            KeyCode::Enter => self.newline(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if self.col > 0 => self.col -= 1,
+            KeyCode::Right if self.col < self.line_len() => self.col += 1,
+            KeyCode::Up if self.row > 0 => {
+                self.row -= 1;
+                self.col = self.col.min(self.line_len());
+            }
+            KeyCode::Down if self.row + 1 < self.lines.len() => {
+                self.row += 1;
+                self.col = self.col.min(self.line_len());
+            }
+            KeyCode::Home => self.col = 0,
+            KeyCode::End => self.col = self.line_len(),
+            _ => return Ok(HandleSuccess::Consumed),
+        }
+
+        self.validate();
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let height = block.inner(area).height as usize;
+
+        // Keeps the cursor in view
+        let mut scroll = self.scroll.get();
+        if self.row < scroll {
+            scroll = self.row;
+        } else if height > 0 && self.row >= scroll + height {
+            scroll = self.row + 1 - height;
+        }
+        self.scroll.set(scroll);
+
+        let lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(height)
+            .map(|(i, line)| match &self.error {
+                // The offending character is highlighted
+                Some((row, col, _)) if *row == i => {
+                    let start = line.char_indices().nth(*col).map_or(line.len(), |(i, _)| i);
+                    let end = line[start..]
+                        .chars()
+                        .next()
+                        .map_or(start, |c| start + c.len_utf8());
+                    Line::from(vec![
+                        Span::raw(&line[..start]),
+                        Span::styled(
+                            if start == end { " " } else { &line[start..end] },
+                            Style::default().bg(Color::Red),
+                        ),
+                        Span::raw(&line[end..]),
+                    ])
+                }
+                _ => Line::from(line.as_str()),
+            })
+            .collect::<Vec<_>>();
+
+        let (title, color) = match (&self.error, self.json) {
+            (Some((row, col, message)), _) => (
+                format!(" body: {message} at {}:{} ", row + 1, col + 1),
+                Color::Red,
+            ),
+            (None, true) => (
+                " body: valid JSON, Ctrl-f to format ".to_owned(),
+                Color::Green,
+            ),
+            (None, false) => (" body ".to_owned(), Color::Blue),
+        };
+
+        let block = block.title(Span::styled(
+            title,
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+
+        let prefix = self.lines[self.row]
+            .chars()
+            .take(self.col)
+            .collect::<String>();
+        frame.set_cursor(
+            area.x + 1 + prefix.width() as u16,
+            area.y + 1 + (self.row - scroll) as u16,
+        );
+    }
+}
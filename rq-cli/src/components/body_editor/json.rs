@@ -0,0 +1,182 @@
+//! JSON checks of bodies that may contain `{{templates}}`, which are kept
+//! as they are.
+
+// Replaces templates with `0`, padded to their length so that error
+// positions still match the text
+fn mask_templates(text: &str) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}").map(|end| end + 2) else {
+            break;
+        };
+        masked.push_str(&rest[..start]);
+        masked.push('0');
+        masked.extend(std::iter::repeat_n(
+            ' ',
+            rest[start..start + len].chars().count() - 1,
+        ));
+        rest = &rest[start + len..];
+    }
+
+    masked.push_str(rest);
+    masked
+}
+
+/// Error in `text` as (line, column), both starting from 0, and message.
+pub fn validate(text: &str) -> Option<(usize, usize, String)> {
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let masked = mask_templates(text);
+    serde_json::from_str::<serde_json::Value>(&masked)
+        .err()
+        .map(|e| {
+            let message = e.to_string();
+            // i.e. "expected `:` at line 2 column 5"
+            let message = message
+                .split(" at line ")
+                .next()
+                .unwrap_or_default()
+                .to_owned();
+
+            // The column counts bytes, the masked text keeping the chars in place
+            let line = e.line().saturating_sub(1);
+            let byte = e.column().saturating_sub(1);
+            let column = masked.lines().nth(line).map_or(byte, |text| {
+                text.char_indices().take_while(|(i, _)| *i < byte).count()
+            });
+            (line, column, message)
+        })
+}
+
+/// Indents `text` by two spaces per level. Only meant for valid JSON.
+pub fn format(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        // Templates are copied whole, even if they contain spaces
+        if c == '{' && chars.peek() == Some(&'{') {
+            out.push(c);
+            for c in chars.by_ref() {
+                out.push(c);
+                if out.ends_with("}}") {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    out.extend(chars.next());
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => (),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format, mask_templates, validate};
+
+    #[test]
+    fn test_mask_templates() {
+        assert_eq!(mask_templates(r#"{"id": {{id}}}"#), r#"{"id": 0     }"#);
+        assert_eq!(
+            mask_templates(r#"{"name": "{{first}} {{last}}"}"#),
+            r#"{"name": "0         0       "}"#
+        );
+        // Padded by chars, not bytes
+        assert_eq!(mask_templates("{{é}}x"), "0    x");
+        // Unterminated ones are left as they are
+        assert_eq!(mask_templates("[{{id"), "[{{id");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("  "), None);
+        assert_eq!(validate(r#"{"id": {{id}}, "tags": ["{{tag}}"]}"#), None);
+        assert_eq!(
+            validate(r#"{"a": {"b": [1, {{n}}, {"c": "{{c}}"}]}}"#),
+            None
+        );
+
+        let (line, column, message) = validate("{\n  \"id\" 1\n}").unwrap();
+        assert_eq!((line, column), (1, 7));
+        assert_eq!(message, "expected `:`");
+    }
+
+    #[test]
+    fn test_validate_non_ascii() {
+        // Columns are chars, the `x` being the 7th
+        let (line, column, _) = validate(r#"{"é": x}"#).unwrap();
+        assert_eq!((line, column), (0, 6));
+
+        let (line, column, _) = validate("{\"{{ñandú}}\": 1, 2}").unwrap();
+        assert_eq!((line, column), (0, 17));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format(r#"{"a":1,"b":[true, {}],"c":{"d":null}}"#),
+            "{\n  \"a\": 1,\n  \"b\": [\n    true,\n    {}\n  ],\n  \"c\": {\n    \"d\": null\n  }\n}"
+        );
+        // Strings and templates are kept whole
+        assert_eq!(
+            format(r#"{"s":"a, {b}: [c]","t":{{ id }}}"#),
+            "{\n  \"s\": \"a, {b}: [c]\",\n  \"t\": {{ id }}\n}"
+        );
+        assert_eq!(
+            format(r#"["{{a b}}", "\"é\""]"#),
+            "[\n  \"{{a b}}\",\n  \"\\\"é\\\"\"\n]"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    style::{Color, Style},
+    widgets::{Paragraph, Wrap},
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::event::{Event, EventSender};
+
+use super::{popup, BlockComponent, HandleResult, HandleSuccess};
+
+/// Yes/no question, emitting `event` when confirmed.
+pub struct ConfirmDialog {
+    question: String,
+    event: Option<Event>,
+}
+
+impl ConfirmDialog {
+    pub fn new(question: String, event: Event) -> Self {
+        Self {
+            question,
+            event: Some(event),
+        }
+    }
+
+    fn text(&self) -> String {
+        format!("{}\n\n[y]es / [n]o", self.question)
+    }
+}
+
+impl BlockComponent for ConfirmDialog {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [("y/Enter", "confirm"), ("any", "cancel")].as_slice()
+    }
+
+    fn content_size(&self, max_width: u16) -> Option<(u16, u16)> {
+        let text = self.text();
+        let width = text.lines().map(|line| line.width()).max().unwrap_or(0);
+        let width = (width as u16).min(max_width);
+
+        Some((width, popup::wrapped_height(&text, width)))
+    }
+
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> HandleResult {
+        events.emit(Event::PopupDismiss);
+
+        if let KeyCode::Char('y' | 'Y') | KeyCode::Enter = key_event.code {
+            if let Some(event) = self.event.take() {
+                events.emit(event);
+            }
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let p = Paragraph::new(self.text())
+            .block(
+                block
+                    .border_style(Style::default().fg(Color::Yellow))
+                    .title(" confirm "),
+            )
+            .wrap(Wrap::default());
+
+        frame.render_widget(p, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use crate::{
+        components::BlockComponent,
+        event::{channel, Event},
+    };
+
+    use super::ConfirmDialog;
+
+    #[test]
+    fn test_confirm() {
+        let (events, mut rx) = channel();
+        let mut dialog = ConfirmDialog::new("exit?".into(), Event::Exit);
+
+        dialog
+            .on_event(KeyEvent::from(KeyCode::Char('y')), &events)
+            .unwrap();
+        assert!(matches!(rx.try_recv(), Ok(Event::PopupDismiss)));
+        assert!(matches!(rx.try_recv(), Ok(Event::Exit)));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let (events, mut rx) = channel();
+        let mut dialog = ConfirmDialog::new("exit?".into(), Event::Exit);
+
+        dialog
+            .on_event(KeyEvent::from(KeyCode::Char('n')), &events)
+            .unwrap();
+        assert!(matches!(rx.try_recv(), Ok(Event::PopupDismiss)));
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+};
+use rq_core::request::token::DecodedToken;
+
+use crate::event::{Event, EventSender};
+
+use super::{
+    menu::{Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+// Tokens longer than this are shortened in the list
+const TOKEN_WIDTH: usize = 48;
+
+impl MenuItem for DecodedToken {
+    fn render(&self) -> Vec<Line<'_>> {
+        let token = match self.token.char_indices().nth(TOKEN_WIDTH) {
+            Some((i, _)) => format!("{}…", &self.token[..i]),
+            None => self.token.clone(),
+        };
+
+        vec![Line::from(vec![
+            Span::styled(
+                format!("{:<12}", self.kind),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::raw(token),
+        ])]
+    }
+}
+
+/// Tokens of the current response that could be decoded, i.e. JWTs, with the
+/// decoding of the selected one.
+pub struct DecodedPanel {
+    menu: Menu<DecodedToken>,
+}
+
+impl DecodedPanel {
+    pub fn new(tokens: Vec<DecodedToken>) -> Self {
+        Self {
+            menu: Menu::new(tokens),
+        }
+    }
+}
+
+impl BlockComponent for DecodedPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [("↓/↑ j/k", "next/previous"), ("Esc", "close")].as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: Block,
+    ) {
+        let [list_area, text_area] = {
+            let x = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(area);
+
+            [x[0], x[1]]
+        };
+
+        let title = format!(" decoded ({}) ", self.menu.items().len());
+        self.menu.render(frame, list_area, block.title(title));
+
+        let text = Paragraph::new(self.menu.selected().text.as_str())
+            .block(super::block().title(format!(" {} ", self.menu.selected().kind)))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(text, text_area);
+    }
+}
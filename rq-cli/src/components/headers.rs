@@ -0,0 +1,106 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use rq_core::parser::TemplateRequest;
+
+use crate::event::{Event, EventSender};
+
+use super::{
+    menu::{Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+pub struct HeaderItem {
+    name: String,
+    value: String,
+    enabled: bool,
+}
+
+impl MenuItem for HeaderItem {
+    fn render(&self) -> Vec<Line<'_>> {
+        let line = if self.enabled {
+            Line::from(vec![
+                Span::raw("[x] "),
+                Span::styled(self.name.as_str(), Style::default().fg(Color::Blue)),
+                Span::raw(": "),
+                Span::raw(self.value.as_str()),
+            ])
+        } else {
+            Line::styled(
+                format!("[ ] # {}: {}", self.name, self.value),
+                Style::default().fg(Color::DarkGray),
+            )
+        };
+
+        vec![line]
+    }
+}
+
+/// Headers of a request, to comment them out or back in without editing the
+/// file by hand.
+pub struct HeadersPanel {
+    // Index of the request
+    idx: usize,
+    menu: Menu<HeaderItem>,
+}
+
+impl HeadersPanel {
+    pub fn new(idx: usize, request: &TemplateRequest) -> Self {
+        let items = request
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(i, (name, value))| HeaderItem {
+                name: name.clone(),
+                value: value.to_string(),
+                enabled: !request.disabled_headers.contains(&i),
+            })
+            .collect();
+
+        Self {
+            idx,
+            menu: Menu::new(items),
+        }
+    }
+}
+
+impl BlockComponent for HeadersPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("↓/↑ j/k", "next/previous"),
+            ("Space/Enter", "enable/disable"),
+            ("Esc", "close"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Char(' ') | KeyCode::Enter if !self.menu.items().is_empty() => {
+                let header = self.menu.idx();
+                let item = self.menu.get_mut(header);
+                item.enabled = !item.enabled;
+                events.emit(Event::ToggleHeader((self.idx, header)));
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = format!(" headers ({}) ", self.menu.items().len());
+        self.menu.render(frame, area, block.title(title));
+    }
+}
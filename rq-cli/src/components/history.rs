@@ -0,0 +1,196 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+use crate::{
+    event::{Event, EventSender},
+    history::Entry,
+};
+
+use super::{
+    menu::{self, Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+impl MenuItem for Entry {
+    fn render(&self) -> Vec<Line<'_>> {
+        let (status, color) = match (self.status, &self.error) {
+            (Some(status), _) => (
+                status.to_string(),
+                match status {
+                    200..=299 => Color::Green,
+                    300..=399 => Color::Yellow,
+                    _ => Color::Red,
+                },
+            ),
+            (None, _) => ("ERR".into(), Color::Red),
+        };
+
+        vec![Line::from(vec![
+            Span::styled(
+                self.time.get(..19).unwrap_or(&self.time).replace('T', " "),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::raw(" "),
+            Span::styled(status, Style::default().fg(color)),
+            Span::raw(" "),
+            Span::styled(self.method.as_str(), Style::default().fg(Color::Green)),
+            Span::raw(" "),
+            Span::raw(self.url.as_str()),
+            Span::styled(
+                format!(" {:?}", self.duration()),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])]
+    }
+}
+
+/// Browser of the requests sent, filtered by typing part of the url, status
+/// or time.
+pub struct HistoryPanel {
+    menu: Menu<Entry>,
+    filter: String,
+    // Entries matching the filter
+    matches: usize,
+}
+
+impl HistoryPanel {
+    pub fn new(entries: Vec<Entry>) -> Self {
+        let matches = entries.len();
+
+        Self {
+            menu: Menu::new(entries),
+            filter: String::new(),
+            matches,
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let layout = self
+            .menu
+            .items()
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                [
+                    entry.url.to_lowercase(),
+                    entry.method.to_lowercase(),
+                    entry.time.clone(),
+                    entry.status.map(|s| s.to_string()).unwrap_or_default(),
+                ]
+                .iter()
+                .any(|field| field.contains(&filter))
+            })
+            .map(|(i, _)| menu::Entry::Item(i))
+            .collect::<Vec<_>>();
+
+        self.matches = layout.len();
+        self.menu.arrange(layout);
+    }
+}
+
+impl BlockComponent for HistoryPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("↓/↑", "next/previous"),
+            ("type", "filter by url, status or time"),
+            ("Enter", "send again"),
+            ("Esc", "close"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down => return self.menu.on_event(key_event, events),
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.apply_filter();
+            }
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.apply_filter();
+            }
+            KeyCode::Enter if self.matches > 0 => {
+                events.emit(Event::PopupDismiss);
+                events.emit(Event::Resend(self.menu.selected().clone()));
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = if self.filter.is_empty() {
+            " history ".to_owned()
+        } else {
+            format!(" history: {} ({}) ", self.filter, self.matches)
+        };
+
+        self.menu.render(frame, area, block.title(title));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Entry, HistoryPanel};
+
+    fn entry(time: &str, method: &str, url: &str, status: Option<u16>) -> Entry {
+        Entry {
+            time: time.into(),
+            workspace: PathBuf::from("/ws"),
+            request: "api.http#0".into(),
+            method: method.into(),
+            url: url.into(),
+            status,
+            error: status.is_none().then(|| "connection refused".into()),
+            duration_ms: 10,
+            request_body: None,
+            response_body: None,
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut panel = HistoryPanel::new(vec![
+            entry(
+                "2024-01-02T10:00:00Z",
+                "GET",
+                "https://test.dev/users",
+                Some(200),
+            ),
+            entry(
+                "2024-01-01T10:00:00Z",
+                "POST",
+                "https://test.dev/Users",
+                Some(404),
+            ),
+            entry("2024-01-01T09:00:00Z", "GET", "https://other.dev/", None),
+        ]);
+
+        let mut filter = |text: &str| {
+            panel.filter = text.into();
+            panel.apply_filter();
+            (panel.matches, panel.menu.selected().url.clone())
+        };
+
+        assert_eq!(filter("users"), (2, "https://test.dev/users".into()));
+        assert_eq!(filter("404"), (1, "https://test.dev/Users".into()));
+        assert_eq!(filter("post"), (1, "https://test.dev/Users".into()));
+        assert_eq!(filter("2024-01-01"), (2, "https://test.dev/Users".into()));
+        assert_eq!(filter("nothing").0, 0);
+        assert_eq!(filter("").0, 3);
+    }
+}
@@ -1,30 +1,35 @@
 use crossterm::event::KeyCode;
 use ratatui::widgets::Paragraph;
+use rq_core::request::header::HeaderName;
 use tui_input::{backend::crossterm::EventHandler, Input};
 
-use crate::event::Event;
+use crate::event::{Event, EventSender};
 
 use super::BlockComponent;
 
 pub mod builder;
 
-type ConfirmCallback = Box<dyn Fn(String)>;
-type CancelCallback = Box<dyn Fn()>;
+type ConfirmCallback = Box<dyn Fn(String, &EventSender)>;
 
+#[derive(Default)]
 pub struct InputComponent {
     input: Input,
     on_confirm_callback: Option<ConfirmCallback>,
-    on_cancel_callback: CancelCallback,
+    // Whole values offered by Tab, i.e. previous ones
+    completions: Vec<String>,
+    // Variable names offered by Tab after `{{`
+    variables: Vec<String>,
+    // Header names offered by Tab for the last word, without other completions
+    headers: &'static [HeaderName],
+    cycle: Option<Cycle>,
 }
 
-impl Default for InputComponent {
-    fn default() -> Self {
-        Self {
-            input: Input::default(),
-            on_confirm_callback: None,
-            on_cancel_callback: Box::new(|| Event::emit(Event::InputCancel)),
-        }
-    }
+// Candidates cycled through by Tab
+struct Cycle {
+    // Char index where the completed text starts
+    start: usize,
+    candidates: Vec<String>,
+    current: usize,
 }
 
 impl InputComponent {
@@ -44,7 +49,7 @@ impl InputComponent {
 
     fn with_confirm_callback<F>(self, confirm_callback: F) -> Self
     where
-        F: Fn(String) + 'static,
+        F: Fn(String, &EventSender) + 'static,
     {
         Self {
             on_confirm_callback: Some(Box::new(confirm_callback)),
@@ -52,23 +57,140 @@ impl InputComponent {
         }
     }
 
-    fn with_cancel_callback<F>(self, cancel_callback: F) -> Self
-    where
-        F: Fn() + 'static,
-    {
+    fn with_completions(self, completions: Vec<String>, variables: Vec<String>) -> Self {
         Self {
-            on_cancel_callback: Box::new(cancel_callback),
+            completions,
+            variables,
             ..self
         }
     }
+
+    fn with_headers(self, headers: &'static [HeaderName]) -> Self {
+        Self { headers, ..self }
+    }
+
+    // Starts cycling through the completions of the text before the cursor
+    fn start_cycle(&self) -> Option<Cycle> {
+        let before = self
+            .input
+            .value()
+            .chars()
+            .take(self.input.cursor())
+            .collect::<String>();
+
+        let (start, candidates) = match before.rfind("{{") {
+            Some(i) if !before[i..].contains("}}") => {
+                let prefix = before[i + 2..].trim_start();
+                let start = before.chars().count() - prefix.chars().count();
+                let candidates = self
+                    .variables
+                    .iter()
+                    .filter(|name| name.starts_with(prefix))
+                    .map(|name| format!("{name}}}}}"))
+                    .collect::<Vec<_>>();
+                (start, candidates)
+            }
+            _ => {
+                let candidates = self
+                    .completions
+                    .iter()
+                    .filter(|value| value.starts_with(&before) && **value != before)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if candidates.is_empty() && !self.headers.is_empty() {
+                    self.header_candidates(&before)
+                } else {
+                    (0, candidates)
+                }
+            }
+        };
+
+        (!candidates.is_empty()).then_some(Cycle {
+            start,
+            candidates,
+            current: 0,
+        })
+    }
+
+    // Header names starting with the last word of `before`, ignoring case
+    fn header_candidates(&self, before: &str) -> (usize, Vec<String>) {
+        let word = before
+            .rsplit(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let start = before.chars().count() - word.chars().count();
+        if word.is_empty() {
+            return (start, Vec::new());
+        }
+
+        let candidates = self
+            .headers
+            .iter()
+            .map(HeaderName::as_str)
+            .filter(|name| name.starts_with(&word) && *name != word)
+            .map(str::to_owned)
+            .collect();
+        (start, candidates)
+    }
+
+    // Replaces the text between the start of the completion and the cursor
+    fn complete(&mut self, forward: bool) {
+        match &mut self.cycle {
+            Some(cycle) if forward => cycle.current = (cycle.current + 1) % cycle.candidates.len(),
+            Some(cycle) => {
+                cycle.current =
+                    (cycle.current + cycle.candidates.len() - 1) % cycle.candidates.len()
+            }
+            None => match self.start_cycle() {
+                Some(cycle) => self.cycle = Some(cycle),
+                None => return,
+            },
+        }
+        let Some(cycle) = &self.cycle else {
+            return;
+        };
+
+        let chars = self.input.value().chars().collect::<Vec<_>>();
+        let candidate = &cycle.candidates[cycle.current];
+        let cursor = cycle.start + candidate.chars().count();
+
+        let mut value = chars[..cycle.start].iter().collect::<String>();
+        value.push_str(candidate);
+        value.extend(&chars[self.input.cursor().min(chars.len())..]);
+
+        self.input = Input::from(value.as_str()).with_cursor(cursor);
+    }
 }
 
 impl BlockComponent for InputComponent {
     fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
-        [("Enter", "confirm"), ("Esc", "cancel")].as_slice()
+        [("Enter", "confirm"), ("Esc", "cancel"), ("Tab", "complete")].as_slice()
+    }
+
+    // A single line, as wide as popups get at least
+    fn content_size(&self, _max_width: u16) -> Option<(u16, u16)> {
+        Some((0, 1))
     }
 
-    fn on_event(&mut self, key_event: crossterm::event::KeyEvent) -> super::HandleResult {
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> super::HandleResult {
+        match key_event.code {
+            KeyCode::Tab => {
+                self.complete(true);
+                return Ok(super::HandleSuccess::Consumed);
+            }
+            KeyCode::BackTab => {
+                self.complete(false);
+                return Ok(super::HandleSuccess::Consumed);
+            }
+            // Typing accepts the current candidate
+            _ => self.cycle = None,
+        }
+
         if self
             .input
             .handle_event(&crossterm::event::Event::Key(key_event))
@@ -80,12 +202,12 @@ impl BlockComponent for InputComponent {
         match key_event.code {
             KeyCode::Enter => {
                 if let Some(callback) = self.on_confirm_callback.as_ref() {
-                    callback(self.input.value().to_string());
+                    callback(self.input.value().to_string(), events);
                     return Ok(super::HandleSuccess::Consumed);
                 }
             }
             KeyCode::Esc => {
-                (self.on_cancel_callback)();
+                events.emit(Event::InputCancel);
                 return Ok(super::HandleSuccess::Consumed);
             }
             _ => (),
@@ -100,15 +222,102 @@ impl BlockComponent for InputComponent {
         area: ratatui::prelude::Rect,
         block: ratatui::widgets::Block,
     ) {
-        let p = Paragraph::new(self.input.value());
-        let scroll = self.input.visual_scroll(area.width as usize);
+        let block = match &self.cycle {
+            Some(cycle) if cycle.candidates.len() > 1 => block.title(format!(
+                " {}/{}: Tab for the next ",
+                cycle.current + 1,
+                cycle.candidates.len()
+            )),
+            _ => block,
+        };
+        // In columns, leaving two for the borders and one for the cursor at the end
+        let scroll = self
+            .input
+            .visual_scroll(area.width.saturating_sub(3) as usize);
+        let p = Paragraph::new(self.input.value()).scroll((0, scroll as u16));
 
         frame.render_widget(p.block(block), area);
         frame.set_cursor(
-            // Put cursor past the end of the input text
-            area.x + ((self.input.visual_cursor()).max(scroll) - scroll) as u16 + 1,
+            // Columns, not chars, as wide characters take two
+            area.x + (self.input.visual_cursor().max(scroll) - scroll) as u16 + 1,
             // Move one line down, from the border to the input line
             area.y + 1,
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rq_core::request::header::{CONTENT_LENGTH, CONTENT_TYPE, COOKIE};
+    use tui_input::Input;
+
+    use super::InputComponent;
+
+    fn input(value: &str) -> InputComponent {
+        InputComponent::from(value).with_completions(
+            vec!["save body".into(), "save all".into(), "send".into()],
+            vec!["host".into(), "token".into(), "tenant".into()],
+        )
+    }
+
+    #[test]
+    fn test_start_cycle() {
+        let cycle = input("sa").start_cycle().unwrap();
+        assert_eq!(cycle.start, 0);
+        assert_eq!(cycle.candidates, ["save body", "save all"]);
+
+        // Whole values aren't offered again
+        assert!(input("send").start_cycle().is_none());
+        assert!(input("x").start_cycle().is_none());
+
+        // Variables, after the last `{{` still open
+        let cycle = input("Bearer {{ t").start_cycle().unwrap();
+        assert_eq!(cycle.start, 10);
+        assert_eq!(cycle.candidates, ["token}}", "tenant}}"]);
+        assert!(input("{{host}} t").start_cycle().is_none());
+
+        // Only the text before the cursor is completed
+        let cycle = input("sa").with_cursor(1).start_cycle().unwrap();
+        assert_eq!(cycle.candidates, ["save body", "save all", "send"]);
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut input = input("{{é}} {{t after");
+        input.input = Input::from(input.input.value()).with_cursor(9);
+
+        input.complete(true);
+        assert_eq!(input.input.value(), "{{é}} {{token}} after");
+        assert_eq!(input.input.cursor(), 15);
+
+        input.complete(true);
+        assert_eq!(input.input.value(), "{{é}} {{tenant}} after");
+
+        // Backwards, wrapping around
+        input.complete(false);
+        input.complete(false);
+        assert_eq!(input.input.value(), "{{é}} {{tenant}} after");
+        assert_eq!(input.input.cursor(), 16);
+    }
+
+    #[test]
+    fn test_complete_headers() {
+        const HEADERS: &[rq_core::request::header::HeaderName] =
+            &[CONTENT_LENGTH, CONTENT_TYPE, COOKIE];
+        let mut input = InputComponent::from("x-id Content-").with_headers(HEADERS);
+
+        let cycle = input.start_cycle().unwrap();
+        assert_eq!(cycle.start, 5);
+        assert_eq!(cycle.candidates, ["content-length", "content-type"]);
+
+        input.complete(true);
+        input.complete(true);
+        assert_eq!(input.input.value(), "x-id content-type");
+
+        // Previous values first
+        let input = InputComponent::from("co")
+            .with_completions(vec!["count".into()], Vec::new())
+            .with_headers(HEADERS);
+        assert_eq!(input.start_cycle().unwrap().candidates, ["count"]);
+    }
+}
@@ -1,16 +1,101 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use rq_core::request::header::{self, HeaderName};
+
 use crate::{components::response_panel::SaveOption, event::Event};
 
-use super::InputComponent;
+use super::{ConfirmCallback, InputComponent};
+
+// Values confirmed in each kind of input, offered as completions
+static HISTORY: Mutex<BTreeMap<&'static str, Vec<String>>> = Mutex::new(BTreeMap::new());
+
+const COMMANDS: &[&str] = &[
+    "send",
+    "save body",
+    "save all",
+    "open",
+    "reveal",
+    "messages",
+    "queue",
+    "env",
+    "e",
+    "q",
+];
+const ENCODINGS: &[&str] = &[
+    "utf-8",
+    "utf-16le",
+    "utf-16be",
+    "iso-8859-1",
+    "windows-1252",
+    "shift_jis",
+    "euc-jp",
+    "euc-kr",
+    "gbk",
+    "big5",
+];
+// Completed in searches, i.e. to find a header of the response
+const HEADERS: &[HeaderName] = &[
+    header::ACCEPT,
+    header::ACCEPT_ENCODING,
+    header::ACCEPT_LANGUAGE,
+    header::ACCEPT_RANGES,
+    header::ACCESS_CONTROL_ALLOW_HEADERS,
+    header::ACCESS_CONTROL_ALLOW_METHODS,
+    header::ACCESS_CONTROL_ALLOW_ORIGIN,
+    header::AGE,
+    header::ALLOW,
+    header::AUTHORIZATION,
+    header::CACHE_CONTROL,
+    header::CONNECTION,
+    header::CONTENT_DISPOSITION,
+    header::CONTENT_ENCODING,
+    header::CONTENT_LANGUAGE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_LOCATION,
+    header::CONTENT_RANGE,
+    header::CONTENT_SECURITY_POLICY,
+    header::CONTENT_TYPE,
+    header::COOKIE,
+    header::DATE,
+    header::ETAG,
+    header::EXPECT,
+    header::EXPIRES,
+    header::HOST,
+    header::IF_MATCH,
+    header::IF_MODIFIED_SINCE,
+    header::IF_NONE_MATCH,
+    header::LAST_MODIFIED,
+    header::LINK,
+    header::LOCATION,
+    header::ORIGIN,
+    header::PRAGMA,
+    header::RANGE,
+    header::REFERER,
+    header::RETRY_AFTER,
+    header::SERVER,
+    header::SET_COOKIE,
+    header::STRICT_TRANSPORT_SECURITY,
+    header::TRANSFER_ENCODING,
+    header::USER_AGENT,
+    header::VARY,
+    header::WWW_AUTHENTICATE,
+];
 
 pub struct InputBuilder {
     content: String,
     cursor: Option<usize>,
     typ: InputType,
+    variables: Vec<String>,
 }
 
 pub enum InputType {
     FileName(SaveOption),
     VarValue(String),
+    // Request index, index of the query param
+    QueryValue((usize, usize)),
+    Encoding,
+    Search,
+    Command,
 }
 
 impl InputBuilder {
@@ -19,9 +104,16 @@ impl InputBuilder {
             content: String::new(),
             cursor: None,
             typ,
+            variables: Vec::new(),
         }
     }
 
+    /// Names completed after `{{`.
+    pub fn with_variables(self, mut variables: Vec<String>) -> Self {
+        variables.sort();
+        Self { variables, ..self }
+    }
+
     pub fn with_content(self, content: String) -> Self {
         Self { content, ..self }
     }
@@ -36,24 +128,84 @@ impl InputBuilder {
     fn build_component(&self) -> InputComponent {
         let input = InputComponent::from(&self.content);
 
-        match self.cursor {
+        let input = match self.cursor {
             Some(i) => input.with_cursor(i),
             None => input,
+        };
+
+        let defaults = match self.typ {
+            InputType::Command => COMMANDS,
+            InputType::Encoding => ENCODINGS,
+            _ => &[],
+        };
+        let mut completions = HISTORY
+            .lock()
+            .unwrap()
+            .get(self.typ.kind())
+            .cloned()
+            .unwrap_or_default();
+        for default in defaults {
+            if !completions.iter().any(|c| c == default) {
+                completions.push(default.to_string());
+            }
+        }
+
+        let input = input.with_completions(completions, self.variables.clone());
+        match self.typ {
+            InputType::Search => input.with_headers(HEADERS),
+            _ => input,
         }
     }
 
     pub fn build(self) -> InputComponent {
         let input = self.build_component();
+        let kind = self.typ.kind();
 
-        match self.typ {
-            InputType::FileName(save_option) => input.with_confirm_callback(move |value| {
-                Event::emit(Event::InputConfirm);
-                Event::emit(Event::Save((value, save_option)));
+        let on_confirm: ConfirmCallback = match self.typ {
+            InputType::FileName(save_option) => Box::new(move |value, events| {
+                events.emit(Event::Save((value, save_option)));
+            }),
+            InputType::VarValue(name) => Box::new(move |value, events| {
+                events.emit(Event::UpdateVar((name.clone(), value)));
             }),
-            InputType::VarValue(name) => input.with_confirm_callback(move |value| {
-                Event::emit(Event::InputConfirm);
-                Event::emit(Event::UpdateVar((name.clone(), value)));
+            InputType::QueryValue((idx, param)) => Box::new(move |value, events| {
+                events.emit(Event::SetQueryValue((idx, param, value)));
             }),
+            InputType::Encoding => Box::new(|value, events| events.emit(Event::SetEncoding(value))),
+            InputType::Search => Box::new(|value, events| events.emit(Event::Search(value))),
+            InputType::Command => Box::new(|value, events| events.emit(Event::Command(value))),
+        };
+
+        input.with_confirm_callback(move |value, events| {
+            remember(kind, &value);
+            events.emit(Event::InputConfirm);
+            on_confirm(value, events);
+        })
+    }
+}
+
+impl InputType {
+    fn kind(&self) -> &'static str {
+        match self {
+            InputType::FileName(_) => "file",
+            InputType::VarValue(_) => "variable",
+            InputType::QueryValue(_) => "query",
+            InputType::Encoding => "encoding",
+            InputType::Search => "search",
+            InputType::Command => "command",
         }
     }
 }
+
+// Most recent first, without duplicates
+fn remember(kind: &'static str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+
+    let mut history = HISTORY.lock().unwrap();
+    let values = history.entry(kind).or_default();
+    values.retain(|v| v != value);
+    values.insert(0, value.to_owned());
+    values.truncate(50);
+}
@@ -0,0 +1,98 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use rq_core::request::link::Link;
+
+use crate::{
+    browser,
+    event::{Event, EventSender, Message},
+};
+
+use super::{
+    menu::{Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+impl MenuItem for Link {
+    fn render(&self) -> Vec<Line<'_>> {
+        let mut spans = vec![Span::styled(
+            format!("{:<16}", self.header.unwrap_or("body")),
+            Style::default().fg(Color::DarkGray),
+        )];
+        if let Some(rel) = &self.rel {
+            spans.push(Span::styled(
+                format!("{rel} "),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        spans.push(Span::raw(self.url.as_str()));
+
+        vec![Line::from(spans)]
+    }
+}
+
+/// Links of the current response, to be opened in the browser or turned
+/// into new requests.
+pub struct LinksPanel {
+    menu: Menu<Link>,
+}
+
+impl LinksPanel {
+    pub fn new(links: Vec<Link>) -> Self {
+        Self {
+            menu: Menu::new(links),
+        }
+    }
+}
+
+impl BlockComponent for LinksPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("↓/↑ j/k", "next/previous"),
+            ("Enter", "open in browser"),
+            ("n", "new GET request"),
+            ("N", "new GET request, saved to file"),
+            ("Esc", "close"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Enter => {
+                let url = &self.menu.selected().url;
+                if let Err(e) = browser::open(url) {
+                    events.emit(Event::Message(Message::Error(format!(
+                        "Couldn't open {url}: {e}"
+                    ))));
+                }
+            }
+            KeyCode::Char(c @ ('n' | 'N')) => {
+                events.emit(Event::PopupDismiss);
+                events.emit(Event::NewRequest((
+                    self.menu.selected().url.clone(),
+                    c == 'N',
+                )));
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = format!(" links ({}) ", self.menu.items().len());
+        self.menu.render(frame, area, block.title(title));
+    }
+}
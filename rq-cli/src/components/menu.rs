@@ -1,9 +1,18 @@
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+};
+
 use crossterm::event::KeyCode;
 use ratatui::{
-    text::Line,
-    widgets::{List, ListItem, ListState},
+    prelude::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{block::Title, List, ListItem, ListState},
 };
 
+use crate::{event::EventSender, keymap};
+
 use super::BlockComponent;
 
 pub trait MenuItem {
@@ -11,34 +20,107 @@ pub trait MenuItem {
     fn render_highlighted(&self) -> Vec<Line<'_>> {
         self.render()
     }
+    /// Like `render_highlighted` or `render`, with the details that can be
+    /// folded, i.e. the body of a request, `expanded` or not.
+    fn render_folded(&self, highlighted: bool, _expanded: bool) -> Vec<Line<'_>> {
+        if highlighted {
+            self.render_highlighted()
+        } else {
+            self.render()
+        }
+    }
 }
 
-type ConfirmCallback<T> = Box<dyn Fn(&T)>;
+type ConfirmCallback<T> = Box<dyn Fn(&T, &EventSender)>;
+
+/// Row of the menu, as arranged by [`Menu::arrange`].
+pub enum Entry {
+    Group(String),
+    // Index in the items
+    Item(usize),
+}
 
 pub struct Menu<T: MenuItem> {
+    // Index in the items, whatever their order on screen
     idx: usize,
     items: Vec<T>,
+    layout: Vec<Entry>,
     on_confirm_callback: Option<ConfirmCallback<T>>,
+    // Whether items are prefixed with their number, which can be typed to jump to them
+    numbered: bool,
+    // Number typed so far, i.e. `12` before `Enter`
+    jump: String,
+    // Items shown with a star, i.e. favorites
+    marked: HashSet<usize>,
+    // Items expanded or collapsed on purpose, the others being expanded only
+    // while highlighted
+    folds: HashMap<usize, bool>,
+    // First row shown, as scrolled by the last frame
+    offset: Cell<usize>,
 }
 
 impl<T: MenuItem> Menu<T> {
     pub fn new(items: Vec<T>) -> Self {
         Self {
             idx: 0,
+            layout: (0..items.len()).map(Entry::Item).collect(),
             items,
             on_confirm_callback: None,
+            numbered: false,
+            jump: String::new(),
+            marked: HashSet::new(),
+            folds: HashMap::new(),
+            offset: Cell::new(0),
         }
     }
 
+    // Indexes of the items in display order
+    fn order(&self) -> Vec<usize> {
+        self.layout
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Item(i) => Some(*i),
+                Entry::Group(_) => None,
+            })
+            .collect()
+    }
+
     fn next(&mut self) {
-        self.idx = (self.idx + 1) % self.items.len();
+        let order = self.order();
+        if let Some(pos) = order.iter().position(|&i| i == self.idx) {
+            self.idx = order[(pos + 1) % order.len()];
+        }
     }
 
     fn previous(&mut self) {
-        self.idx = match self.idx {
-            0 => self.items.len() - 1,
-            i => i - 1,
-        };
+        let order = self.order();
+        if let Some(pos) = order.iter().position(|&i| i == self.idx) {
+            self.idx = order[(pos + order.len() - 1) % order.len()];
+        }
+    }
+
+    /// Shows the items in the order of `layout`, under group headers. Items
+    /// left out are hidden, moving the selection to the first shown one.
+    pub fn arrange(&mut self, layout: Vec<Entry>) {
+        self.layout = layout;
+
+        let order = self.order();
+        if !order.contains(&self.idx) {
+            if let Some(&first) = order.first() {
+                self.idx = first;
+            }
+        }
+    }
+
+    // Selects the item numbered `n`, counting from 1
+    fn jump(&mut self, n: usize) {
+        if (1..=self.items.len()).contains(&n) {
+            self.idx = n - 1;
+        }
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        self.jump(idx + 1);
     }
 
     pub fn selected(&self) -> &T {
@@ -49,10 +131,27 @@ impl<T: MenuItem> Menu<T> {
         self.idx
     }
 
+    pub fn offset(&self) -> usize {
+        self.offset.get()
+    }
+
+    /// Scrolls to show row `offset` first, as far as the selection stays in view.
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset.set(offset);
+    }
+
     pub fn get(&self, idx: usize) -> &T {
         &self.items[idx]
     }
 
+    pub fn get_mut(&mut self, idx: usize) -> &mut T {
+        &mut self.items[idx]
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
     pub fn update<P>(&mut self, predicate: P, value: T)
     where
         P: Fn(&T) -> bool,
@@ -62,33 +161,107 @@ impl<T: MenuItem> Menu<T> {
         }
     }
 
+    pub fn set_marked(&mut self, idx: usize, marked: bool) {
+        if marked {
+            self.marked.insert(idx);
+        } else {
+            self.marked.remove(&idx);
+        }
+    }
+
+    fn expanded(&self, idx: usize) -> bool {
+        self.folds.get(&idx).copied().unwrap_or(idx == self.idx)
+    }
+
+    /// Expands the selected item if it's collapsed, or collapses it.
+    pub fn toggle_fold(&mut self) {
+        let expanded = self.expanded(self.idx);
+        self.folds.insert(self.idx, !expanded);
+    }
+
+    /// Collapses every item, the highlighted one included.
+    pub fn collapse_all(&mut self) {
+        self.folds = (0..self.items.len()).map(|i| (i, false)).collect();
+    }
+
     pub fn add(&mut self, value: T) {
+        self.layout.push(Entry::Item(self.items.len()));
         self.items.push(value);
     }
 
     pub fn with_confirm_callback<F>(self, confirm_callback: F) -> Self
     where
-        F: Fn(&T) + 'static,
+        F: Fn(&T, &EventSender) + 'static,
     {
         Self {
             on_confirm_callback: Some(Box::new(confirm_callback)),
             ..self
         }
     }
+
+    pub fn with_numbers(self) -> Self {
+        Self {
+            numbered: true,
+            ..self
+        }
+    }
 }
 
+// Keys following the digits of an item number
+const JUMP_KEYMAPS: &[(&str, &str)] = &[
+    ("Enter", "select"),
+    ("Backspace", "delete digit"),
+    ("Esc", "cancel"),
+];
+
 impl<T: MenuItem> BlockComponent for Menu<T> {
     fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
-        [("↓/↑ j/k", "next/previous"), ("Enter", "select")].as_slice()
+        if self.numbered {
+            [
+                ("↓/↑ j/k", "next/previous"),
+                ("Enter", "select"),
+                ("<n> Enter", "select the n-th"),
+            ]
+            .as_slice()
+        } else {
+            [("↓/↑ j/k", "next/previous"), ("Enter", "select")].as_slice()
+        }
     }
 
-    fn on_event(&mut self, key_event: crossterm::event::KeyEvent) -> super::HandleResult {
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> super::HandleResult {
+        if self.numbered {
+            match key_event.code {
+                KeyCode::Char(c @ '0'..='9') => {
+                    self.jump.push(c);
+                    return Ok(super::HandleSuccess::Consumed);
+                }
+                KeyCode::Backspace if !self.jump.is_empty() => {
+                    self.jump.pop();
+                    return Ok(super::HandleSuccess::Consumed);
+                }
+                KeyCode::Esc if !self.jump.is_empty() => {
+                    self.jump.clear();
+                    return Ok(super::HandleSuccess::Consumed);
+                }
+                KeyCode::Enter if !self.jump.is_empty() => {
+                    let n = std::mem::take(&mut self.jump).parse().unwrap_or_default();
+                    self.jump(n);
+                    return Ok(super::HandleSuccess::Consumed);
+                }
+                _ => self.jump.clear(),
+            }
+        }
+
         match key_event.code {
             KeyCode::Char('j') | KeyCode::Down => self.next(),
             KeyCode::Char('k') | KeyCode::Up => self.previous(),
             KeyCode::Enter => {
                 if let Some(callback) = self.on_confirm_callback.as_ref() {
-                    callback(self.selected());
+                    callback(self.selected(), events);
                 }
             }
             _ => return Ok(super::HandleSuccess::Ignored),
@@ -103,25 +276,62 @@ impl<T: MenuItem> BlockComponent for Menu<T> {
         area: ratatui::prelude::Rect,
         block: ratatui::widgets::Block,
     ) {
+        let width = self.items.len().to_string().len();
         let items = self
-            .items
+            .layout
             .iter()
-            .enumerate()
-            .map(|(i, item)| {
-                if self.idx == i {
-                    ListItem::new(item.render_highlighted())
-                } else {
-                    ListItem::new(item.render())
+            .map(|entry| {
+                let i = match entry {
+                    Entry::Item(i) => *i,
+                    Entry::Group(name) => {
+                        return ListItem::new(Line::styled(
+                            name.as_str(),
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::BOLD),
+                        ))
+                    }
+                };
+                let item = &self.items[i];
+
+                let mut lines = item.render_folded(self.idx == i, self.expanded(i));
+
+                if self.numbered {
+                    let mark = if self.marked.contains(&i) { '★' } else { ' ' };
+                    for (j, line) in lines.iter_mut().enumerate() {
+                        let prefix = match j {
+                            0 => format!("{:>width$}{mark} ", i + 1),
+                            _ => " ".repeat(width + 2),
+                        };
+                        line.spans.insert(
+                            0,
+                            Span::styled(prefix, Style::default().fg(Color::DarkGray)),
+                        );
+                    }
                 }
+
+                ListItem::new(lines)
             })
             .collect::<Vec<_>>();
 
+        let block = if self.jump.is_empty() {
+            block
+        } else {
+            let hint = format!(" {}: {} ", self.jump, keymap::hint(JUMP_KEYMAPS));
+            block.title(Title::from(hint).alignment(Alignment::Right))
+        };
+
         let list = List::new(items).highlight_symbol("> ");
 
-        frame.render_stateful_widget(
-            list.block(block),
-            area,
-            &mut ListState::default().with_selected(Some(self.idx)),
-        );
+        let selected = self
+            .layout
+            .iter()
+            .position(|entry| matches!(entry, Entry::Item(i) if *i == self.idx));
+
+        let mut state = ListState::default()
+            .with_selected(selected)
+            .with_offset(self.offset.get());
+        frame.render_stateful_widget(list.block(block), area, &mut state);
+        self.offset.set(state.offset());
     }
 }
@@ -1,18 +1,31 @@
+use std::cell::Cell;
+
+use crossterm::event::KeyCode;
 use ratatui::{
-    style::{Color, Style},
-    widgets::{Paragraph, Wrap},
+    prelude::Alignment,
+    style::Style,
+    widgets::{block::Title, Paragraph, Wrap},
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::event::{Event, Message};
+use crate::event::{Event, EventSender, Message};
 
-use super::{BlockComponent, HandleResult, HandleSuccess};
+use super::{messages, popup, BlockComponent, HandleResult, HandleSuccess};
 
 pub struct MessageDialog {
     content: Message,
+    // Rows scrolled, when the content doesn't fit
+    scroll: usize,
+    // Of the last render, 0 when everything is shown
+    max_scroll: Cell<usize>,
 }
 impl MessageDialog {
     pub fn new(message: Message) -> Self {
-        Self { content: message }
+        Self {
+            content: message,
+            scroll: 0,
+            max_scroll: Cell::new(0),
+        }
     }
 
     fn format_title(title: &str) -> String {
@@ -22,11 +35,39 @@ impl MessageDialog {
 
 impl BlockComponent for MessageDialog {
     fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
-        [("any", "dismiss")].as_slice()
+        [("↓/↑ j/k", "scroll, if it doesn't fit"), ("any", "dismiss")].as_slice()
     }
 
-    fn on_event(&mut self, _key_event: crossterm::event::KeyEvent) -> HandleResult {
-        Event::emit(Event::PopupDismiss);
+    fn content_size(&self, max_width: u16) -> Option<(u16, u16)> {
+        let (title, _) = messages::kind(&self.content);
+        let text = self.content.text();
+
+        let width = text
+            .lines()
+            .map(|line| line.width())
+            .chain([Self::format_title(title).width()])
+            .max()
+            .unwrap_or(0);
+        let width = (width as u16).min(max_width);
+
+        Some((width, popup::wrapped_height(text, width)))
+    }
+
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> HandleResult {
+        let max_scroll = self.max_scroll.get();
+        match key_event.code {
+            KeyCode::Down | KeyCode::Char('j') if max_scroll > 0 => {
+                self.scroll = (self.scroll + 1).min(max_scroll);
+            }
+            KeyCode::Up | KeyCode::Char('k') if max_scroll > 0 => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            _ => events.emit(Event::PopupDismiss),
+        }
 
         Ok(HandleSuccess::Consumed)
     }
@@ -37,17 +78,27 @@ impl BlockComponent for MessageDialog {
         area: ratatui::prelude::Rect,
         block: ratatui::widgets::Block,
     ) {
-        let (content, title, color) = match &self.content {
-            Message::Info(content) => (content.as_str(), Self::format_title("info"), Color::Green),
-            Message::Error(content) => (content.as_str(), Self::format_title("error"), Color::Red),
-            Message::Custom(title, content) => {
-                (content.as_str(), Self::format_title(title), Color::Green)
-            }
-        };
+        // The kind in the title, not just in the color of the border
+        let (title, color) = messages::kind(&self.content);
+        let mut block = block
+            .border_style(Style::default().fg(color))
+            .title(Self::format_title(title));
+
+        let inner = block.inner(area);
+        let rows = popup::wrapped_height(self.content.text(), inner.width) as usize;
+        let max_scroll = rows.saturating_sub(inner.height as usize);
+        self.max_scroll.set(max_scroll);
+        let scroll = self.scroll.min(max_scroll);
+        if max_scroll > 0 {
+            block = block.title(
+                Title::from(format!(" {}/{rows} ↓/↑ ", scroll + 1)).alignment(Alignment::Right),
+            );
+        }
 
-        let p = Paragraph::new(content)
-            .block(block.border_style(Style::default().fg(color)).title(title))
-            .wrap(Wrap::default());
+        let p = Paragraph::new(self.content.text())
+            .block(block)
+            .wrap(Wrap::default())
+            .scroll((scroll as u16, 0));
 
         frame.render_widget(p, area);
     }
@@ -0,0 +1,149 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::event::{Event, EventSender, Message};
+
+use super::{
+    menu::{Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+// Of the toast area, in the bottom right corner
+const TOAST_WIDTH: u16 = 48;
+// Shown at once, the oldest first
+const MAX_TOASTS: usize = 3;
+
+/// Label and color of the kind of `message`, the label spelled out when plain.
+pub fn kind(message: &Message) -> (&str, Color) {
+    let plain = super::plain();
+    match message {
+        Message::Info(_) if plain => ("[INFO]", Color::Green),
+        Message::Info(_) => ("info", Color::Green),
+        Message::Warning(_) if plain => ("[WARNING]", Color::Yellow),
+        Message::Warning(_) => ("warning", Color::Yellow),
+        Message::Error(_) if plain => ("[ERROR]", Color::Red),
+        Message::Error(_) => ("error", Color::Red),
+        Message::Custom(title, _) => (title.as_str(), Color::Green),
+    }
+}
+
+/// Non-modal messages, stacked above the bottom right corner of `area`.
+pub fn render_toasts(frame: &mut crate::terminal::Frame, area: Rect, toasts: &[&Message]) {
+    let width = TOAST_WIDTH.min(area.width);
+    let mut bottom = area.bottom();
+
+    for message in toasts.iter().rev().take(MAX_TOASTS) {
+        let (title, color) = kind(message);
+        let text = message.text();
+
+        // Rows of the text once wrapped, at most a few
+        let inner = width.saturating_sub(2).max(1) as usize;
+        let rows = text
+            .lines()
+            .map(|line| line.width().div_ceil(inner).max(1))
+            .sum::<usize>()
+            .min(4) as u16;
+        let height = (rows + 2).min(bottom.saturating_sub(area.y));
+        if height < 3 {
+            break;
+        }
+        bottom -= height;
+
+        let toast_area = Rect::new(area.right() - width, bottom, width, height);
+        let toast = Paragraph::new(text)
+            .block(
+                super::block()
+                    .border_style(Style::default().fg(color))
+                    .title(format!(" {title} ")),
+            )
+            .wrap(Wrap { trim: false });
+        frame.render_widget(Clear, toast_area);
+        frame.render_widget(toast, toast_area);
+    }
+}
+
+/// Message shown earlier, kept for `:messages`.
+#[derive(Clone)]
+pub struct LoggedMessage {
+    // Local, as `HH:MM:SS`
+    pub time: String,
+    pub message: Message,
+}
+
+impl MenuItem for LoggedMessage {
+    fn render(&self) -> Vec<Line<'_>> {
+        let (label, color) = kind(&self.message);
+        let first = self.message.text().lines().next().unwrap_or_default();
+
+        vec![Line::from(vec![
+            Span::styled(
+                format!("{} ", self.time),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(format!("{label:<10}"), Style::default().fg(color)),
+            Span::raw(first),
+        ])]
+    }
+
+    fn render_highlighted(&self) -> Vec<Line<'_>> {
+        let mut lines = self.render();
+        lines.extend(
+            self.message
+                .text()
+                .lines()
+                .skip(1)
+                .map(|line| Line::from(format!("{:20}{line}", ""))),
+        );
+
+        lines
+    }
+}
+
+/// Messages of the session, oldest first, the last one selected.
+pub struct MessagesPanel {
+    menu: Menu<LoggedMessage>,
+}
+
+impl MessagesPanel {
+    pub fn new(messages: Vec<LoggedMessage>) -> Self {
+        let last = messages.len().saturating_sub(1);
+        let mut menu = Menu::new(messages);
+        menu.select(last);
+
+        Self { menu }
+    }
+}
+
+impl BlockComponent for MessagesPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [("↓/↑ j/k", "next/previous"), ("Esc", "close")].as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = format!(" messages ({}) ", self.menu.items().len());
+        self.menu.render(frame, area, block.title(title));
+    }
+}
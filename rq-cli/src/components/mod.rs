@@ -1,16 +1,54 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use crossterm::event::KeyEvent;
-use ratatui::{prelude::Rect, widgets::Block};
+use ratatui::{
+    prelude::Rect,
+    widgets::{Block, Borders},
+};
 
-use crate::terminal::Frame;
+use crate::{event::EventSender, terminal::Frame};
 
+pub mod body_editor;
+pub mod confirm_dialog;
+pub mod decoded;
+pub mod headers;
+pub mod history;
 pub mod input;
+pub mod links;
 pub mod menu;
 pub mod message_dialog;
+pub mod messages;
 pub mod popup;
+pub mod query;
+pub mod queue;
 pub mod response_panel;
+pub mod retry_dialog;
+pub mod snippets;
 pub mod template_request;
 pub mod variables;
 
+// Screen reader friendly rendering, see `set_plain`
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Renders without borders, scrollbars and meanings carried by colors
+/// alone: panels are stacked and states are spelled out, i.e. `[ERROR]`.
+pub fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub fn plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Frame of a panel or popup: a border, or only its title when plain.
+pub fn block() -> Block<'static> {
+    if plain() {
+        Block::default()
+    } else {
+        Block::default().borders(Borders::ALL)
+    }
+}
+
 pub enum HandleSuccess {
     Consumed,
     Ignored,
@@ -18,21 +56,19 @@ pub enum HandleSuccess {
 
 pub type HandleResult = anyhow::Result<HandleSuccess>;
 
-pub trait Component {
-    fn on_event(&mut self, _key_event: KeyEvent) -> HandleResult {
-        Ok(HandleSuccess::Ignored)
-    }
-    fn update(&mut self) {}
-    fn render(&self, frame: &mut Frame, area: Rect);
-}
-
 pub trait BlockComponent {
-    fn on_event(&mut self, _key_event: KeyEvent) -> HandleResult {
+    /// Handles a key press. Blocking work, i.e. I/O, goes through
+    /// [`EventSender::spawn`] instead of being done here.
+    fn on_event(&mut self, _key_event: KeyEvent, _events: &EventSender) -> HandleResult {
         Ok(HandleSuccess::Ignored)
     }
-    fn update(&mut self) {}
     fn render(&self, frame: &mut Frame, area: Rect, block: Block);
     fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
         &[]
     }
+    /// Width and height of the content, without the border, when at most
+    /// `max_width` wide. Popups fit it, unless given a size.
+    fn content_size(&self, _max_width: u16) -> Option<(u16, u16)> {
+        None
+    }
 }
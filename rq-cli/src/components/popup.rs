@@ -2,13 +2,22 @@ use ratatui::{
     prelude::{Constraint, Direction, Layout, Rect},
     widgets::Clear,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::event::EventSender;
 
 use super::BlockComponent;
 
+// Bounds of popups sized by their content, in percent of the screen
+const MIN_WIDTH: u16 = 40;
+const MAX_SIZE: u16 = 80;
+
 pub struct Popup<T: BlockComponent> {
     component: T,
     w_percent: u16,
     h_percent: u16,
+    // Whether the size was given, instead of fitting the content
+    fixed: bool,
 }
 
 impl<T: BlockComponent> Popup<T> {
@@ -17,17 +26,101 @@ impl<T: BlockComponent> Popup<T> {
             component: widget,
             w_percent: 40,
             h_percent: 25,
+            fixed: false,
+        }
+    }
+
+    pub fn with_size(self, w_percent: u16, h_percent: u16) -> Self {
+        Self {
+            w_percent,
+            h_percent,
+            fixed: true,
+            ..self
         }
     }
+
+    // Fitting the content of the component, if it tells its size
+    fn fitted_area(&self, area: Rect) -> Option<Rect> {
+        if self.fixed {
+            return None;
+        }
+
+        let percent = |length: u16, percent: u16| (length as u32 * percent as u32 / 100) as u16;
+        let max_width = percent(area.width, MAX_SIZE);
+        let (width, height) = self.component.content_size(max_width.saturating_sub(2))?;
+
+        // Plus the border
+        let width = width
+            .saturating_add(2)
+            .clamp(percent(area.width, MIN_WIDTH), max_width.max(3));
+        let height = height
+            .saturating_add(2)
+            .min(percent(area.height, MAX_SIZE).max(3));
+
+        Some(Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width.min(area.width),
+            height.min(area.height),
+        ))
+    }
+}
+
+/// Rows taken by `text` once wrapped at `width` by words, like `Wrap` does.
+pub fn wrapped_height(text: &str, width: u16) -> u16 {
+    let width = width.max(1) as usize;
+
+    let rows = text
+        .lines()
+        .map(|line| {
+            let mut rows = 1;
+            let mut row = 0;
+            for word in line.split_inclusive(' ') {
+                let word_width = word.trim_end().width();
+                if row + word_width > width && row > 0 {
+                    rows += 1;
+                    row = 0;
+                }
+                if word_width <= width {
+                    row += word.width();
+                    continue;
+                }
+
+                // Words longer than a row are cut, a wide character not
+                // fitting at the end of a row moving to the next one
+                let trimmed = word.trim_end();
+                for c in trimmed.chars() {
+                    let c_width = c.width().unwrap_or(0);
+                    if row + c_width > width {
+                        rows += 1;
+                        row = 0;
+                    }
+                    row += c_width;
+                }
+                row += word.len() - trimmed.len();
+            }
+            rows
+        })
+        .sum::<usize>();
+
+    rows.max(1).min(u16::MAX as usize) as u16
 }
 
 impl<T: BlockComponent> BlockComponent for Popup<T> {
-    fn on_event(&mut self, key_event: crossterm::event::KeyEvent) -> super::HandleResult {
-        self.component.on_event(key_event)
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> super::HandleResult {
+        self.component.on_event(key_event, events)
+    }
+
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        self.component.keymaps()
     }
 
-    fn update(&mut self) {
-        self.component.update();
+    fn content_size(&self, max_width: u16) -> Option<(u16, u16)> {
+        self.component.content_size(max_width)
     }
 
     fn render(
@@ -36,6 +129,12 @@ impl<T: BlockComponent> BlockComponent for Popup<T> {
         area: Rect,
         block: ratatui::widgets::Block,
     ) {
+        if let Some(popup_area) = self.fitted_area(area) {
+            frame.render_widget(Clear, popup_area);
+            self.component.render(frame, popup_area, block);
+            return;
+        }
+
         let popup_area = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -58,3 +157,33 @@ impl<T: BlockComponent> BlockComponent for Popup<T> {
         self.component.render(frame, popup_area, block);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::wrapped_height;
+
+    #[test]
+    fn test_wrapped_height() {
+        assert_eq!(wrapped_height("", 10), 1);
+        assert_eq!(wrapped_height("one two three", 13), 1);
+        assert_eq!(wrapped_height("one two three", 8), 2);
+        assert_eq!(wrapped_height("one\ntwo", 10), 2);
+    }
+
+    #[test]
+    fn test_wrapped_height_long_words() {
+        assert_eq!(wrapped_height("abcdefghij", 4), 3);
+        // On a row of its own
+        assert_eq!(wrapped_height("ab abcdefghij", 4), 4);
+        assert_eq!(wrapped_height("abcdefghij ab", 4), 4);
+        assert_eq!(wrapped_height("abc", 0), 3);
+    }
+
+    #[test]
+    fn test_wrapped_height_wide_chars() {
+        assert_eq!(wrapped_height("中文中文", 4), 2);
+        // A column left at the end of each row
+        assert_eq!(wrapped_height("中文中文中", 3), 5);
+        assert_eq!(wrapped_height("中文 中文", 5), 2);
+    }
+}
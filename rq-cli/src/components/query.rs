@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use rq_core::parser::{variables::TemplateString, TemplateRequest};
+
+use crate::event::{Event, EventSender};
+
+use super::{
+    input::builder::{InputBuilder, InputType},
+    menu::{Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+pub struct QueryItem {
+    name: String,
+    template: String,
+    // Filled and percent-decoded, or why it couldn't be
+    value: Result<String, String>,
+    enabled: bool,
+}
+
+impl MenuItem for QueryItem {
+    fn render(&self) -> Vec<Line<'_>> {
+        if !self.enabled {
+            return vec![Line::styled(
+                format!("[ ] {} = {}", self.name, self.template),
+                Style::default().fg(Color::DarkGray),
+            )];
+        }
+
+        let (value, detail) = match &self.value {
+            Ok(value) if *value == self.template => (value.as_str(), None),
+            Ok(value) => (
+                value.as_str(),
+                Some((self.template.as_str(), Color::DarkGray)),
+            ),
+            Err(e) => (self.template.as_str(), Some((e.as_str(), Color::Red))),
+        };
+
+        let mut lines = vec![Line::from(vec![
+            Span::raw("[x] "),
+            Span::styled(self.name.as_str(), Style::default().fg(Color::Blue)),
+            Span::raw(" = "),
+            Span::raw(value),
+        ])];
+        if let Some((detail, color)) = detail {
+            lines.push(Line::styled(
+                format!("    {detail}"),
+                Style::default().fg(color),
+            ));
+        }
+
+        lines
+    }
+}
+
+/// Query params of a request, resolved with the current variables, to leave
+/// them out or change their value without editing the file.
+pub struct QueryPanel {
+    // Index of the request
+    idx: usize,
+    menu: Menu<QueryItem>,
+}
+
+impl QueryPanel {
+    pub fn new(
+        idx: usize,
+        request: &TemplateRequest,
+        vars: &HashMap<String, TemplateString>,
+    ) -> Self {
+        let items = request
+            .decoded_query(vars)
+            .into_iter()
+            .zip(request.query.iter())
+            .enumerate()
+            .map(|(i, ((name, value), (_, template)))| QueryItem {
+                name,
+                template: template.to_string(),
+                value: value.map_err(|e| e.to_string()),
+                enabled: !request.disabled_query.contains(&i),
+            })
+            .collect();
+
+        Self {
+            idx,
+            menu: Menu::new(items),
+        }
+    }
+
+    pub fn with_selected(mut self, param: usize) -> Self {
+        self.menu.select(param);
+        self
+    }
+}
+
+impl BlockComponent for QueryPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("↓/↑ j/k", "next/previous"),
+            ("Space", "enable/disable"),
+            ("Enter", "edit value"),
+            ("Esc", "close"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Char(' ') if !self.menu.items().is_empty() => {
+                let param = self.menu.idx();
+                let item = self.menu.get_mut(param);
+                item.enabled = !item.enabled;
+                events.emit(Event::ToggleQuery((self.idx, param)));
+            }
+            KeyCode::Enter if !self.menu.items().is_empty() => {
+                let param = self.menu.idx();
+                // The panel is opened again once the value is confirmed
+                events.emit(Event::PopupDismiss);
+                events.emit(Event::NewInput(
+                    InputBuilder::new(InputType::QueryValue((self.idx, param)))
+                        .with_content(self.menu.selected().template.clone()),
+                ));
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = format!(" query params ({}) ", self.menu.items().len());
+        self.menu.render(frame, area, block.title(title));
+    }
+}
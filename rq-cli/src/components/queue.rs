@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    prelude::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+use crate::{
+    event::{Event, EventSender},
+    queue::Queued,
+};
+
+use super::{
+    menu::{self, Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+impl MenuItem for Queued {
+    fn render(&self) -> Vec<Line<'_>> {
+        vec![Line::from(vec![
+            Span::styled(
+                format!("{} ", self.time),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(self.method.as_str(), Style::default().fg(Color::Green)),
+            Span::raw(" "),
+            Span::raw(self.url.as_str()),
+        ])]
+    }
+}
+
+/// Requests waiting for their host to be reachable, oldest first.
+pub struct QueuePanel {
+    menu: Menu<Queued>,
+    // Indexes in the menu of the ones taken out of the queue
+    removed: HashSet<usize>,
+}
+
+impl QueuePanel {
+    pub fn new(queue: Vec<Queued>) -> Self {
+        Self {
+            menu: Menu::new(queue),
+            removed: HashSet::new(),
+        }
+    }
+
+    fn left(&self) -> usize {
+        self.menu.items().len() - self.removed.len()
+    }
+}
+
+impl BlockComponent for QueuePanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("↓/↑ j/k", "next/previous"),
+            ("d", "remove from the queue"),
+            ("Esc", "close"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Char('d') if self.left() > 0 => {
+                let selected = self.menu.idx();
+                events.emit(Event::Unqueue(self.menu.selected().idx));
+                self.removed.insert(selected);
+
+                let layout = (0..self.menu.items().len())
+                    .filter(|i| !self.removed.contains(i))
+                    .map(menu::Entry::Item)
+                    .collect();
+                self.menu.arrange(layout);
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = format!(" queued until reachable ({}) ", self.left());
+        self.menu.render(frame, area, block.title(title));
+    }
+}
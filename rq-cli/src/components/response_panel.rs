@@ -4,14 +4,34 @@ use ratatui::{
     prelude::{Constraint, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Paragraph, Scrollbar, ScrollbarState, Wrap},
+    widgets::{Paragraph, Scrollbar, ScrollbarState},
+};
+use rq_core::request::{
+    header::CONTENT_TYPE,
+    mime::Payload,
+    redact::Redaction,
+    token::{self, DecodedToken},
+    Response, Spilled, StatusCode,
+};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    fmt::Write,
+    iter,
+    ops::Range,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use rq_core::request::{mime::Payload, Response, StatusCode};
-use std::{fmt::Write, iter};
 
 use crate::{
     app::FocusState,
-    event::{Event, Message},
+    event::{Event, EventSender, Message},
+};
+
+use self::{
+    highlight::{Syntax, Tokens},
+    wrap::WrapCache,
 };
 
 use super::{
@@ -19,6 +39,12 @@ use super::{
     BlockComponent, HandleResult, HandleSuccess,
 };
 
+mod csv;
+pub mod highlight;
+mod reader;
+mod wrap;
+mod xml;
+
 #[derive(Copy, Clone, Default)]
 pub enum SaveOption {
     #[default]
@@ -31,14 +57,99 @@ enum State {
     #[default]
     Empty,
     Loading,
-    Received(Response),
+    Received(Box<Response>),
 }
 
+// Sends of a request, shown in the header once it's sent again
+#[derive(Default)]
+struct Stats {
+    sends: usize,
+    // Failed requests and error statuses
+    errors: usize,
+    // Of the responses, to average their latency
+    responses: u32,
+    latency: Duration,
+    first_sent: Option<Instant>,
+}
+
+impl Stats {
+    fn record(&mut self, latency: Option<Duration>, error: bool) {
+        self.sends += 1;
+        self.errors += usize::from(error);
+        if let Some(latency) = latency {
+            self.responses += 1;
+            self.latency += latency;
+        }
+        self.first_sent.get_or_insert_with(Instant::now);
+    }
+
+    // i.e. `12 sends · 1 error · avg 85 ms · 2.3/s`
+    fn summary(&self) -> Option<String> {
+        if self.sends < 2 {
+            return None;
+        }
+
+        let mut summary = format!("{} sends · {} errors", self.sends, self.errors);
+        if self.responses > 0 {
+            let avg = self.latency / self.responses;
+            let _ = write!(summary, " · avg {} ms", avg.as_millis());
+        }
+        let elapsed = self
+            .first_sent
+            .map_or(0.0, |first| first.elapsed().as_secs_f64());
+        if elapsed > 0.0 {
+            let _ = write!(summary, " · {:.1}/s", self.sends as f64 / elapsed);
+        }
+
+        Some(summary)
+    }
+}
+
+// Bodies bigger than this are summarized until opened
+const LARGE_BODY: usize = 1024 * 1024;
+
+// Columns scrolled horizontally by each key press
+const HSCROLL_STEP: usize = 8;
+
+// Saves are written, and their progress reported, this many bytes at a time
+const SAVE_CHUNK: usize = 1024 * 1024;
+
 #[derive(Default)]
 pub struct ResponsePanel {
     state: State,
-    scroll: u16,
+    // Body as displayed, split in lines once per response instead of every
+    // frame. The title, i.e. "decoded with encoding 'utf-8':", is line 0.
+    body_title: String,
+    body_text: Arc<str>,
+    body_lines: Vec<Range<usize>>,
+    // Content type, charset, line count and size, in the title
+    summary: String,
+    // Of the body lines, `None` for plain text
+    syntax: Option<Syntax>,
+    // Body rows at the current width, and the row counts of the last frame
+    wrapped: RefCell<WrapCache>,
+    header_rows: Cell<usize>,
+    total_rows: Cell<usize>,
+    height: Cell<usize>,
+    // In rows
+    scroll: usize,
+    // In columns, for tables
+    hscroll: usize,
     show_raw: bool,
+    // XML bodies shown as sent instead of indented
+    raw_xml: bool,
+    // HTML bodies shown as formatted text
+    reader: bool,
+    line_numbers: bool,
+    opened: bool,
+    search: Option<String>,
+    stats: Stats,
+    // Bytes of the body sent and its size, while loading
+    upload: Option<(u64, u64)>,
+    // Header and value of the request id sent, see `workspace::RequestId`
+    request_id: Option<(String, String)>,
+    // Of the protocol of the last response, see `Response::protocol_warnings`
+    warnings: Vec<String>,
     idx: usize,
 }
 
@@ -49,46 +160,235 @@ impl ResponsePanel {
 
     pub fn set_loading(&mut self) {
         self.state = State::Loading;
+        self.upload = None;
+        self.refresh_body();
+    }
+
+    // The request failed without a response
+    pub fn set_failed(&mut self) {
+        self.stats.record(None, true);
+        self.state = State::Empty;
+        self.refresh_body();
     }
 
     pub fn set_response(&mut self, value: Response) {
-        self.state = State::Received(value);
+        let latency = value.timings.wait + value.timings.receive;
+        let error = value.status.is_client_error() || value.status.is_server_error();
+        self.stats.record(Some(latency), error);
+
+        self.state = State::Received(Box::new(value));
+        self.opened = false;
+        self.scroll = 0;
+        self.hscroll = 0;
+        self.refresh_body();
+    }
+
+    pub fn set_upload(&mut self, sent: u64, total: u64) {
+        if self.is_loading() {
+            self.upload = Some((sent, total));
+        }
+    }
+
+    pub fn set_request_id(&mut self, header: String, value: String) {
+        self.request_id = Some((header, value));
+    }
+
+    pub fn set_warnings(&mut self, warnings: Vec<String>) {
+        self.warnings = warnings;
+    }
+
+    pub fn has_response(&self) -> bool {
+        matches!(self.state, State::Received(_))
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self.state, State::Loading)
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match &self.state {
+            State::Received(response) => Some(response.status),
+            _ => None,
+        }
+    }
+
+    pub fn set_encoding(&mut self, label: &str) -> anyhow::Result<()> {
+        let State::Received(response) = &mut self.state else {
+            return Err(anyhow!("Request not sent"));
+        };
+
+        response.payload = response
+            .payload
+            .with_encoding(label)
+            .ok_or_else(|| anyhow!("Unknown encoding '{label}'"))?;
+        self.refresh_body();
+
+        Ok(())
+    }
+
+    /// Scrolls to the next line containing `query`, opening large bodies.
+    pub fn search(&mut self, query: String) -> anyhow::Result<()> {
+        if !self.opened {
+            self.opened = true;
+            self.refresh_body();
+        }
+        self.search = Some(query);
+
+        self.search_next()
+    }
+
+    fn search_next(&mut self) -> anyhow::Result<()> {
+        let Some(query) = &self.search else {
+            return Ok(());
+        };
+
+        let header_rows = self.header_rows.get();
+        let wrapped = self.wrapped.borrow();
+
+        // Starting from the line after the one at the top of the viewport
+        let from = match self.scroll.checked_sub(header_rows) {
+            Some(row) => wrapped.rows().get(row).map_or(0, |(line, _)| line + 1),
+            None => 0,
+        };
+
+        let found = self
+            .lines()
+            .enumerate()
+            .skip(from)
+            .find(|(_, line)| line.contains(query.as_str()));
+
+        match found {
+            Some((i, _)) => {
+                self.scroll = header_rows + wrapped.first_row_of(i);
+                Ok(())
+            }
+            None => Err(anyhow!("'{query}' not found")),
+        }
+    }
+
+    /// Scrolls to line `n` of the body, starting from 1, opening large bodies.
+    pub fn goto_line(&mut self, n: usize) -> anyhow::Result<()> {
+        if !self.opened {
+            self.opened = true;
+            self.refresh_body();
+        }
+        if n == 0 || n > self.body_lines.len() {
+            return Err(anyhow!("No line {n}"));
+        }
+
+        self.scroll = self.header_rows.get() + self.wrapped.borrow().first_row_of(n);
+        Ok(())
+    }
+
+    fn refresh_body(&mut self) {
+        let (title, text) = match self.body() {
+            Ok(body) if body.size() > LARGE_BODY && !self.opened => (
+                "large body:".into(),
+                format!(
+                    "{} body — press o to open, / to search",
+                    human_size(self.body_size())
+                )
+                .into(),
+            ),
+            _ => self.body_view(),
+        };
+        self.syntax = match self.body() {
+            Ok(_) if self.reading() || self.tabular().is_some() => None,
+            Ok(body) if body.is_text() && (self.opened || body.size() <= LARGE_BODY) => {
+                body.extension().and_then(Syntax::from_extension)
+            }
+            _ => None,
+        };
+
+        self.body_lines = text
+            .lines()
+            .map(|line| {
+                let start = line.as_ptr() as usize - text.as_ptr() as usize;
+                start..start + line.len()
+            })
+            .collect();
+        self.body_title = title;
+        self.body_text = text;
+        self.summary = self.summary();
+
+        self.wrapped.borrow_mut().rebuild(self.lines());
+    }
+
+    // Body lines, title included
+    fn lines(&self) -> impl Iterator<Item = &str> {
+        iter::once(self.body_title.as_str()).chain(
+            self.body_lines
+                .iter()
+                .map(|range| &self.body_text[range.clone()]),
+        )
+    }
+
+    fn line(&self, i: usize) -> &str {
+        match i {
+            0 => &self.body_title,
+            i => &self.body_text[self.body_lines[i - 1].clone()],
+        }
     }
 }
 
 impl ResponsePanel {
     fn scroll_down(&mut self) {
-        self.scroll = self.scroll.saturating_add(1);
+        let last = self.total_rows.get().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add(1).min(last);
     }
 
     fn scroll_up(&mut self) {
         self.scroll = self.scroll.saturating_sub(1);
     }
 
-    fn body(&self) -> anyhow::Result<Payload> {
+    fn body(&self) -> anyhow::Result<&Payload> {
         match &self.state {
-            State::Received(response) => Ok(response.payload.clone()),
+            State::Received(response) => Ok(&response.payload),
             State::Empty | State::Loading => Err(anyhow!("Request not sent")),
         }
     }
 
-    fn to_string(&self) -> anyhow::Result<String> {
+    // Body too big to be kept in memory, the payload holding its head only
+    fn spilled(&self) -> Option<&Spilled> {
+        match &self.state {
+            State::Received(response) => response.spilled.as_ref(),
+            State::Empty | State::Loading => None,
+        }
+    }
+
+    // Of the whole body, spilled or not
+    fn body_size(&self) -> usize {
+        match (self.spilled(), self.body()) {
+            (Some(spilled), _) => spilled.size as usize,
+            (None, Ok(body)) => body.size(),
+            (None, Err(_)) => 0,
+        }
+    }
+
+    fn to_string(&self, redaction: Option<&Redaction>) -> anyhow::Result<String> {
         match &self.state {
             State::Received(response) => {
-                let headers = response
-                    .headers
-                    .iter()
-                    .fold(String::new(), |mut acc, (k, v)| {
-                        writeln!(acc, "{k}: {}", v.to_str().unwrap()).unwrap();
-                        acc
-                    });
+                let headers = match redaction {
+                    Some(redaction) => Cow::Owned(redaction.headers(&response.headers)),
+                    None => Cow::Borrowed(&response.headers),
+                };
+                let headers = headers.iter().fold(String::new(), |mut acc, (k, v)| {
+                    writeln!(acc, "{k}: {}", v.to_str().unwrap()).unwrap();
+                    acc
+                });
 
-                let body = self.body_as_string().join("\n");
+                let (title, body) = self.body_view();
 
-                let s = format!(
-                    "{} {}\n{headers}\n\n{body}",
+                let mut s = format!(
+                    "{} {}\n{headers}\n\n{title}\n{body}",
                     response.version, response.status
                 );
+                if !response.trailers.is_empty() {
+                    s.push_str("\n\n");
+                    for (k, v) in &response.trailers {
+                        writeln!(s, "{k}: {}", String::from_utf8_lossy(v.as_bytes())).unwrap();
+                    }
+                }
 
                 Ok(s)
             }
@@ -96,30 +396,247 @@ impl ResponsePanel {
         }
     }
 
-    fn body_as_string(&self) -> Vec<String> {
+    // Title and text of the whole body, sharing the decoded text when possible
+    fn body_view(&self) -> (String, Arc<str>) {
+        if let (Some(spilled), Ok(body)) = (self.spilled(), self.body()) {
+            return spilled_view(spilled, body, self.show_raw);
+        }
+
         match self.body() {
             Ok(body) => match body {
-                Payload::Text(t) => iter::once(format!("decoded with encoding '{}':", t.charset))
-                    .chain(t.text.lines().map(str::to_string))
-                    .collect(),
-                Payload::Bytes(b) if self.show_raw => iter::once("lossy utf-8 decode:".to_string())
-                    .chain(
-                        String::from_utf8_lossy(&b.bytes)
-                            .lines()
-                            .map(str::to_string),
-                    )
-                    .collect(),
-                Payload::Bytes(_) => vec!["raw bytes".into()],
+                Payload::Text(t) => {
+                    let title = match &t.warning {
+                        Some(warning) => {
+                            format!("decoded with encoding '{}' ({warning}):", t.charset)
+                        }
+                        None => format!("decoded with encoding '{}':", t.charset),
+                    };
+
+                    if self.reading() {
+                        let title = format!("{} as text:", title.trim_end_matches(':'));
+                        return (title, reader::to_text(&t.text).into());
+                    }
+
+                    if let Some(text) = self.tabular().and_then(|d| csv::table(&t.text, d)) {
+                        let title = format!("{} as table:", title.trim_end_matches(':'));
+                        return (title, text.into());
+                    }
+
+                    // Raw when malformed
+                    if !self.raw_xml && matches!(body.extension(), Some("xml" | "svg")) {
+                        if let Some(text) = xml::format(&t.text) {
+                            let title = format!("{} and indented:", title.trim_end_matches(':'));
+                            return (title, text.into());
+                        }
+                    }
+
+                    (title, t.text.clone())
+                }
+                Payload::Bytes(_) if self.show_raw => (
+                    "lossy utf-8 decode:".into(),
+                    body.as_text_lossy().as_ref().into(),
+                ),
+                Payload::Bytes(_) => ("raw bytes".into(), "".into()),
             },
-            Err(e) => vec![e.to_string()],
+            Err(e) => (e.to_string(), "".into()),
         }
     }
 
-    fn render_body(&self) -> Vec<Line> {
-        let mut lines: Vec<Line> = self.body_as_string().into_iter().map(Line::from).collect();
-        lines[0].patch_style(
-            Style::default().add_modifier(Modifier::ITALIC.union(Modifier::UNDERLINED)),
-        );
+    // i.e. `application/json · utf-8 · 2 341 lines · 1.2 MiB`
+    fn summary(&self) -> String {
+        let State::Received(response) = &self.state else {
+            return String::new();
+        };
+
+        let mut parts = response
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|essence| essence.trim().to_owned())
+            .into_iter()
+            .collect::<Vec<_>>();
+        if let Payload::Text(t) = &response.payload {
+            parts.push(t.charset.clone());
+            // Unknown when only the head is kept
+            if response.spilled.is_none() {
+                parts.push(format!("{} lines", group_digits(t.text.lines().count())));
+            }
+        }
+        parts.push(human_size(self.body_size()));
+
+        parts.join(" · ")
+    }
+
+    // Tokens of the headers and of the body lines in the viewport, without
+    // duplicates
+    fn decoded_tokens(&self) -> Vec<DecodedToken> {
+        let State::Received(response) = &self.state else {
+            return Vec::new();
+        };
+
+        let header_rows = self.header_rows.get();
+        let wrapped = self.wrapped.borrow();
+        let rows = wrapped.rows();
+        let start = self.scroll.saturating_sub(header_rows).min(rows.len());
+        let end = (self.scroll + self.height.get())
+            .saturating_sub(header_rows)
+            .min(rows.len());
+
+        let mut lines = rows[start..end]
+            .iter()
+            .map(|(i, _)| *i)
+            .filter(|&i| i > 0)
+            .collect::<Vec<_>>();
+        lines.dedup();
+
+        let mut tokens: Vec<DecodedToken> = Vec::new();
+        let headers = response
+            .headers
+            .values()
+            .filter_map(|value| value.to_str().ok());
+        for text in headers.chain(lines.into_iter().map(|i| self.line(i))) {
+            for decoded in token::decode_all(text) {
+                if !tokens.iter().any(|t| t.token == decoded.token) {
+                    tokens.push(decoded);
+                }
+            }
+        }
+
+        tokens
+    }
+
+    // Width of the line numbers, and the space after them, 0 when hidden
+    fn gutter_width(&self) -> usize {
+        match self.state {
+            State::Received(_) if self.line_numbers => {
+                self.body_lines.len().max(1).to_string().len() + 1
+            }
+            _ => 0,
+        }
+    }
+
+    fn reading(&self) -> bool {
+        self.reader
+            && matches!(self.body(), Ok(body) if body.is_text() && body.extension() == Some("html"))
+    }
+
+    // Delimiter of CSV and TSV bodies shown as tables
+    fn tabular(&self) -> Option<char> {
+        match self.body() {
+            Ok(body) if !self.show_raw && body.is_text() => match body.extension() {
+                Some("csv") => Some(','),
+                Some("tsv") => Some('\t'),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Status line, headers and the empty line preceding the body
+    fn header_lines(&self) -> Vec<Line<'_>> {
+        let State::Received(response) = &self.state else {
+            return Vec::new();
+        };
+
+        let mut lines = vec![];
+        let dim = Style::default().fg(Color::DarkGray);
+
+        if let Some(summary) = self.stats.summary() {
+            lines.push(Line::styled(summary, dim));
+        }
+
+        // Where the request went
+        // <STATUS> <URL>, for each redirect
+        // <URL> (<REMOTE ADDR>, <PROTOCOL>)
+        for redirect in &response.redirects {
+            lines.push(Line::styled(
+                format!("{} {}", redirect.status.as_u16(), redirect.url),
+                dim,
+            ));
+        }
+        let details = [
+            response.remote_addr.map(|addr| addr.to_string()),
+            response.protocol.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        lines.push(if details.is_empty() {
+            Line::styled(response.url.clone(), dim)
+        } else {
+            Line::styled(format!("{} ({})", response.url, details.join(", ")), dim)
+        });
+
+        // <VERSION> <STATUS>
+        lines.push(Line::from(vec![
+            response.version.clone().into(),
+            " ".into(),
+            Span::styled(
+                if super::plain() {
+                    format!("[{}]", response.status)
+                } else {
+                    response.status.to_string()
+                },
+                Style::default().fg(status_code_color(response.status)),
+            ),
+        ]));
+
+        // i.e. HTTP/2.0 requested, downgraded to HTTP/1.1
+        let warning = if super::plain() { "[WARNING]" } else { "⚠" };
+        for text in &self.warnings {
+            lines.push(Line::styled(
+                format!("{warning} {text}"),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+
+        // Sent along, to be found in the server logs
+        if let Some((header, value)) = &self.request_id {
+            lines.push(Line::styled(
+                format!("{header}: {value}"),
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+
+        // Headers
+        // <KEY>: <VALUE>
+        for (k, v) in &response.headers {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{k}"), Style::default().fg(Color::Blue)),
+                ": ".into(),
+                v.to_str().unwrap().into(),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+
+        lines
+    }
+
+    // Empty line and trailers following the body, i.e. `grpc-status`: only
+    // gRPC responses have them
+    fn trailer_lines(&self) -> Vec<Line<'_>> {
+        let State::Received(response) = &self.state else {
+            return Vec::new();
+        };
+        if response.trailers.is_empty() {
+            return Vec::new();
+        }
+
+        let mut lines = vec![
+            Line::from(""),
+            Line::styled("Trailers", Style::default().fg(Color::DarkGray)),
+        ];
+        for (k, v) in &response.trailers {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{k}"), Style::default().fg(Color::Blue)),
+                ": ".into(),
+                String::from_utf8_lossy(v.as_bytes()).into_owned().into(),
+            ]));
+        }
 
         lines
     }
@@ -127,36 +644,52 @@ impl ResponsePanel {
     fn extension(&self) -> Option<String> {
         self.body()
             .ok()
-            .and_then(|payload| match payload {
-                Payload::Bytes(b) => b.extension,
-                Payload::Text(t) => t.extension,
-            })
-            .map(|s| ".".to_string() + s.as_str())
+            .and_then(|payload| payload.extension().map(|s| ".".to_string() + s))
     }
 
-    pub fn save_body(&self, file_name: &str) -> anyhow::Result<()> {
-        let to_save = match self.body()? {
-            Payload::Bytes(b) => b.bytes,
-            Payload::Text(t) => t.text.into(),
-        };
-
-        std::fs::write(file_name, to_save)?;
-
-        Event::emit(Event::Message(Message::Info(format!(
-            "Saved to {file_name}"
-        ))));
+    pub fn save_body(&self, file_name: String, events: &EventSender) -> anyhow::Result<()> {
+        let payload = self.body()?.clone();
+        match self.spilled().cloned() {
+            Some(spilled) => spawn_save(
+                file_name,
+                move |path, progress| {
+                    let file = std::fs::File::open(&spilled.file.0)?;
+                    write_chunked(path, file, spilled.size, progress)
+                },
+                events,
+            ),
+            None => spawn_save(
+                file_name,
+                move |path, progress| {
+                    let content = payload.content();
+                    write_chunked(path, content, content.len() as u64, progress)
+                },
+                events,
+            ),
+        }
 
         Ok(())
     }
 
-    pub fn save_all(&self, file_name: &str) -> anyhow::Result<()> {
-        let to_save = self.to_string()?;
-
-        std::fs::write(file_name, to_save)?;
+    // Temporary file of a body too big to be kept in memory
+    fn spilled_file(&self) -> Option<PathBuf> {
+        self.spilled().map(|spilled| spilled.file.0.clone())
+    }
 
-        Event::emit(Event::Message(Message::Info(format!(
-            "Saved to {file_name}"
-        ))));
+    pub fn save_all(
+        &self,
+        file_name: String,
+        redaction: Option<&Redaction>,
+        events: &EventSender,
+    ) -> anyhow::Result<()> {
+        let to_save = self.to_string(redaction)?;
+        spawn_save(
+            file_name,
+            move |path, progress| {
+                write_chunked(path, to_save.as_bytes(), to_save.len() as u64, progress)
+            },
+            events,
+        );
 
         Ok(())
     }
@@ -170,17 +703,32 @@ impl BlockComponent for ResponsePanel {
             ("Enter", "send request"),
             ("s", "save body"),
             ("S", "save all"),
-            ("t", "toggle raw bytes"),
+            ("t", "toggle raw"),
+            ("x", "toggle xml indentation"),
+            ("←/→ h/l", "scroll table left/right"),
+            ("r", "toggle html as text"),
+            ("e", "decode with encoding"),
+            ("o", "open large body"),
+            ("O", "open body too big to show externally"),
+            ("/ n", "search, next match"),
+            ("#", "toggle line numbers"),
+            ("d", "decode tokens"),
+            ("L", "links"),
+            ("f F", "follow location/next link, and save"),
         ]
         .as_slice()
     }
 
-    fn on_event(&mut self, key_event: crossterm::event::KeyEvent) -> HandleResult {
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> HandleResult {
         match key_event.code {
             KeyCode::Down | KeyCode::Char('j') => self.scroll_down(),
             KeyCode::Up | KeyCode::Char('k') => self.scroll_up(),
             KeyCode::Char('s') => {
-                Event::emit(Event::NewInput(
+                events.emit(Event::NewInput(
                     InputBuilder::new(InputType::FileName(SaveOption::Body))
                         .with_content(self.extension().unwrap_or_default())
                         .with_cursor(0),
@@ -188,7 +736,7 @@ impl BlockComponent for ResponsePanel {
             }
 
             KeyCode::Char('S') => {
-                Event::emit(Event::NewInput(
+                events.emit(Event::NewInput(
                     InputBuilder::new(InputType::FileName(SaveOption::All))
                         .with_content(self.extension().unwrap_or_default())
                         .with_cursor(0),
@@ -196,9 +744,85 @@ impl BlockComponent for ResponsePanel {
             }
             KeyCode::Char('t') => {
                 self.show_raw = !self.show_raw;
+                self.refresh_body();
+            }
+            KeyCode::Left | KeyCode::Char('h') if self.tabular().is_some() => {
+                self.hscroll = self.hscroll.saturating_sub(HSCROLL_STEP);
+            }
+            KeyCode::Right | KeyCode::Char('l') if self.tabular().is_some() => {
+                self.hscroll += HSCROLL_STEP;
+            }
+            KeyCode::Char('L') => {
+                let State::Received(response) = &self.state else {
+                    return Err(anyhow!("Request not sent"));
+                };
+                let links = response.links();
+                if links.is_empty() {
+                    return Err(anyhow!("No links found"));
+                }
+                events.emit(Event::ShowLinks(links));
+            }
+            KeyCode::Char(c @ ('f' | 'F')) => {
+                let State::Received(response) = &self.state else {
+                    return Err(anyhow!("Request not sent"));
+                };
+                let link = response
+                    .follow_link()
+                    .ok_or_else(|| anyhow!("No Location header or next link"))?;
+                events.emit(Event::NewRequest((link.url, c == 'F')));
+            }
+            KeyCode::Char('x') => {
+                self.raw_xml = !self.raw_xml;
+                self.scroll = 0;
+                self.refresh_body();
+            }
+            KeyCode::Char('r') => {
+                self.reader = !self.reader;
+                self.scroll = 0;
+                self.refresh_body();
+            }
+            KeyCode::Char('d') => {
+                if !self.has_response() {
+                    return Err(anyhow!("Request not sent"));
+                }
+                let tokens = self.decoded_tokens();
+                if tokens.is_empty() {
+                    return Err(anyhow!("Nothing to decode"));
+                }
+                events.emit(Event::ShowDecoded(tokens));
+            }
+            KeyCode::Char('#') => self.line_numbers = !self.line_numbers,
+            KeyCode::Char('O') => match self.spilled_file() {
+                Some(path) => events.emit(Event::OpenFile(path)),
+                None => return Err(anyhow!("The whole body is shown")),
+            },
+            KeyCode::Char('o') => {
+                self.opened = true;
+                self.refresh_body();
+            }
+            KeyCode::Char('/') => {
+                events.emit(Event::NewInput(
+                    InputBuilder::new(InputType::Search)
+                        .with_content(self.search.clone().unwrap_or_default()),
+                ));
+            }
+            KeyCode::Char('n') => {
+                if let Err(e) = self.search_next() {
+                    events.emit(Event::Message(Message::Error(e.to_string())));
+                }
             }
-            KeyCode::Enter => Event::emit(Event::SendRequest(self.idx)),
-            KeyCode::Esc => Event::emit(Event::Focus(FocusState::RequestsList)),
+            KeyCode::Char('e') => {
+                let charset = match self.body() {
+                    Ok(Payload::Text(t)) => t.charset.clone(),
+                    _ => String::new(),
+                };
+
+                events.emit(Event::NewInput(
+                    InputBuilder::new(InputType::Encoding).with_content(charset),
+                ));
+            }
+            KeyCode::Enter => events.emit(Event::SendRequest(self.idx)),
+            KeyCode::Esc => events.emit(Event::Focus(FocusState::RequestsList)),
             _ => return Ok(HandleSuccess::Ignored),
         };
 
@@ -211,80 +835,155 @@ impl BlockComponent for ResponsePanel {
         area: ratatui::prelude::Rect,
         block: ratatui::widgets::Block,
     ) {
+        let [paragraph_area, scrollbar_area] = {
+            let x = Layout::default()
+                .direction(ratatui::prelude::Direction::Horizontal)
+                .constraints([
+                    Constraint::Min(1),
+                    Constraint::Length(if super::plain() { 0 } else { 1 }),
+                ])
+                .split(block.inner(area));
+
+            [x[0], x[1]]
+        };
+        let width = paragraph_area.width as usize;
+        let height = paragraph_area.height as usize;
+        self.height.set(height);
+        let gutter = self.gutter_width();
+        let body_width = width.saturating_sub(gutter);
+
+        // Only the rows in the viewport are materialized
         let content = match &self.state {
-            State::Received(response) => {
-                let mut lines = vec![];
-
-                // First line
-                // <VERSION> <STATUS>
-                lines.push(Line::from(vec![
-                    response.version.clone().into(),
-                    " ".into(),
-                    Span::styled(
-                        response.status.to_string(),
-                        Style::default().fg(status_code_color(response.status)),
-                    ),
-                ]));
-
-                // Headers
-                // <KEY>: <VALUE>
-                for (k, v) in &response.headers {
-                    lines.push(Line::from(vec![
-                        Span::styled(format!("{k}"), Style::default().fg(Color::Blue)),
-                        ": ".into(),
-                        v.to_str().unwrap().into(),
-                    ]));
-                }
+            State::Received(_) => {
+                // Table rows aren't wrapped, but scrolled horizontally
+                let table = self.tabular().is_some();
+                let mut wrapped = self.wrapped.borrow_mut();
+                wrapped.set_width(self.lines(), if table { usize::MAX } else { body_width });
+
+                let header_lines = self
+                    .header_lines()
+                    .into_iter()
+                    .flat_map(|line| wrap::wrap_line(line, width))
+                    .collect::<Vec<_>>();
+                let trailer_lines = self
+                    .trailer_lines()
+                    .into_iter()
+                    .flat_map(|line| wrap::wrap_line(line, width))
+                    .collect::<Vec<_>>();
+                self.header_rows.set(header_lines.len());
+                self.total_rows
+                    .set(header_lines.len() + wrapped.rows().len() + trailer_lines.len());
+
+                let body_start = self.scroll.saturating_sub(header_lines.len());
+                // Tokens of the last line, shared by its wrapped rows
+                let mut tokens: Option<(usize, Tokens)> = None;
+                let body_lines = wrapped.rows()[body_start.min(wrapped.rows().len())..]
+                    .iter()
+                    .map(|(i, range)| {
+                        let line = self.line(*i);
+                        let mut row = match self.syntax {
+                            _ if *i > 0 && table => {
+                                let text = wrap::columns(line, self.hscroll, body_width);
+                                match i {
+                                    1 => Line::styled(
+                                        text,
+                                        Style::default().add_modifier(Modifier::BOLD),
+                                    ),
+                                    2 => Line::styled(text, Style::default().fg(Color::DarkGray)),
+                                    _ => Line::from(Span::raw(text)),
+                                }
+                            }
+                            _ if *i == 0 => Line::styled(
+                                &line[range.clone()],
+                                Style::default()
+                                    .add_modifier(Modifier::ITALIC.union(Modifier::UNDERLINED)),
+                            ),
+                            Some(syntax) => {
+                                if tokens.as_ref().is_none_or(|(last, _)| last != i) {
+                                    tokens = Some((*i, Tokens::new(syntax, line)));
+                                }
+                                let (_, tokens) = tokens.as_ref().unwrap();
+                                Line::from(tokens.spans(line, range.clone()))
+                            }
+                            None => Line::from(&line[range.clone()]),
+                        };
 
-                // Body
-                // with initial empty line
-                lines.push(Line::from(""));
-                lines.append(&mut self.render_body());
+                        // Numbered on their first row, not the title
+                        if gutter > 0 {
+                            let number = match (*i, range.start) {
+                                (1.., 0) => i.to_string(),
+                                _ => String::new(),
+                            };
+                            row.spans.insert(
+                                0,
+                                Span::styled(
+                                    format!("{number:>0$} ", gutter - 1),
+                                    Style::default().fg(Color::DarkGray),
+                                ),
+                            );
+                        }
+                        row
+                    });
 
+                let trailer_start = self
+                    .scroll
+                    .saturating_sub(header_lines.len() + wrapped.rows().len());
+                header_lines
+                    .into_iter()
+                    .skip(self.scroll)
+                    .chain(body_lines)
+                    .chain(trailer_lines.into_iter().skip(trailer_start))
+                    .take(height)
+                    .collect::<Vec<_>>()
+            }
+            State::Empty => {
+                self.total_rows.set(1);
+                vec![Line::styled(
+                    "Empty",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::ITALIC),
+                )]
+            }
+            State::Loading => {
+                let mut lines = vec![Line::styled(
+                    "Loading...",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::ITALIC),
+                )];
+                if let Some((sent, total)) = self.upload {
+                    lines.push(Line::from(upload_bar(sent, total, width)));
+                }
+                self.total_rows.set(lines.len());
                 lines
             }
-            State::Empty => vec![Line::styled(
-                "Empty",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::ITALIC),
-            )],
-            State::Loading => vec![Line::styled(
-                "Loading...",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::ITALIC),
-            )],
         };
 
-        let content_length = content
-            .iter()
-            .map(|line| (line.width() / (block.inner(area).width) as usize) + 1)
-            .sum::<usize>();
-
-        let [paragraph_area, scrollbar_area] = {
-            let x = Layout::default()
-                .direction(ratatui::prelude::Direction::Horizontal)
-                .constraints([Constraint::Min(1), Constraint::Length(1)])
-                .split(block.inner(area));
-
-            [x[0], x[1]]
+        // The scrollbar only takes u16, so very long bodies are scaled down
+        let total_rows = self.total_rows.get();
+        let scale = |n: usize| match u16::try_from(total_rows) {
+            Ok(_) => n as u16,
+            Err(_) => (n as u64 * u16::MAX as u64 / total_rows as u64) as u16,
         };
 
-        let paragraph = Paragraph::new(content)
-            .wrap(Wrap { trim: false })
-            .scroll((self.scroll, 0));
-
-        frame.render_widget(paragraph, paragraph_area);
-        frame.render_stateful_widget(
-            Scrollbar::default().orientation(ratatui::widgets::ScrollbarOrientation::VerticalRight),
-            scrollbar_area,
-            &mut ScrollbarState::default()
-                .position(self.scroll)
-                .content_length(content_length as u16)
-                .viewport_content_length(block.inner(area).height),
-        );
-        frame.render_widget(block, area);
+        frame.render_widget(Paragraph::new(content), paragraph_area);
+        if !super::plain() {
+            frame.render_stateful_widget(
+                Scrollbar::default()
+                    .orientation(ratatui::widgets::ScrollbarOrientation::VerticalRight),
+                scrollbar_area,
+                &mut ScrollbarState::default()
+                    .position(scale(self.scroll))
+                    .content_length(scale(total_rows))
+                    .viewport_content_length(scale(height)),
+            );
+        }
+        if self.summary.is_empty() {
+            frame.render_widget(block, area);
+        } else {
+            frame.render_widget(block.title(format!(" {} ", self.summary)), area);
+        }
     }
 }
 
@@ -299,3 +998,132 @@ fn status_code_color(status_code: StatusCode) -> Color {
         Color::default()
     }
 }
+
+// i.e. `uploaded [████░░░░] 50% 1.0 MiB / 2.0 MiB`
+fn upload_bar(sent: u64, total: u64, width: usize) -> String {
+    const BAR_WIDTH: usize = 30;
+
+    let ratio = sent as f64 / total.max(1) as f64;
+    let text = format!(
+        "{:.0}% {} / {}",
+        ratio * 100.0,
+        human_size(sent as usize),
+        human_size(total as usize)
+    );
+    if super::plain() {
+        return format!("uploaded {text}");
+    }
+
+    let bar_width = BAR_WIDTH.min(width.saturating_sub(text.len() + "uploaded [] ".len()));
+    let filled = (ratio * bar_width as f64) as usize;
+
+    format!(
+        "uploaded [{}{}] {text}",
+        "█".repeat(filled),
+        "░".repeat(bar_width - filled)
+    )
+}
+
+// Runs `write` from a blocking task, with the name of the file and the
+// sender of its progress, emitting whether it worked
+fn spawn_save(
+    file_name: String,
+    write: impl FnOnce(&str, &EventSender) -> std::io::Result<u64> + Send + 'static,
+    events: &EventSender,
+) {
+    let progress = events.clone();
+
+    events.spawn(async move {
+        let path = file_name.clone();
+        let result = tokio::task::spawn_blocking(move || write(&path, &progress)).await?;
+
+        Ok(Event::SaveDone((
+            file_name,
+            result.map_err(|e| e.to_string()),
+        )))
+    });
+}
+
+// Copies the `total` bytes of `content` to `file_name`, emitting the progress
+// of big ones
+fn write_chunked(
+    file_name: &str,
+    mut content: impl std::io::Read,
+    total: u64,
+    events: &EventSender,
+) -> std::io::Result<u64> {
+    use std::io::Write;
+
+    let mut file = std::fs::File::create(file_name)?;
+    let mut chunk = vec![0; SAVE_CHUNK.min(total as usize)];
+    let mut written = 0;
+
+    loop {
+        let read = content.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&chunk[..read])?;
+        written += read as u64;
+        if written < total {
+            events.emit(Event::SaveProgress((file_name.to_owned(), written, total)));
+        }
+    }
+    file.flush()?;
+
+    Ok(written)
+}
+
+// Head and tail of a body too big to be kept in memory, the bytes between
+// them skipped
+fn spilled_view(spilled: &Spilled, head: &Payload, show_raw: bool) -> (String, Arc<str>) {
+    let title = format!(
+        "{} body, too big to keep in memory — press O to open it externally:",
+        human_size(spilled.size as usize)
+    );
+    if let (Payload::Bytes(_), false) = (head, show_raw) {
+        return (title, "raw bytes".into());
+    }
+
+    let skipped = spilled.size as usize - head.size() - spilled.tail.len();
+    let text = format!(
+        "{}\n… {} not shown …\n{}",
+        head.as_text_lossy(),
+        human_size(skipped),
+        String::from_utf8_lossy(&spilled.tail)
+    );
+
+    (title, text.into())
+}
+
+// i.e. `2 341`
+fn group_digits(n: usize) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(' ');
+        }
+        grouped.push(c);
+    }
+
+    grouped
+}
+
+/// i.e. `1.2 MiB`
+pub fn human_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
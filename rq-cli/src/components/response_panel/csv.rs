@@ -0,0 +1,118 @@
+//! Layout of CSV and TSV bodies as a table: a header row, a separator and
+//! the records, with numeric columns aligned to the right.
+
+use unicode_width::UnicodeWidthStr;
+
+const SEPARATOR: &str = " │ ";
+
+/// Table of the `delimiter`-separated `text`, `None` if it has no records.
+pub fn table(text: &str, delimiter: char) -> Option<String> {
+    let records = records(text, delimiter);
+    let columns = records.iter().map(Vec::len).max().filter(|&n| n > 0)?;
+
+    let mut widths = vec![0; columns];
+    // Every value but the header is a number
+    let mut numeric = vec![true; columns];
+    for (i, record) in records.iter().enumerate() {
+        for (column, value) in record.iter().enumerate() {
+            widths[column] = widths[column].max(value.width());
+            if i > 0 && !value.is_empty() && value.parse::<f64>().is_err() {
+                numeric[column] = false;
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (i, record) in records.iter().enumerate() {
+        let cells = (0..columns).map(|column| {
+            let value = record.get(column).map_or("", String::as_str);
+            let padding = " ".repeat(widths[column] - value.width());
+            if numeric[column] && i > 0 {
+                format!("{padding}{value}")
+            } else {
+                format!("{value}{padding}")
+            }
+        });
+        out.push_str(cells.collect::<Vec<_>>().join(SEPARATOR).trim_end());
+        out.push('\n');
+
+        if i == 0 {
+            let rules = widths.iter().map(|&width| "─".repeat(width));
+            out.push_str(&rules.collect::<Vec<_>>().join("─┼─"));
+            out.push('\n');
+        }
+    }
+
+    Some(out)
+}
+
+// RFC 4180 records, line breaks in quoted values being shown as `↵`
+fn records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, '\r') => (),
+            (true, '\n') => value.push('↵'),
+            (true, c) => value.push(c),
+            (false, '"') if value.is_empty() => quoted = true,
+            (false, c) if c == delimiter => record.push(std::mem::take(&mut value)),
+            (false, '\r') => (),
+            (false, '\n') => {
+                record.push(std::mem::take(&mut value));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) => value.push(c),
+        }
+    }
+
+    if !value.is_empty() || !record.is_empty() {
+        record.push(value);
+        records.push(record);
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{records, table};
+
+    #[test]
+    fn test_records() {
+        assert_eq!(
+            records("\"a,b\",\"say \"\"hi\"\"\"\nc,\n", ','),
+            [vec!["a,b", "say \"hi\""], vec!["c", ""]]
+        );
+        assert_eq!(records("\"x\r\ny\",z\r\n", ','), [vec!["x↵y", "z"]]);
+        assert_eq!(records("a\tb", '\t'), [vec!["a", "b"]]);
+        // Quotes only open a value at its start
+        assert_eq!(records("a\"b,c", ','), [vec!["a\"b", "c"]]);
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            table("name,age\nbob,3\nalice,12\n", ',').unwrap(),
+            "name  │ age\n──────┼────\nbob   │   3\nalice │  12\n"
+        );
+        assert!(table("", ',').is_none());
+    }
+
+    #[test]
+    fn test_ragged_table() {
+        assert_eq!(
+            table("a,b,c\n1\n2,3\n", ',').unwrap(),
+            "a │ b │ c\n──┼───┼──\n1 │   │\n2 │ 3 │\n"
+        );
+    }
+}
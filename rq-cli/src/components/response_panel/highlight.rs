@@ -0,0 +1,594 @@
+//! Syntax highlighting of markup, stylesheets, scripts and JSON bodies,
+//! keyed off the extension of the payload.
+//!
+//! Lines are tokenized on their own, so that only the ones in the viewport
+//! need to be: constructs spanning several lines, i.e. block comments, are
+//! only highlighted on their first line.
+
+use std::{
+    ops::Range,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use anyhow::anyhow;
+use ratatui::{
+    style::{Color, Style},
+    text::Span,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Theme {
+    Dark,
+    Light,
+    // Plain text
+    None,
+}
+
+impl FromStr for Theme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            "none" => Ok(Theme::None),
+            _ => Err(anyhow!("unknown theme '{s}', expected dark, light or none")),
+        }
+    }
+}
+
+static THEME: AtomicU8 = AtomicU8::new(Theme::Dark as u8);
+
+pub fn set_theme(theme: Theme) {
+    THEME.store(theme as u8, Ordering::Relaxed);
+}
+
+fn theme() -> Theme {
+    match THEME.load(Ordering::Relaxed) {
+        0 => Theme::Dark,
+        1 => Theme::Light,
+        _ => Theme::None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Syntax {
+    // HTML and XML
+    Markup,
+    Css,
+    Js,
+    Json,
+}
+
+impl Syntax {
+    /// Syntax of the bodies with `extension`, `None` when it has no
+    /// highlighting or the theme is `none`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        if theme() == Theme::None {
+            return None;
+        }
+
+        match extension {
+            "html" | "htm" | "xhtml" | "xml" | "svg" => Some(Syntax::Markup),
+            "css" => Some(Syntax::Css),
+            "js" | "mjs" => Some(Syntax::Js),
+            "json" => Some(Syntax::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Kind {
+    Tag,
+    Attribute,
+    String,
+    Comment,
+    Keyword,
+    Number,
+}
+
+impl Kind {
+    fn style(self) -> Style {
+        let color = match (theme(), self) {
+            (_, Kind::Tag) => Color::Blue,
+            (_, Kind::Comment) => Color::DarkGray,
+            (Theme::Light, Kind::Attribute) => Color::Red,
+            (Theme::Light, Kind::String) => Color::Green,
+            (Theme::Light, Kind::Keyword) => Color::Magenta,
+            (Theme::Light, Kind::Number) => Color::Cyan,
+            (_, Kind::Attribute) => Color::Cyan,
+            (_, Kind::String) => Color::LightGreen,
+            (_, Kind::Keyword) => Color::LightMagenta,
+            (_, Kind::Number) => Color::Yellow,
+        };
+
+        Style::default().fg(color)
+    }
+}
+
+/// Highlighted tokens of a line, as sorted byte ranges.
+pub struct Tokens(Vec<(Range<usize>, Kind)>);
+
+impl Tokens {
+    pub fn new(syntax: Syntax, line: &str) -> Self {
+        let mut scanner = Scanner {
+            bytes: line.as_bytes(),
+            pos: 0,
+            tokens: Vec::new(),
+        };
+
+        match syntax {
+            Syntax::Markup => scanner.markup(),
+            Syntax::Css => scanner.css(),
+            Syntax::Js => scanner.js(),
+            Syntax::Json => scanner.json(),
+        }
+
+        Tokens(scanner.tokens)
+    }
+
+    /// Spans of `line[range]`, i.e. a wrapped row of the line.
+    pub fn spans<'a>(&self, line: &'a str, range: Range<usize>) -> Vec<Span<'a>> {
+        let mut spans = Vec::new();
+        let mut pos = range.start;
+
+        for (token, kind) in &self.0 {
+            if token.end <= pos {
+                continue;
+            }
+            if token.start >= range.end {
+                break;
+            }
+
+            let start = token.start.max(pos);
+            let end = token.end.min(range.end);
+            if start > pos {
+                spans.push(Span::raw(&line[pos..start]));
+            }
+            spans.push(Span::styled(&line[start..end], kind.style()));
+            pos = end;
+        }
+
+        if pos < range.end {
+            spans.push(Span::raw(&line[pos..range.end]));
+        }
+
+        spans
+    }
+}
+
+const JS_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "null",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+// Only splits at ASCII bytes, so that every range is on a char boundary
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    tokens: Vec<(Range<usize>, Kind)>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.bytes[self.pos..].starts_with(prefix.as_bytes())
+    }
+
+    fn push(&mut self, start: usize, kind: Kind) {
+        if self.pos > start {
+            self.tokens.push((start..self.pos, kind));
+        }
+    }
+
+    // Up to and including `end`, or to the end of the line
+    fn until(&mut self, end: &str) {
+        match find(&self.bytes[self.pos..], end.as_bytes()) {
+            Some(i) => self.pos += i + end.len(),
+            None => self.pos = self.bytes.len(),
+        }
+    }
+
+    fn while_ident(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|b| !b.is_ascii() || b.is_ascii_alphanumeric() || b"_-$:.".contains(&b))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn string(&mut self, quote: u8) {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'\\' => self.pos = (self.pos + 1).min(self.bytes.len()),
+                b if b == quote => break,
+                _ => (),
+            }
+        }
+        self.push(start, Kind::String);
+    }
+
+    // Including a leading sign
+    fn number(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'.')
+        {
+            self.pos += 1;
+        }
+        self.push(start, Kind::Number);
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn markup(&mut self) {
+        while let Some(b) = self.peek() {
+            if self.starts_with("<!--") {
+                let start = self.pos;
+                self.until("-->");
+                self.push(start, Kind::Comment);
+            } else if b == b'<' {
+                self.tag();
+            } else if b == b'&' {
+                // Entities, i.e. `&amp;`
+                let start = self.pos;
+                self.pos += 1;
+                while self
+                    .peek()
+                    .is_some_and(|b| b.is_ascii_alphanumeric() || b == b'#')
+                {
+                    self.pos += 1;
+                }
+                if self.peek() == Some(b';') {
+                    self.pos += 1;
+                    self.push(start, Kind::Keyword);
+                }
+            } else {
+                self.pos += 1;
+            }
+        }
+    }
+
+    // `<name attr="value">`, `</name>`, `<?xml ...?>` or `<!DOCTYPE ...>`
+    fn tag(&mut self) {
+        let start = self.pos;
+        self.pos += 1;
+        if self.peek().is_some_and(|b| b"/?!".contains(&b)) {
+            self.pos += 1;
+        }
+        self.while_ident();
+        self.push(start, Kind::Tag);
+
+        while let Some(b) = self.peek() {
+            match b {
+                b'>' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    self.push(start, Kind::Tag);
+                    return;
+                }
+                b'/' | b'?' if self.bytes.get(self.pos + 1) == Some(&b'>') => {
+                    let start = self.pos;
+                    self.pos += 2;
+                    self.push(start, Kind::Tag);
+                    return;
+                }
+                b'"' | b'\'' => self.string(b),
+                b if b.is_ascii_alphabetic() || !b.is_ascii() => {
+                    let start = self.pos;
+                    self.while_ident();
+                    self.push(start, Kind::Attribute);
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn css(&mut self) {
+        // Lines opening a block hold selectors, the others declarations
+        let selectors = self.bytes.contains(&b'{');
+
+        while let Some(b) = self.peek() {
+            match b {
+                b'/' if self.starts_with("/*") => {
+                    let start = self.pos;
+                    self.until("*/");
+                    self.push(start, Kind::Comment);
+                }
+                b'"' | b'\'' => self.string(b),
+                b'@' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    self.while_ident();
+                    self.push(start, Kind::Keyword);
+                }
+                b'#' if !selectors => {
+                    // Colors
+                    let start = self.pos;
+                    self.pos += 1;
+                    self.while_ident();
+                    self.push(start, Kind::Number);
+                }
+                b'-' | b'.' if self.bytes.get(self.pos + 1).is_some_and(u8::is_ascii_digit) => {
+                    self.number()
+                }
+                b if b.is_ascii_digit() => self.number(),
+                b if b.is_ascii_alphabetic() || b == b'-' || b == b'.' || b == b'#' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    while self.peek().is_some_and(|b| {
+                        !b.is_ascii() || b.is_ascii_alphanumeric() || b"_-".contains(&b)
+                    }) {
+                        self.pos += 1;
+                    }
+
+                    let end = self.pos;
+                    self.skip_whitespace();
+                    let property = !selectors && self.peek() == Some(b':');
+                    self.pos = end;
+
+                    if selectors {
+                        self.push(start, Kind::Tag);
+                    } else if property {
+                        self.push(start, Kind::Attribute);
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn js(&mut self) {
+        while let Some(b) = self.peek() {
+            match b {
+                b'/' if self.starts_with("//") => {
+                    let start = self.pos;
+                    self.pos = self.bytes.len();
+                    self.push(start, Kind::Comment);
+                }
+                b'/' if self.starts_with("/*") => {
+                    let start = self.pos;
+                    self.until("*/");
+                    self.push(start, Kind::Comment);
+                }
+                b'"' | b'\'' | b'`' => self.string(b),
+                b if b.is_ascii_digit() => self.number(),
+                b if b.is_ascii_alphabetic() || b == b'_' || b == b'$' || !b.is_ascii() => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|b| {
+                        !b.is_ascii() || b.is_ascii_alphanumeric() || b"_$".contains(&b)
+                    }) {
+                        self.pos += 1;
+                    }
+
+                    let word = &self.bytes[start..self.pos];
+                    if JS_KEYWORDS.iter().any(|k| k.as_bytes() == word) {
+                        self.push(start, Kind::Keyword);
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn json(&mut self) {
+        while let Some(b) = self.peek() {
+            match b {
+                b'"' => {
+                    self.string(b);
+
+                    // Object keys are followed by a colon
+                    let end = self.pos;
+                    self.skip_whitespace();
+                    if self.peek() == Some(b':') {
+                        self.tokens.last_mut().unwrap().1 = Kind::Attribute;
+                    }
+                    self.pos = end;
+                }
+                b'-' | b'0'..=b'9' => self.number(),
+                b't' | b'f' | b'n' => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|b| b.is_ascii_alphabetic()) {
+                        self.pos += 1;
+                    }
+                    let word = &self.bytes[start..self.pos];
+                    if [&b"true"[..], b"false", b"null"].contains(&word) {
+                        self.push(start, Kind::Keyword);
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Kind, Syntax, Tokens};
+
+    // Text of each token of `line`, with its kind
+    fn tokens(syntax: Syntax, line: &str) -> Vec<(&str, Kind)> {
+        Tokens::new(syntax, line)
+            .0
+            .into_iter()
+            .map(|(range, kind)| (&line[range], kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_markup() {
+        assert_eq!(
+            tokens(Syntax::Markup, r#"<a href="/é">&amp; ü</a><br/>"#),
+            [
+                ("<a", Kind::Tag),
+                ("href", Kind::Attribute),
+                (r#""/é""#, Kind::String),
+                (">", Kind::Tag),
+                ("&amp;", Kind::Keyword),
+                ("</a", Kind::Tag),
+                (">", Kind::Tag),
+                ("<br", Kind::Tag),
+                ("/>", Kind::Tag),
+            ]
+        );
+        assert_eq!(
+            tokens(Syntax::Markup, "<!-- ß --> <?xml"),
+            [("<!-- ß -->", Kind::Comment), ("<?xml", Kind::Tag)]
+        );
+
+        // Unterminated, up to the end of the line
+        assert_eq!(
+            tokens(Syntax::Markup, "<!-- never closed"),
+            [("<!-- never closed", Kind::Comment)]
+        );
+        assert_eq!(
+            tokens(Syntax::Markup, r#"<p title="open"#),
+            [
+                ("<p", Kind::Tag),
+                ("title", Kind::Attribute),
+                (r#""open"#, Kind::String)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_css() {
+        assert_eq!(
+            tokens(Syntax::Css, "@media screen { .nav > a {"),
+            [
+                ("@media", Kind::Keyword),
+                ("screen", Kind::Tag),
+                (".nav", Kind::Tag),
+                ("a", Kind::Tag),
+            ]
+        );
+        assert_eq!(
+            tokens(
+                Syntax::Css,
+                "  color: #fff; margin: -1.5em; content: \"→\";"
+            ),
+            [
+                ("color", Kind::Attribute),
+                ("#fff", Kind::Number),
+                ("margin", Kind::Attribute),
+                ("-1.5em", Kind::Number),
+                ("content", Kind::Attribute),
+                ("\"→\"", Kind::String),
+            ]
+        );
+        assert_eq!(tokens(Syntax::Css, "/* ü"), [("/* ü", Kind::Comment)]);
+    }
+
+    #[test]
+    fn test_js() {
+        assert_eq!(
+            tokens(Syntax::Js, "const ñ = await f('a\\'b', 42); // done"),
+            [
+                ("const", Kind::Keyword),
+                ("await", Kind::Keyword),
+                ("'a\\'b'", Kind::String),
+                ("42", Kind::Number),
+                ("// done", Kind::Comment),
+            ]
+        );
+        // Keywords are whole words only
+        assert!(tokens(Syntax::Js, "letter constant").is_empty());
+        assert_eq!(
+            tokens(Syntax::Js, "x = `unterminated ü"),
+            [("`unterminated ü", Kind::String)]
+        );
+        assert_eq!(tokens(Syntax::Js, "/* open"), [("/* open", Kind::Comment)]);
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            tokens(
+                Syntax::Json,
+                r#"{"ключ" : [-1.5e3, true, null, "v"], "n": nope}"#
+            ),
+            [
+                (r#""ключ""#, Kind::Attribute),
+                ("-1.5e3", Kind::Number),
+                ("true", Kind::Keyword),
+                ("null", Kind::Keyword),
+                (r#""v""#, Kind::String),
+                (r#""n""#, Kind::Attribute),
+            ]
+        );
+        // A trailing backslash doesn't run past the line
+        assert_eq!(tokens(Syntax::Json, r#""a\"#), [(r#""a\"#, Kind::String)]);
+    }
+
+    #[test]
+    fn test_spans() {
+        let line = r#"{"é": 1}"#;
+        let tokens = Tokens::new(Syntax::Json, line);
+
+        // A wrapped row cutting through the key
+        let spans = tokens.spans(line, 0..4);
+        let text = spans.iter().map(|span| &*span.content).collect::<Vec<_>>();
+        assert_eq!(text, ["{", "\"é"]);
+
+        let spans = tokens.spans(line, 4..line.len());
+        let text = spans.iter().map(|span| &*span.content).collect::<Vec<_>>();
+        assert_eq!(text, ["\"", ": ", "1", "}"]);
+    }
+}
@@ -0,0 +1,375 @@
+//! Conversion of HTML to formatted plain text, à la lynx: headings, lists
+//! and paragraphs are laid out, links numbered and listed at the end.
+
+/// Formatted text of the `html` document.
+pub fn to_text(html: &str) -> String {
+    let mut reader = Reader::default();
+    let mut rest = html;
+
+    while let Some(i) = rest.find('<') {
+        reader.text(&rest[..i]);
+        rest = &rest[i..];
+
+        rest = if let Some(comment) = rest.strip_prefix("<!--") {
+            after(comment, "-->")
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            after(rest, ">")
+        } else {
+            let end = rest.find('>').unwrap_or(rest.len());
+            let tag = &rest[1..end];
+            rest = rest.get(end + 1..).unwrap_or_default();
+
+            match tag.strip_prefix('/') {
+                Some(name) => reader.close(&tag_name(name)),
+                None => {
+                    let name = tag_name(tag);
+                    // Their content isn't text
+                    if ["script", "style", "template"].contains(&name.as_str()) {
+                        rest = after_ignore_case(rest, &format!("</{name}"));
+                        rest = after(rest, ">");
+                    } else {
+                        reader.open(&name, tag);
+                    }
+                }
+            }
+
+            rest
+        };
+    }
+    reader.text(rest);
+
+    reader.finish()
+}
+
+#[derive(Default)]
+struct Reader {
+    out: String,
+    // Whitespace was collapsed before the next text
+    space: bool,
+    pre: usize,
+    // Item counters of the open lists, `None` for unordered ones
+    lists: Vec<Option<usize>>,
+    // Target of the open link
+    href: Option<String>,
+    links: Vec<String>,
+    first_cell: bool,
+}
+
+impl Reader {
+    fn text(&mut self, text: &str) {
+        let text = decode_entities(text);
+
+        if self.pre > 0 {
+            self.out.push_str(&text);
+            return;
+        }
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+                continue;
+            }
+
+            if self.space && !self.at_line_start() {
+                self.out.push(' ');
+            }
+            self.space = false;
+            self.out.push(c);
+        }
+    }
+
+    fn open(&mut self, name: &str, tag: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.blank_line();
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            "p" | "blockquote" | "table" | "title" => self.blank_line(),
+            "ul" => {
+                self.newline();
+                self.lists.push(None);
+            }
+            "ol" => {
+                self.newline();
+                self.lists.push(Some(0));
+            }
+            "li" => {
+                self.newline();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        self.out.push_str(&format!("{n}. "));
+                    }
+                    _ => self.out.push_str("• "),
+                }
+            }
+            "dt" | "dd" => {
+                self.newline();
+                if name == "dd" {
+                    self.out.push_str("  ");
+                }
+            }
+            "br" => {
+                self.out.push('\n');
+                self.space = false;
+            }
+            "hr" => {
+                self.newline();
+                self.out.push_str(&"─".repeat(40));
+                self.out.push('\n');
+            }
+            "pre" => {
+                self.blank_line();
+                self.pre += 1;
+            }
+            "tr" => {
+                self.newline();
+                self.first_cell = true;
+            }
+            "td" | "th" => {
+                if !self.first_cell {
+                    self.out.push_str(" | ");
+                }
+                self.first_cell = false;
+                self.space = false;
+            }
+            "a" => self.href = attribute(tag, "href"),
+            "img" => {
+                if let Some(alt) = attribute(tag, "alt").filter(|alt| !alt.is_empty()) {
+                    self.text(&format!(" [{alt}] "));
+                }
+            }
+            name if is_block(name) => self.newline(),
+            _ => (),
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "blockquote" | "table" | "title" => {
+                self.blank_line()
+            }
+            "ul" | "ol" => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            "pre" => {
+                self.pre = self.pre.saturating_sub(1);
+                self.blank_line();
+            }
+            "a" => {
+                let Some(href) = self.href.take() else {
+                    return;
+                };
+                if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+                    return;
+                }
+
+                self.links.push(href);
+                self.out.push_str(&format!("[{}]", self.links.len()));
+            }
+            name if is_block(name) => self.newline(),
+            _ => (),
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with(' ')
+    }
+
+    fn newline(&mut self) {
+        self.space = false;
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut out = self.out.trim_end().to_owned();
+
+        if !self.links.is_empty() {
+            out.push_str("\n\nReferences\n");
+            for (i, link) in self.links.iter().enumerate() {
+                out.push_str(&format!("\n[{}] {link}", i + 1));
+            }
+        }
+
+        out
+    }
+}
+
+fn is_block(name: &str) -> bool {
+    [
+        "address", "article", "aside", "body", "caption", "dl", "div", "fieldset", "figure",
+        "footer", "form", "header", "main", "nav", "section",
+    ]
+    .contains(&name)
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+// Value of the attribute `name`, quoted or not
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+
+    while let Some(i) = lower[from..].find(name) {
+        let start = from + i;
+        from = start + name.len();
+
+        let preceded = lower[..start].ends_with(|c: char| c.is_ascii_whitespace());
+        let value = lower[from..].trim_start();
+        if !preceded || !value.starts_with('=') {
+            continue;
+        }
+
+        let value = tag[tag.len() - value.len() + 1..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value
+                .split(|c: char| c.is_ascii_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default(),
+        };
+
+        return Some(decode_entities(value));
+    }
+
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((entity(&rest[1..end + 1])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+fn entity(name: &str) -> Option<char> {
+    if let Some(code) = name.strip_prefix('#') {
+        let code = match code.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => code.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "mdash" => '—',
+        "ndash" => '–',
+        "hellip" => '…',
+        "laquo" => '«',
+        "raquo" => '»',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "middot" => '·',
+        "bull" => '•',
+        _ => return None,
+    })
+}
+
+fn after<'a>(s: &'a str, pattern: &str) -> &'a str {
+    s.find(pattern).map_or("", |i| &s[i + pattern.len()..])
+}
+
+fn after_ignore_case<'a>(s: &'a str, pattern: &str) -> &'a str {
+    s.as_bytes()
+        .windows(pattern.len())
+        .position(|w| w.eq_ignore_ascii_case(pattern.as_bytes()))
+        .map_or("", |i| &s[i + pattern.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_entities, to_text};
+
+    #[test]
+    fn test_entities() {
+        assert_eq!(
+            decode_entities("a &amp; b &lt;c&gt; &#233;&#x2014;&quot;"),
+            "a & b <c> é—\""
+        );
+        // Unknown or unterminated ones are kept
+        assert_eq!(decode_entities("&bogus; & &amp"), "&bogus; & &amp");
+        assert_eq!(
+            to_text(r#"<p>Tom &amp; Jerry</p><a href="/a?x=1&amp;y=2">link</a>"#),
+            "Tom & Jerry\n\nlink[1]\n\nReferences\n\n[1] /a?x=1&y=2"
+        );
+    }
+
+    #[test]
+    fn test_pre() {
+        assert_eq!(
+            to_text("<p>a   b</p><pre>  x\n    y &lt;z&gt;</pre><p>c</p>"),
+            "a b\n\n  x\n    y <z>\n\nc"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let html =
+            "<ul><li>one<ol><li>first</li><li>second</li></ol></li><li>two</li></ul><p>end</p>";
+        assert_eq!(
+            to_text(html),
+            "• one\n  1. first\n  2. second\n• two\n\nend"
+        );
+    }
+
+    #[test]
+    fn test_stripped() {
+        let html = r#"<html><head><title>Tïtle</title><style>p { color: red }</style>
+            <SCRIPT>if (a < b) { alert("<p>") }</SCRIPT></head>
+            <body><!-- <p>hidden</p> --><h2>Head</h2>text</body></html>"#;
+        assert_eq!(to_text(html), "Tïtle\n\n## Head\n\ntext");
+    }
+}
@@ -0,0 +1,149 @@
+//! Hard wrapping of lines at a given display width, so that only the rows in
+//! the viewport need to be materialized.
+
+use std::{borrow::Cow, ops::Range};
+
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthChar;
+
+/// Rows of the body, as `(line index, byte range in the line)`, cached for
+/// the last width they were computed at.
+#[derive(Default)]
+pub struct WrapCache {
+    width: usize,
+    rows: Vec<(usize, Range<usize>)>,
+}
+
+impl WrapCache {
+    pub fn rows(&self) -> &[(usize, Range<usize>)] {
+        &self.rows
+    }
+
+    /// Wraps `lines` again if `width` changed.
+    pub fn set_width<'a>(&mut self, lines: impl Iterator<Item = &'a str>, width: usize) {
+        if width != self.width {
+            self.width = width;
+            self.rebuild(lines);
+        }
+    }
+
+    /// Wraps `lines` at the current width, if one was set.
+    pub fn rebuild<'a>(&mut self, lines: impl Iterator<Item = &'a str>) {
+        self.rows.clear();
+        if self.width == 0 {
+            return;
+        }
+
+        for (i, line) in lines.enumerate() {
+            self.rows
+                .extend(rows(line, self.width).into_iter().map(|range| (i, range)));
+        }
+    }
+
+    /// Index of the first row of line `line`.
+    pub fn first_row_of(&self, line: usize) -> usize {
+        self.rows.partition_point(|(i, _)| *i < line)
+    }
+}
+
+/// Byte ranges of the rows `line` wraps into, at least one.
+fn rows(line: &str, width: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut row_width = 0;
+
+    for (i, c) in line.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width && i > start {
+            rows.push(start..i);
+            start = i;
+            row_width = 0;
+        }
+        row_width += char_width;
+    }
+    rows.push(start..line.len());
+
+    rows
+}
+
+/// Wraps a styled line, keeping the style of each span.
+pub fn wrap_line(line: Line<'_>, width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut current = Vec::new();
+    let mut row_width = 0;
+
+    for span in line.spans {
+        let mut text = String::new();
+
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if row_width + char_width > width && row_width > 0 {
+                if !text.is_empty() {
+                    current.push(Span::styled(std::mem::take(&mut text), span.style));
+                }
+                lines.push(Line::from(std::mem::take(&mut current)));
+                row_width = 0;
+            }
+            text.push(c);
+            row_width += char_width;
+        }
+
+        if !text.is_empty() {
+            current.push(Span::styled(text, span.style));
+        }
+    }
+    lines.push(Line::from(current));
+
+    lines
+}
+
+/// Part of `line` displayed in the columns `from..from + width`, i.e. when
+/// scrolled horizontally. A wide character cut at `from` is replaced by a
+/// space, keeping the columns of what follows in place.
+pub fn columns(line: &str, from: usize, width: usize) -> Cow<'_, str> {
+    let mut start = line.len();
+    let mut end = line.len();
+    let mut column = 0;
+    let mut cut = false;
+
+    for (i, c) in line.char_indices() {
+        if column >= from && start == line.len() {
+            start = i;
+            cut = column > from;
+        }
+        column += c.width().unwrap_or(0);
+        if column > from + width {
+            end = i;
+            break;
+        }
+    }
+
+    let text = &line[start.min(end)..end];
+    if cut {
+        Cow::Owned(format!(" {text}"))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::columns;
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(columns("abcdef", 0, 3), "abc");
+        assert_eq!(columns("abcdef", 2, 3), "cde");
+        assert_eq!(columns("abcdef", 4, 10), "ef");
+        assert_eq!(columns("abc", 5, 3), "");
+    }
+
+    #[test]
+    fn test_wide_columns() {
+        // Cut at its second column
+        assert_eq!(columns("a中b", 2, 3), " b");
+        // Not fitting in the width
+        assert_eq!(columns("ab中", 0, 3), "ab");
+        assert_eq!(columns("中文", 0, 4), "中文");
+    }
+}
@@ -0,0 +1,147 @@
+//! Indentation of XML documents, one node per line. Elements containing only
+//! text are kept on a single line.
+
+enum Node<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    // Self-closing tags, declarations, comments and CDATA sections
+    Empty(&'a str),
+    Text(&'a str),
+}
+
+/// Indented `xml`, `None` if it isn't well-formed enough to be.
+pub fn format(xml: &str) -> Option<String> {
+    let nodes = nodes(xml)?;
+    let mut out = String::with_capacity(xml.len());
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    let mut line = |depth: usize, parts: &[&str]| {
+        out.push_str(&"  ".repeat(depth));
+        parts.iter().for_each(|part| out.push_str(part));
+        out.push('\n');
+    };
+
+    while i < nodes.len() {
+        match (&nodes[i], nodes.get(i + 1), nodes.get(i + 2)) {
+            (Node::Open(open), Some(Node::Text(text)), Some(Node::Close(close))) => {
+                line(depth, &[open, text.trim(), close]);
+                i += 3;
+            }
+            (Node::Open(open), Some(Node::Close(close)), _) => {
+                line(depth, &[open, close]);
+                i += 2;
+            }
+            (Node::Open(open), _, _) => {
+                line(depth, &[open]);
+                depth += 1;
+                i += 1;
+            }
+            (Node::Close(close), _, _) => {
+                depth = depth.checked_sub(1)?;
+                line(depth, &[close]);
+                i += 1;
+            }
+            (Node::Empty(node), _, _) => {
+                line(depth, &[node]);
+                i += 1;
+            }
+            (Node::Text(text), _, _) => {
+                line(depth, &[text.trim()]);
+                i += 1;
+            }
+        }
+    }
+
+    Some(out)
+}
+
+fn nodes(xml: &str) -> Option<Vec<Node<'_>>> {
+    let mut nodes = Vec::new();
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            push_text(&mut nodes, rest);
+            break;
+        };
+        push_text(&mut nodes, &rest[..start]);
+        rest = &rest[start..];
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->")? + 3
+        } else if rest.starts_with("<![CDATA[") {
+            rest.find("]]>")? + 3
+        } else if rest.starts_with("<?") {
+            rest.find("?>")? + 2
+        } else {
+            tag_end(rest)?
+        };
+
+        let tag = &rest[..end];
+        nodes.push(if tag.starts_with("</") {
+            Node::Close(tag)
+        } else if tag.ends_with("/>") || tag.starts_with("<!") || tag.starts_with("<?") {
+            Node::Empty(tag)
+        } else {
+            Node::Open(tag)
+        });
+        rest = &rest[end..];
+    }
+
+    Some(nodes)
+}
+
+fn push_text<'a>(nodes: &mut Vec<Node<'a>>, text: &'a str) {
+    if !text.trim().is_empty() {
+        nodes.push(Node::Text(text));
+    }
+}
+
+// After the closing `>`, which can appear in quoted attribute values
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            format("<a><b>text</b><c/><d></d></a>").unwrap(),
+            "<a>\n  <b>text</b>\n  <c/>\n  <d></d>\n</a>\n"
+        );
+        assert!(format("<a></b></a></a>").is_none());
+        assert!(format("<a").is_none());
+    }
+
+    #[test]
+    fn test_special_nodes() {
+        assert_eq!(
+            format("<?xml version=\"1.0\"?><a><!-- <b> --><![CDATA[<c>]]></a>").unwrap(),
+            "<?xml version=\"1.0\"?>\n<a>\n  <!-- <b> -->\n  <![CDATA[<c>]]>\n</a>\n"
+        );
+        assert!(format("<a><!-- unterminated </a>").is_none());
+    }
+
+    #[test]
+    fn test_quoted_attributes() {
+        assert_eq!(
+            format("<a href=\"x>y\"><b title='>'/></a>").unwrap(),
+            "<a href=\"x>y\">\n  <b title='>'/>\n</a>\n"
+        );
+    }
+}
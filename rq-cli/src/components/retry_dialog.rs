@@ -0,0 +1,133 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use crossterm::event::KeyCode;
+use ratatui::{
+    style::{Color, Style},
+    widgets::{Paragraph, Wrap},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::event::{Event, EventSender};
+
+use super::{popup, BlockComponent, HandleResult, HandleSuccess};
+
+// Seconds before a request that failed to connect is sent again, 0 waiting
+// for a key press, see `set_auto_retry`
+static AUTO_RETRY: AtomicU64 = AtomicU64::new(0);
+
+/// Sends a request that failed to connect or timed out again after `seconds`,
+/// unless the prompt is dismissed first.
+pub fn set_auto_retry(seconds: u64) {
+    AUTO_RETRY.store(seconds, Ordering::Relaxed);
+}
+
+pub fn auto_retry() -> Option<Duration> {
+    match AUTO_RETRY.load(Ordering::Relaxed) {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+/// Error of a request worth sending again as it is, i.e. a refused connection,
+/// emitting `Event::Retry` with its `id` once answered.
+pub struct RetryDialog {
+    id: u64,
+    error: String,
+    // Of the automatic retry
+    deadline: Option<Instant>,
+}
+
+impl RetryDialog {
+    pub fn new(id: u64, error: String, after: Option<Duration>) -> Self {
+        Self {
+            id,
+            error,
+            deadline: after.map(|after| Instant::now() + after),
+        }
+    }
+
+    fn text(&self) -> String {
+        let countdown = match self.deadline {
+            // Rounded up, not to show 0s before retrying
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                format!(", retrying in {}s", left.as_millis().div_ceil(1000))
+            }
+            None => String::new(),
+        };
+
+        format!("{}\n\n[r]etry{countdown} / any key to cancel", self.error)
+    }
+}
+
+impl BlockComponent for RetryDialog {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [("r/Enter", "send again"), ("any", "cancel")].as_slice()
+    }
+
+    fn content_size(&self, max_width: u16) -> Option<(u16, u16)> {
+        let text = self.text();
+        let width = text.lines().map(|line| line.width()).max().unwrap_or(0);
+        let width = (width as u16).min(max_width);
+
+        Some((width, popup::wrapped_height(&text, width)))
+    }
+
+    fn on_event(
+        &mut self,
+        key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
+    ) -> HandleResult {
+        let again = matches!(key_event.code, KeyCode::Char('r') | KeyCode::Enter);
+        events.emit(Event::PopupDismiss);
+        events.emit(Event::Retry((self.id, again)));
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let p = Paragraph::new(self.text())
+            .block(
+                block
+                    .border_style(Style::default().fg(Color::Red))
+                    .title(" request failed "),
+            )
+            .wrap(Wrap::default());
+
+        frame.render_widget(p, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    use crate::{
+        components::BlockComponent,
+        event::{channel, Event},
+    };
+
+    use super::RetryDialog;
+
+    #[test]
+    fn test_answer() {
+        let (events, mut rx) = channel();
+
+        for (key, again) in [(KeyCode::Char('r'), true), (KeyCode::Esc, false)] {
+            let mut dialog = RetryDialog::new(7, "connection refused".into(), None);
+            dialog.on_event(KeyEvent::from(key), &events).unwrap();
+
+            assert!(matches!(rx.try_recv(), Ok(Event::PopupDismiss)));
+            assert!(matches!(rx.try_recv(), Ok(Event::Retry((7, a))) if a == again));
+            assert!(rx.try_recv().is_err());
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    event::{Event, EventSender},
+    snippets::Snippet,
+};
+
+use super::{
+    menu::{Menu, MenuItem},
+    BlockComponent, HandleResult, HandleSuccess,
+};
+
+impl MenuItem for Snippet {
+    fn render(&self) -> Vec<Line<'_>> {
+        // Name and request line
+        let request_line = self.text.lines().next().unwrap_or_default();
+
+        // Padded by width, as `format!` counts chars
+        let padding = " ".repeat(16usize.saturating_sub(self.name.width()));
+
+        vec![Line::from(vec![
+            Span::raw(format!("{}{padding}", self.name)),
+            Span::styled(request_line, Style::default().fg(Color::DarkGray)),
+        ])]
+    }
+
+    fn render_highlighted(&self) -> Vec<Line<'_>> {
+        let mut lines = self.render();
+        lines.extend(
+            self.text
+                .lines()
+                .skip(1)
+                .map(|line| Line::styled(line, Style::default().fg(Color::DarkGray))),
+        );
+
+        lines
+    }
+}
+
+/// Snippets of the workspace, to start a new request from.
+pub struct SnippetsPanel {
+    menu: Menu<Snippet>,
+}
+
+impl SnippetsPanel {
+    pub fn new(snippets: Vec<Snippet>) -> Self {
+        Self {
+            menu: Menu::new(snippets),
+        }
+    }
+}
+
+impl BlockComponent for SnippetsPanel {
+    fn keymaps(&self) -> &'static [(&'static str, &'static str)] {
+        [
+            ("↓/↑ j/k", "next/previous"),
+            ("Enter/n", "new request"),
+            ("N", "new request, saved to file"),
+            ("Esc", "close"),
+        ]
+        .as_slice()
+    }
+
+    fn on_event(&mut self, key_event: KeyEvent, events: &EventSender) -> HandleResult {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('j' | 'k') => {
+                return self.menu.on_event(key_event, events)
+            }
+            KeyCode::Enter | KeyCode::Char('n' | 'N') if !self.menu.items().is_empty() => {
+                events.emit(Event::PopupDismiss);
+                events.emit(Event::NewSnippet((
+                    self.menu.selected().text.clone(),
+                    key_event.code == KeyCode::Char('N'),
+                )));
+            }
+            KeyCode::Esc => events.emit(Event::PopupDismiss),
+            _ => (),
+        }
+
+        Ok(HandleSuccess::Consumed)
+    }
+
+    fn render(
+        &self,
+        frame: &mut crate::terminal::Frame,
+        area: ratatui::prelude::Rect,
+        block: ratatui::widgets::Block,
+    ) {
+        let title = format!(" new request ({}) ", self.menu.items().len());
+        self.menu.render(frame, area, block.title(title));
+    }
+}
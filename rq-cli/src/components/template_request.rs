@@ -1,65 +1,242 @@
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
 use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
 };
-use rq_core::parser::TemplateRequest;
+use rq_core::parser::{RequestPart, TemplateRequest};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use super::menu::MenuItem;
 
+// Urls and query values of collapsed requests are cut beyond this many columns
+const URL_WIDTH: usize = 80;
+
+static FULL_URLS: AtomicBool = AtomicBool::new(false);
+
+/// Shows the whole urls of collapsed requests too, or toggles it back.
+pub fn toggle_full_urls() {
+    FULL_URLS.fetch_xor(true, Ordering::Relaxed);
+}
+
+// By display width, as wide characters, i.e. CJK or emoji, take two columns
+fn truncate(s: String, full: bool) -> String {
+    if full || s.width() <= URL_WIDTH {
+        return s;
+    }
+
+    // Leaving a column for the ellipsis
+    let mut width = 0;
+    let end = s
+        .char_indices()
+        .find(|(_, c)| {
+            width += c.width().unwrap_or(0);
+            width > URL_WIDTH - 1
+        })
+        .map_or(s.len(), |(i, _)| i);
+
+    format!("{}…", &s[..end])
+}
+
+// Spans of `text`, found at byte `offset` of its template, with the bytes in
+// `span` marked as the cause of the last fill error
+fn marked(
+    text: String,
+    offset: usize,
+    span: Option<&Range<usize>>,
+    style: Style,
+) -> Vec<Span<'static>> {
+    let range = span.map(|span| {
+        span.start.saturating_sub(offset).min(text.len())
+            ..span.end.saturating_sub(offset).min(text.len())
+    });
+    let Some(range) = range.filter(|range| !range.is_empty()) else {
+        return vec![Span::styled(text, style)];
+    };
+
+    vec![
+        Span::styled(text[..range.start].to_owned(), style),
+        Span::styled(
+            text[range.clone()].to_owned(),
+            Style::default().fg(Color::White).bg(Color::Red),
+        ),
+        Span::styled(text[range.end..].to_owned(), style),
+    ]
+}
+
+// Request line, query params and headers, the urls cut unless `full`
+fn head(request: &TemplateRequest, full: bool) -> Vec<Line<'_>> {
+    let full = full || FULL_URLS.load(Ordering::Relaxed);
+    // Span of the last fill error in `part`, which is shown whole
+    let error = |part: &RequestPart| {
+        request
+            .fill_error
+            .as_ref()
+            .filter(|error| error.part == *part)
+            .map(|error| error.span.as_ref())
+    };
+
+    let mut lines = Vec::new();
+
+    let mut first_line_spans = vec![
+        Span::styled(
+            request.method.to_string(),
+            Style::default().fg(Color::Green),
+        ),
+        Span::raw(" "),
+    ];
+    let url_error = error(&RequestPart::Url);
+    first_line_spans.extend(marked(
+        truncate(request.url.to_string(), full || url_error.is_some()),
+        0,
+        url_error.flatten(),
+        Style::default(),
+    ));
+    let mut version_spans = vec![Span::raw(format!(
+        " {:?}",
+        request.version.unwrap_or_default()
+    ))];
+    if !request.notes.is_empty() {
+        version_spans.push(Span::styled(" ✎", Style::default().fg(Color::DarkGray)));
+    }
+
+    let mut query = request
+        .query
+        .iter()
+        .enumerate()
+        .map(|(i, (k, v))| {
+            // Only the first enabled param of that name is pointed at, as filled first
+            let first = request
+                .query
+                .iter()
+                .enumerate()
+                .position(|(j, (name, _))| name == k && !request.disabled_query.contains(&j));
+            let value_error = (first == Some(i))
+                .then(|| error(&RequestPart::Query(k.clone())))
+                .flatten();
+            let mut spans = vec![
+                Span::raw(" ".repeat(request.method.to_string().len() + 1)),
+                Span::styled(
+                    if i == 0 { "?" } else { "&" },
+                    Style::default().fg(Color::Blue),
+                ),
+                Span::raw(k),
+                Span::raw(if request.encoded_query.contains(&i) {
+                    "=="
+                } else {
+                    "="
+                }),
+            ];
+            spans.extend(marked(
+                truncate(v.to_string(), full || value_error.is_some()),
+                0,
+                value_error.flatten(),
+                Style::default(),
+            ));
+
+            // Left out with `p`
+            if request.disabled_query.contains(&i) {
+                Line::from(
+                    spans
+                        .into_iter()
+                        .map(|span| {
+                            Span::styled(span.content, Style::default().fg(Color::DarkGray))
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            } else {
+                Line::from(spans)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if query.is_empty() {
+        first_line_spans.extend(version_spans);
+        lines.push(Line::from(first_line_spans));
+    } else {
+        lines.push(Line::from(first_line_spans));
+        query.last_mut().unwrap().spans.extend(version_spans);
+        lines.extend(query);
+    }
+
+    let headers: Vec<Line> = request
+        .headers
+        .iter()
+        .enumerate()
+        .map(|(i, (k, v))| {
+            if request.disabled_headers.contains(&i) {
+                return Line::styled(format!("# {k}: {v}"), Style::default().fg(Color::DarkGray));
+            }
+
+            let first = request
+                .headers
+                .iter()
+                .enumerate()
+                .position(|(j, (name, _))| name == k && !request.disabled_headers.contains(&j));
+            let value_error = (first == Some(i))
+                .then(|| error(&RequestPart::Header(k.clone())))
+                .flatten()
+                .flatten();
+            let mut spans = vec![
+                Span::styled(k.to_string(), Style::default().fg(Color::Blue)),
+                Span::raw(": "),
+            ];
+            spans.extend(marked(v.to_string(), 0, value_error, Style::default()));
+
+            Line::from(spans)
+        })
+        .collect();
+    lines.extend(headers);
+
+    lines
+}
+
 impl MenuItem for TemplateRequest {
-    fn render(&self) -> Vec<ratatui::text::Line<'_>> {
-        let mut lines = Vec::new();
-
-        let mut first_line_spans = vec![
-            Span::styled(self.method.to_string(), Style::default().fg(Color::Green)),
-            Span::raw(" "),
-            Span::raw(self.url.to_string()),
-        ];
-        let version_span = Span::raw(format!(" {:?}", self.version));
-
-        let mut query = self
-            .query
-            .iter()
-            .enumerate()
-            .map(|(i, (k, v))| {
-                Line::from(vec![
-                    Span::raw(" ".repeat(self.method.to_string().len() + 1)),
-                    Span::styled(
-                        if i == 0 { "?" } else { "&" },
-                        Style::default().fg(Color::Blue),
-                    ),
-                    Span::raw(k),
-                    Span::raw("="),
-                    Span::raw(v.to_string()),
-                ])
-            })
-            .collect::<Vec<_>>();
-
-        if query.is_empty() {
-            first_line_spans.push(version_span);
-            lines.push(Line::from(first_line_spans));
-        } else {
-            lines.push(Line::from(first_line_spans));
-            query.last_mut().unwrap().spans.push(version_span);
-            lines.extend(query);
+    fn render(&self) -> Vec<Line<'_>> {
+        self.render_folded(false, false)
+    }
+
+    fn render_highlighted(&self) -> Vec<Line<'_>> {
+        self.render_folded(true, true)
+    }
+
+    fn render_folded(&self, highlighted: bool, expanded: bool) -> Vec<Line<'_>> {
+        let mut lines = head(self, expanded);
+
+        if highlighted {
+            lines[0].patch_style(
+                Style::default()
+                    .add_modifier(Modifier::UNDERLINED)
+                    .add_modifier(Modifier::BOLD),
+            );
         }
 
-        let headers: Vec<Line> = self
-            .headers
-            .iter()
-            .map(|(k, v)| {
-                Line::from(vec![
-                    Span::styled(k.to_string(), Style::default().fg(Color::Blue)),
-                    Span::raw(": "),
-                    Span::raw(v.to_string()),
-                ])
-            })
-            .collect();
-        lines.extend(headers);
-
-        if !self.body.is_empty() {
+        let body = self.body.to_string();
+        let body_style = Style::default().fg(Color::Rgb(246, 133, 116));
+        let body_error = self
+            .fill_error
+            .as_ref()
+            .filter(|error| error.part == RequestPart::Body);
+        if expanded || body_error.is_some() {
+            let span = body_error.and_then(|error| error.span.as_ref());
+            let mut offset = 0;
+            // Like `lines`, keeping track of the offsets
+            for line in body.split_inclusive('\n') {
+                let text = line.trim_end_matches('\n').trim_end_matches('\r');
+                lines.push(Line::from(marked(
+                    text.to_owned(),
+                    offset,
+                    span,
+                    body_style,
+                )));
+                offset += line.len();
+            }
+        } else if !body.is_empty() {
             lines.push(Line::styled(
-                "Focus to show body",
+                format!("Press z to show body ({} lines)", body.lines().count()),
                 Style::default()
                     .fg(Color::Rgb(246, 133, 116))
                     .add_modifier(Modifier::ITALIC),
@@ -69,31 +246,4 @@ impl MenuItem for TemplateRequest {
         lines.push(Line::from(""));
         lines
     }
-
-    fn render_highlighted(&self) -> Vec<Line<'_>> {
-        let mut lines = self.render();
-
-        // Underline first line
-        lines[0].patch_style(
-            Style::default()
-                .add_modifier(Modifier::UNDERLINED)
-                .add_modifier(Modifier::BOLD),
-        );
-
-        // Replace body with expanded version
-        if !self.body.is_empty() {
-            lines.pop();
-            lines.pop();
-
-            for line in self.body.to_string().lines() {
-                lines.push(Line::styled(
-                    line.to_owned(),
-                    Style::default().fg(Color::Rgb(246, 133, 116)),
-                ));
-            }
-            lines.push(Line::from(""));
-        }
-
-        lines
-    }
 }
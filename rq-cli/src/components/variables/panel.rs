@@ -9,7 +9,7 @@ use crate::{
         menu::Menu,
         BlockComponent, HandleSuccess,
     },
-    event::Event,
+    event::{Event, EventSender},
 };
 
 pub struct VarsPanel {
@@ -20,8 +20,8 @@ pub struct VarsPanel {
 impl VarsPanel {
     pub fn new(vars: HashMap<String, TemplateString>) -> Self {
         let menu = Menu::new(vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
-            .with_confirm_callback(|(name, value)| {
-                Event::emit(Event::NewInput(
+            .with_confirm_callback(|(name, value), events| {
+                events.emit(Event::NewInput(
                     InputBuilder::new(InputType::VarValue(name.clone()))
                         .with_content(value.to_string()),
                 ));
@@ -58,14 +58,15 @@ impl BlockComponent for VarsPanel {
     fn on_event(
         &mut self,
         key_event: crossterm::event::KeyEvent,
+        events: &EventSender,
     ) -> crate::components::HandleResult {
-        match self.menu.on_event(key_event)? {
+        match self.menu.on_event(key_event, events)? {
             HandleSuccess::Consumed => return Ok(HandleSuccess::Consumed),
             HandleSuccess::Ignored => (),
         }
 
         if matches!(key_event.code, KeyCode::Esc) {
-            Event::emit(Event::Focus(crate::app::FocusState::RequestsList));
+            events.emit(Event::Focus(crate::app::FocusState::RequestsList));
         }
 
         Ok(HandleSuccess::Ignored)
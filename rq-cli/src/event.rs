@@ -1,19 +1,36 @@
-use std::{collections::VecDeque, sync::Mutex};
+use std::{future::Future, path::PathBuf};
 
-use once_cell::sync::Lazy;
+use rq_core::request::{link::Link, token::DecodedToken};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
 use crate::{
     app::FocusState,
     components::{input::builder::InputBuilder, response_panel::SaveOption},
+    history,
 };
 
-static EVENT_QUEUE: Lazy<Mutex<VecDeque<Event>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
-
 pub enum Event {
     Focus(FocusState),
+    Exit,
     Save((String, SaveOption)),
+    // File being saved, bytes written and total
+    SaveProgress((String, u64, u64)),
+    // File saved and its size, or why it couldn't be
+    SaveDone((String, Result<u64, String>)),
+    // Opened with the `[open]` command, i.e. a body too big to be shown
+    OpenFile(PathBuf),
     PopupDismiss,
     Message(Message),
+    // Id of a toast whose time is up
+    DismissToast(u64),
+    // Id of a retry prompt, and whether to send its request again
+    Retry((u64, bool)),
+    // Id of a retry prompt counting down
+    RetryTick(u64),
+    // The host of the queued requests can be reached again
+    Reachable,
+    // Request index, taken out of the queue
+    Unqueue(usize),
     NewInput(InputBuilder),
 
     InputConfirm,
@@ -25,29 +42,101 @@ pub enum Event {
     // Name, value
     UpdateVar((String, String)),
 
+    // Charset label to decode the current response with
+    SetEncoding(String),
+
+    // Text to look for in the current response
+    Search(String),
+
+    // Line typed after `:`, i.e. `send 3`
+    Command(String),
+
+    // Request index, edited body
+    SetBody((usize, String)),
+    // Request index, index of the header to comment out or back in
+    ToggleHeader((usize, usize)),
+    // Request index, index of the query param to leave out or put back
+    ToggleQuery((usize, usize)),
+    // Request index, index of the query param, its new value
+    SetQueryValue((usize, usize, String)),
+
+    // Sends the request of a history entry again
+    Resend(history::Entry),
+    ShowHistory(Vec<history::Entry>),
+
+    // Links of the current response, and the url of a `GET` request to add,
+    // appended to its file too if `true`
+    ShowLinks(Vec<Link>),
+    NewRequest((String, bool)),
+    // Text of a request to add, appended to the file too if `true`
+    NewSnippet((String, bool)),
+    // Request index, bytes of the body sent and its size
+    Upload((usize, u64, u64)),
+
+    // Tokens of the current response that could be decoded
+    ShowDecoded(Vec<DecodedToken>),
+
     Key(crossterm::event::KeyEvent),
-    Other(crossterm::event::Event),
 }
 
+/// Shown as a toast that goes away by itself, except for errors and
+/// `Custom` ones, i.e. the keymaps, which are kept until a key is pressed.
+#[derive(Clone)]
 pub enum Message {
     Info(String),
+    Warning(String),
     Error(String),
     Custom(String, String),
 }
 
-impl Event {
-    pub fn emit(event: Event) {
-        EVENT_QUEUE.lock().unwrap().push_front(event);
+impl Message {
+    pub fn text(&self) -> &str {
+        match self {
+            Message::Info(text) | Message::Warning(text) | Message::Error(text) => text,
+            Message::Custom(_, text) => text,
+        }
     }
+}
+
+/// Handle given to components to emit events, received by the main loop.
+#[derive(Clone)]
+pub struct EventSender(UnboundedSender<Event>);
 
-    pub fn poll() -> Option<Self> {
-        EVENT_QUEUE.lock().unwrap().pop_back()
+impl EventSender {
+    pub fn emit(&self, event: Event) {
+        // The receiver is only dropped on exit
+        let _ = self.0.send(event);
     }
 
-    pub fn parse(event: crossterm::event::Event) -> Self {
+    /// Runs `task` in the background, emitting the event it resolves to, or
+    /// its error as a message. This is how components perform I/O without
+    /// blocking the UI.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = anyhow::Result<Event>> + Send + 'static,
+    {
+        let events = self.clone();
+
+        tokio::spawn(async move {
+            let event = task
+                .await
+                .unwrap_or_else(|e| Event::Message(Message::Error(e.to_string())));
+            events.emit(event);
+        });
+    }
+}
+
+pub fn channel() -> (EventSender, UnboundedReceiver<Event>) {
+    let (tx, rx) = unbounded_channel();
+    (EventSender(tx), rx)
+}
+
+impl Event {
+    /// Key presses, the other terminal events only causing a redraw.
+    pub fn parse(event: crossterm::event::Event) -> Option<Self> {
         match event {
-            crossterm::event::Event::Key(e) => Self::Key(e),
-            _ => Self::Other(event),
+            crossterm::event::Event::Key(e) => Some(Self::Key(e)),
+            _ => None,
         }
     }
 }
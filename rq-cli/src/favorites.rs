@@ -0,0 +1,109 @@
+//! Starred requests, stored one per line in a sidecar file: `rq.favorites`
+//! next to the manifest, or `<file>.favorites` next to a single file.
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context};
+use rq_core::parser::TemplateRequest;
+use tokio::sync::Mutex;
+
+use crate::event::{Event, EventSender, Message};
+
+pub struct Favorites {
+    path: PathBuf,
+    keys: HashSet<String>,
+    // Of the last change, and of the last one written, the writes in the
+    // background being applied one at a time and never overwriting a newer one
+    version: u64,
+    saved: Arc<Mutex<u64>>,
+}
+
+impl Favorites {
+    /// Reads the favorites of the workspace at `path`, none if the file
+    /// doesn't exist yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let path = if path.is_dir() {
+            path.join("rq.favorites")
+        } else {
+            let mut file = path.as_os_str().to_owned();
+            file.push(".favorites");
+            PathBuf::from(file)
+        };
+
+        let keys = match fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_owned)
+                .collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+
+        Ok(Self {
+            path,
+            keys,
+            version: 0,
+            saved: Arc::default(),
+        })
+    }
+
+    pub fn contains(&self, request: &TemplateRequest) -> bool {
+        self.keys.contains(&key(request))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Stars or unstars `request`, saving the file in the background.
+    pub fn toggle(&mut self, request: &TemplateRequest, events: &EventSender) {
+        let key = key(request);
+        let starred = !self.keys.remove(&key);
+        if starred {
+            self.keys.insert(key.clone());
+        }
+
+        let mut keys = self.keys.iter().cloned().collect::<Vec<_>>();
+        keys.sort();
+        let content = keys.into_iter().map(|key| key + "\n").collect::<String>();
+        let path = self.path.clone();
+        self.version += 1;
+        let (version, saved) = (self.version, self.saved.clone());
+
+        events.spawn(async move {
+            let mut saved = saved.lock().await;
+            if *saved < version {
+                write(&path, content)
+                    .await
+                    .map_err(|e| anyhow!("saving {}: {e}", path.display()))?;
+                *saved = version;
+            }
+
+            let action = if starred { "Starred" } else { "Unstarred" };
+            Ok(Event::Message(Message::Info(format!("{action} {key}"))))
+        });
+    }
+}
+
+// Through a temporary file, not to leave a truncated one behind
+async fn write(path: &Path, content: String) -> std::io::Result<()> {
+    let temp = path.with_extension("favorites.tmp");
+    tokio::fs::write(&temp, content).await?;
+    tokio::fs::rename(&temp, path).await
+}
+
+/// Identifies a request across runs: by name, or by method and url.
+pub fn key(request: &TemplateRequest) -> String {
+    match &request.name {
+        Some(name) => format!("@{name}"),
+        None => format!("{} {}", request.method, request.url),
+    }
+}
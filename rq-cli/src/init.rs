@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+
+use crate::workspace::Workspace;
+
+const HTTP_FILE: &str = "requests.http";
+
+// `# @note` lines are shown with `i`, as plain comments aren't part of the syntax
+const EXAMPLE_REQUESTS: &str = r#"@user = rq
+@page_size = 10
+
+###
+
+# @name list
+# @note Relative urls start with the `base_url` of the environment, see rq.toml
+# @note Enter sends the selected request, ? lists the keys
+GET /get?page=1&size={{page_size}}
+Accept: application/json
+
+###
+
+# @name create
+# @note The body follows the headers after an empty line, b edits it
+# @note The name is captured from the response, as {{name}}
+# @capture name = $.json.name
+POST /post
+Content-Type: application/json
+
+{
+    "name": "{{user}}",
+    "created": {{$timestamp}}
+}
+
+###
+
+# @depends create
+# @note Sends create first, when it wasn't yet, for {{name}} to be captured
+GET /anything/{{name}}
+# X-Debug: disabled headers are kept, but not sent
+
+"#;
+
+const EXAMPLE_MANIFEST: &str = r#"# Files of the workspace, opened together
+files = ["requests.http"]
+
+# Shared variables, overridden by the ones defined in the .http files
+[variables]
+base_url = "https://httpbin.org"
+
+# Environments, selected with `--env <name>`, take precedence over everything else
+[environments.local]
+base_url = "http://localhost:8080"
+
+[environments.httpbin]
+base_url = "https://httpbin.org"
+
+[defaults]
+environment = "httpbin"
+"#;
+
+/// `rq init` subcommand, writing an example workspace to the current
+/// directory: a .http file and the `rq.toml` manifest with its environments.
+pub fn run() -> anyhow::Result<()> {
+    let files = [
+        (HTTP_FILE, EXAMPLE_REQUESTS),
+        (Workspace::MANIFEST, EXAMPLE_MANIFEST),
+    ];
+
+    if let Some((path, _)) = files.iter().find(|(path, _)| Path::new(path).exists()) {
+        return Err(anyhow!("{path} already exists, not overwriting it"));
+    }
+
+    for (path, content) in files {
+        std::fs::write(path, content).with_context(|| format!("writing {path}"))?;
+        eprintln!("created {path}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+//! Checks of the keymap tables, as listed by `?`, i.e. `("↓/↑ j/k", "next/previous")`.
+
+/// Keys bound to more than one action in `keymaps`, as `(key, actions)`.
+pub fn conflicts<'a>(keymaps: &[(&'a str, &'a str)]) -> Vec<(&'a str, Vec<&'a str>)> {
+    let mut bound: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    for (spec, action) in keymaps {
+        for key in keys(spec) {
+            match bound.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, actions)) => actions.push(action),
+                None => bound.push((key, vec![action])),
+            }
+        }
+    }
+
+    bound.retain(|(_, actions)| actions.len() > 1);
+    bound
+}
+
+// Alternative keys of a spec, separated by spaces or `/`. Sequences, i.e.
+// `<n> Enter`, and catch-alls like `any` don't bind a single key.
+fn keys(spec: &str) -> Vec<&str> {
+    let words = spec.split(' ').collect::<Vec<_>>();
+    if words
+        .iter()
+        .any(|word| matches!(*word, "<n>" | "any" | "type"))
+    {
+        return Vec::new();
+    }
+
+    words
+        .into_iter()
+        .flat_map(|word| match word {
+            "/" => vec![word],
+            _ => word.split('/').collect(),
+        })
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// One line hint of the keys that can follow a prefix, i.e. digits typed to
+/// jump to an item.
+pub fn hint(keymaps: &[(&str, &str)]) -> String {
+    keymaps
+        .iter()
+        .map(|(key, action)| format!("{key} {action}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,21 @@
+use std::{fs::File, path::Path, sync::Mutex};
+
+use anyhow::Context;
+use tracing::Level;
+
+/// Writes JSON logs to `path`, as stderr hosts the TUI.
+pub fn init(path: &Path, level: Level) -> anyhow::Result<()> {
+    let file = File::options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("opening {}", path.display()))?;
+
+    tracing_subscriber::fmt()
+        .json()
+        .with_max_level(level)
+        .with_writer(Mutex::new(file))
+        .init();
+
+    Ok(())
+}
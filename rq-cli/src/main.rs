@@ -1,33 +1,212 @@
-use rq_core::parser::parse;
-
 mod app;
+mod browser;
 mod components;
 mod event;
+mod favorites;
+mod history;
+mod init;
+mod keymap;
+mod logging;
+mod queue;
+mod secret;
+mod send;
+mod session;
+mod snippets;
 mod terminal;
+mod workspace;
 
 use app::App;
+use components::response_panel::highlight;
+use rq_core::parser::{
+    target,
+    variables::{command, dynamic::random},
+    IpVersion,
+};
+use workspace::Workspace;
 
 use std::env;
-use std::fs;
+use std::path::{Path, PathBuf};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let Some(file_path) = env::args().nth(1) else {
-        eprintln!("error: no files provided");
-        std::process::exit(1);
+    let mut path = None;
+    let mut environment = None;
+    let mut log_file = None;
+    let mut verbosity = 0;
+    let mut display = terminal::Options::default();
+
+    // Plain rendering can be made the default in the shell profile
+    if env::var_os("RQ_PLAIN").is_some_and(|value| !value.is_empty() && value != "0") {
+        components::set_plain(true);
+    }
+
+    let mut args = env::args().skip(1).peekable();
+
+    if args.next_if(|arg| arg == "secret").is_some() {
+        if let Err(e) = secret::run(args) {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    if args.next_if(|arg| arg == "new").is_some() {
+        if let Err(e) = snippets::run(args) {
+            eprintln!("error: {e:#}");
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    // `rq init`, then opening the example workspace
+    if args.next_if(|arg| arg == "init").is_some() {
+        if let Err(e) = init::run() {
+            eprintln!("error: {e:#}");
+            std::process::exit(1);
+        }
+        path = Some(PathBuf::from("."));
+    }
+
+    // `rq send`, the rest of the arguments being the same
+    let mut send = args
+        .next_if(|arg| arg == "send")
+        .map(|_| send::Options::default());
+
+    while let Some(arg) = args.next() {
+        if let Some(options) = &mut send {
+            match options.parse_arg(&arg, &mut args) {
+                Ok(true) => continue,
+                Ok(false) => (),
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        match arg.as_str() {
+            "-e" | "--env" => environment = args.next(),
+            "--allow-commands" => command::set_enabled(true),
+            "--no-history" => history::set_enabled(false),
+            "--offline-queue" => queue::set_enabled(true),
+            "--history-bodies" => history::set_bodies(true),
+            "--request-id" => workspace::set_request_id(true),
+            "--redact" => workspace::set_redact(true),
+            "--log-file" => match args.next() {
+                Some(path) => log_file = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("error: --log-file expects a path");
+                    std::process::exit(1);
+                }
+            },
+            // `--inline [lines]`
+            "--inline" => {
+                let height = args.next_if(|arg| arg.parse::<u16>().is_ok_and(|lines| lines > 0));
+                display.screen = terminal::Screen::Inline(
+                    height.map_or(terminal::INLINE_HEIGHT, |lines| lines.parse().unwrap()),
+                );
+            }
+            "--no-alt-screen" => display.screen = terminal::Screen::Main,
+            "--stdout" => display.output = terminal::Output::Stdout,
+            "--plain" => components::set_plain(true),
+            "-4" | "--ipv4" => workspace::set_ip_version(Some(IpVersion::V4)),
+            "-6" | "--ipv6" => workspace::set_ip_version(Some(IpVersion::V6)),
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            "--scheme" => match args.next() {
+                Some(scheme) => target::set_default_scheme(&scheme),
+                None => {
+                    eprintln!("error: --scheme expects a scheme");
+                    std::process::exit(1);
+                }
+            },
+            "--theme" => match args.next().map(|theme| theme.parse()) {
+                Some(Ok(theme)) => highlight::set_theme(theme),
+                Some(Err(e)) => {
+                    eprintln!("error: {e}");
+                    std::process::exit(1);
+                }
+                None => {
+                    eprintln!("error: --theme expects dark, light or none");
+                    std::process::exit(1);
+                }
+            },
+            "--auto-retry" => match args.next().map(|seconds| seconds.parse()) {
+                Some(Ok(seconds)) => components::retry_dialog::set_auto_retry(seconds),
+                _ => {
+                    eprintln!("error: --auto-retry expects a number of seconds");
+                    std::process::exit(1);
+                }
+            },
+            "--seed" => match args.next().map(|seed| seed.parse()) {
+                Some(Ok(seed)) => random::seed(seed),
+                _ => {
+                    eprintln!("error: --seed expects a number");
+                    std::process::exit(1);
+                }
+            },
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    // `-v` alone logs to `rq.log`
+    if log_file.is_some() || verbosity > 0 {
+        let level = match verbosity {
+            0 => tracing::Level::INFO,
+            1 => tracing::Level::DEBUG,
+            _ => tracing::Level::TRACE,
+        };
+        let log_file = log_file.unwrap_or_else(|| PathBuf::from("rq.log"));
+
+        if let Err(e) = logging::init(&log_file, level) {
+            eprintln!("error: {e:#}");
+            std::process::exit(1);
+        }
+    }
+
+    let path = match path {
+        Some(path) => path,
+        None if Path::new(Workspace::MANIFEST).is_file() => PathBuf::from("."),
+        None => {
+            eprintln!("error: no files provided");
+            std::process::exit(1);
+        }
     };
-    let file_content = fs::read_to_string(&file_path)?;
 
-    let http_file = match parse(&file_content) {
-        Ok(file) => file,
+    let workspace = match Workspace::load(&path, environment.as_deref()) {
+        Ok(workspace) => workspace,
         Err(e) => {
-            eprintln!("parsing error: {e}");
-            std::process::exit(1);
+            let failure = send::Failure::from(e);
+            eprintln!("error: {failure}");
+            std::process::exit(failure.exit_code());
         }
     };
 
-    let app = App::new(file_path, http_file);
-    terminal::run(app).await?;
+    if let Err(e) = workspace.configure_client() {
+        eprintln!("error: {e:#}");
+        std::process::exit(1);
+    }
+
+    if let Some(options) = send {
+        if let Err(failure) = send::run(workspace, options).await {
+            eprintln!("error: {failure}");
+            std::process::exit(failure.exit_code());
+        }
+        std::process::exit(0);
+    }
+
+    let (events, event_rx) = event::channel();
+    let mut app = match App::new(workspace, events) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            std::process::exit(1);
+        }
+    };
+    // Saved even if the terminal failed
+    let result = terminal::run(&mut app, event_rx, display).await;
+    app.save_session();
+    result?;
 
     std::process::exit(0)
 }
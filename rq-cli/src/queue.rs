@@ -0,0 +1,53 @@
+//! Requests sent while their host couldn't be reached, with `--offline-queue`,
+//! sent again once it can.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Between two probes of the host of the first queued request
+const PROBE_INTERVAL: Duration = Duration::from_secs(5);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Queues requests that fail because their host can't be reached, with
+/// `--offline-queue`.
+pub fn set_enabled(value: bool) {
+    ENABLED.store(value, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone)]
+pub struct Queued {
+    // Of the request in the list
+    pub idx: usize,
+    // See `favorites::key`, to find it again once reloaded
+    pub key: String,
+    pub method: String,
+    pub url: String,
+    // Local, as `HH:MM:SS`
+    pub time: String,
+}
+
+/// Whether a connection can be opened to the host of `url` in a few seconds.
+pub async fn reachable(url: &str) -> bool {
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, rq_core::request::probe(url)).await,
+        Ok(Ok(()))
+    )
+}
+
+/// Resolves once the host of `url` can be reached, probed every few seconds.
+pub async fn wait_reachable(url: String) {
+    loop {
+        tokio::time::sleep(PROBE_INTERVAL).await;
+        if reachable(&url).await {
+            return;
+        }
+    }
+}
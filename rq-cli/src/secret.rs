@@ -0,0 +1,74 @@
+use std::io::{BufRead, IsTerminal};
+
+use anyhow::anyhow;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use rq_core::keyring;
+
+const USAGE: &str = "usage: rq secret <set|delete> <service> <user>";
+
+/// `rq secret` subcommand, managing secrets available as
+/// `{{$keyring.<service>.<user>}}`.
+pub fn run(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let (Some(action), Some(service), Some(user)) = (args.next(), args.next(), args.next()) else {
+        return Err(anyhow!(USAGE));
+    };
+
+    match action.as_str() {
+        "set" => {
+            eprint!("secret for {service}.{user}: ");
+            let secret = read_secret()?;
+
+            keyring::set(&service, &user, &secret)?;
+            eprintln!("stored, use it as {{{{$keyring.{service}.{user}}}}}");
+        }
+        "delete" => keyring::delete(&service, &user)?,
+        _ => return Err(anyhow!(USAGE)),
+    }
+
+    Ok(())
+}
+
+// A line of stdin, not echoed when typed in a terminal so that the secret
+// isn't left on screen or in the scrollback
+fn read_secret() -> anyhow::Result<String> {
+    if !std::io::stdin().is_terminal() {
+        let mut secret = String::new();
+        std::io::stdin().lock().read_line(&mut secret)?;
+        return Ok(secret.trim_end_matches(['\r', '\n']).to_owned());
+    }
+
+    terminal::enable_raw_mode()?;
+    let secret = read_keys();
+    terminal::disable_raw_mode()?;
+    eprintln!();
+
+    secret
+}
+
+fn read_keys() -> anyhow::Result<String> {
+    let mut secret = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Enter => return Ok(secret),
+            KeyCode::Esc => return Err(anyhow!("cancelled")),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(anyhow!("cancelled"))
+            }
+            KeyCode::Char(c) => secret.push(c),
+            KeyCode::Backspace => {
+                secret.pop();
+            }
+            _ => (),
+        }
+    }
+}
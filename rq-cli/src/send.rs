@@ -0,0 +1,371 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{IsTerminal, Read, Write},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context};
+use chrono::Utc;
+use rq_core::{
+    parser::{scenario, variables::TemplateString, HttpRequest, ParseError},
+    request::{
+        download::{download, StatusError},
+        header::{HeaderValue, AUTHORIZATION},
+        Response, StatusCode,
+    },
+};
+
+use crate::{
+    browser, favorites, history,
+    workspace::{self, Workspace},
+};
+
+/// Options of the `rq send` subcommand, sending a single request without the TUI.
+#[derive(Default)]
+pub struct Options {
+    index: Option<usize>,
+    name: Option<String>,
+    // `-` for stdin, `@<path>` for a file, or the body itself
+    body: Option<String>,
+    // Fail on `4xx` and `5xx` responses, like `curl --fail`
+    fail: bool,
+    output: Output,
+    // Body streamed to a file instead of stdout, resumed with `--continue`
+    to_file: Option<PathBuf>,
+    resume: bool,
+    color: Color,
+}
+
+/// Parts of the response printed to stdout.
+#[derive(Default, Clone, Copy)]
+enum Output {
+    #[default]
+    Body,
+    // Status line, headers and body
+    Include,
+    // Status line and headers
+    Head,
+    Headers,
+    Status,
+}
+
+/// Whether the status line and headers are colored, `--color`.
+#[derive(Default, Clone, Copy)]
+enum Color {
+    // When stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Color::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+// ANSI escape codes
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const CYAN: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+/// Why `rq send` failed, each with its own exit code for scripts.
+pub enum Failure {
+    // Invalid arguments, unreadable files or configuration
+    Usage(anyhow::Error),
+    Parse(anyhow::Error),
+    // Variables that can't be filled or captured
+    Template(anyhow::Error),
+    Network(anyhow::Error),
+    // `4xx` or `5xx` response with `--fail`, or any but `2xx` with `-o`
+    Http(StatusCode),
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Usage(_) => 1,
+            Failure::Parse(_) => 2,
+            Failure::Template(_) => 3,
+            Failure::Network(_) => 4,
+            Failure::Http(_) => 22,
+        }
+    }
+}
+
+impl From<anyhow::Error> for Failure {
+    fn from(value: anyhow::Error) -> Self {
+        if value.downcast_ref::<ParseError>().is_some() {
+            Failure::Parse(value)
+        } else {
+            Failure::Usage(value)
+        }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Usage(e) | Failure::Parse(e) | Failure::Template(e) => write!(f, "{e:#}"),
+            // reqwest errors already include their sources
+            Failure::Network(e) => write!(f, "{e}"),
+            Failure::Http(status) => write!(f, "server responded with {status}"),
+        }
+    }
+}
+
+impl Options {
+    /// Parses the option `arg`, taking its value from `args`. Returns whether
+    /// `arg` is an option of `rq send`.
+    pub fn parse_arg(
+        &mut self,
+        arg: &str,
+        args: &mut impl Iterator<Item = String>,
+    ) -> anyhow::Result<bool> {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{arg} expects a value"));
+
+        match arg {
+            "--index" => {
+                self.index = Some(
+                    value()?
+                        .parse()
+                        .map_err(|_| anyhow!("--index expects a number"))?,
+                )
+            }
+            "--name" => self.name = Some(value()?),
+            "--body" => self.body = Some(value()?),
+            "--fail" => self.fail = true,
+            "-i" | "--include" => self.output = Output::Include,
+            "--head" => self.output = Output::Head,
+            "--headers-only" => self.output = Output::Headers,
+            "--status-only" => self.output = Output::Status,
+            "-o" | "--output" => self.to_file = Some(PathBuf::from(value()?)),
+            "-C" | "--continue" => self.resume = true,
+            "--color" => {
+                self.color = match value()?.as_str() {
+                    "auto" => Color::Auto,
+                    "always" => Color::Always,
+                    "never" => Color::Never,
+                    other => {
+                        return Err(anyhow!(
+                            "--color expects auto, always or never, not '{other}'"
+                        ))
+                    }
+                }
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Sends the selected request of `workspace`, along with its dependencies,
+/// printing the response to stdout.
+pub async fn run(workspace: Workspace, options: Options) -> Result<(), Failure> {
+    let requests = &workspace.http_file.requests;
+    let idx = match (&options.name, options.index) {
+        (Some(name), _) => requests
+            .iter()
+            .position(|r| r.name.as_ref() == Some(name))
+            .ok_or_else(|| anyhow!("no request named '{name}'"))?,
+        (None, Some(idx)) if idx < requests.len() => idx,
+        (None, Some(idx)) => return Err(anyhow!("no request at index {idx}").into()),
+        (None, None) if requests.len() == 1 => 0,
+        (None, None) => return Err(anyhow!("select a request with --index or --name").into()),
+    };
+
+    let body = options.body.as_deref().map(read_body).transpose()?;
+
+    let mut order = scenario::execution_order(requests, idx).map_err(|e| anyhow!(e))?;
+    if let Some(auth_idx) = requests.iter().position(|r| r.auth.is_some()) {
+        if !order.contains(&auth_idx) {
+            order.insert(0, auth_idx);
+        }
+    }
+
+    let mut vars = workspace.http_file.variables.clone();
+    for i in order {
+        let template = &requests[i];
+        let mut request = template
+            .fill(&vars)
+            .map_err(|e| Failure::Template(e.into()))?;
+        if i == idx {
+            if let Some(body) = &body {
+                request.body = body.clone();
+            }
+        }
+
+        workspace::apply_host_rules(&workspace.hosts, &mut request, &vars)
+            .map_err(Failure::Template)?;
+        if let Some(request_id) = &workspace.request_id {
+            let value = request_id
+                .apply(&mut request, &vars)
+                .map_err(Failure::Template)?;
+            eprintln!("{}: {value}", request_id.header);
+        }
+
+        if let Some(name) = &template.oauth2 {
+            authorize(&workspace, name, &vars, &mut request).await?;
+        }
+
+        // Not recorded in the history, the body being on disk
+        if let (true, Some(path)) = (i == idx, &options.to_file) {
+            // The error page isn't saved, with or without `--fail`
+            let download = download(request, path, options.resume).await.map_err(|e| {
+                match e.downcast::<StatusError>() {
+                    Ok(e) => Failure::Http(e.0),
+                    Err(e) => Failure::Network(anyhow!(e)),
+                }
+            })?;
+            if download.resumed_from > 0 {
+                eprintln!(
+                    "resumed from byte {}, {} bytes written",
+                    download.resumed_from, download.written
+                );
+            }
+
+            return Ok(());
+        }
+
+        let started_at = Utc::now();
+        let result = rq_core::request::execute(request.clone())
+            .await
+            .map_err(|e| e.to_string());
+
+        let entry = history::Entry::new(
+            &workspace.path,
+            favorites::key(template),
+            &request,
+            result.as_ref().map_err(String::as_str),
+            started_at,
+        );
+        if let Err(e) = history::append(&entry).await {
+            tracing::warn!(error = format!("{e:#}"), "history not recorded");
+        }
+
+        let response = result.map_err(|e| Failure::Network(anyhow!(e)))?;
+
+        if options.fail && (response.status.is_client_error() || response.status.is_server_error())
+        {
+            return Err(Failure::Http(response.status));
+        }
+
+        if i == idx {
+            return match print(&response, options.output, options.color.enabled()) {
+                // i.e. piped into `head`
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+                result => Ok(result.context("writing the response")?),
+            };
+        }
+
+        for capture in &template.captures {
+            let value = capture
+                .extract(&response)
+                .map_err(|e| Failure::Template(e.into()))?;
+            vars.insert(capture.name.clone(), TemplateString::raw(&value));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_body(arg: &str) -> anyhow::Result<String> {
+    if arg == "-" {
+        let mut body = String::new();
+        std::io::stdin()
+            .read_to_string(&mut body)
+            .context("reading the body from stdin")?;
+        return Ok(body);
+    }
+
+    match arg.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path).with_context(|| format!("reading {path}")),
+        None => Ok(arg.to_owned()),
+    }
+}
+
+async fn authorize(
+    workspace: &Workspace,
+    name: &str,
+    vars: &HashMap<String, TemplateString>,
+    request: &mut HttpRequest,
+) -> anyhow::Result<()> {
+    let config = workspace
+        .oauth2
+        .get(name)
+        .ok_or_else(|| anyhow!("oauth2 configuration '{name}' not found"))?
+        .fill(vars)?;
+
+    let token = config
+        .acquire(|url| {
+            eprintln!("Complete the authorization in your browser:\n{url}");
+            let _ = browser::open(url);
+        })
+        .await?;
+
+    let value = HeaderValue::from_str(&format!("Bearer {}", token.access_token))?;
+    request.headers.insert(AUTHORIZATION, value);
+
+    Ok(())
+}
+
+fn print(response: &Response, output: Output, color: bool) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+
+    let status_color = match response.status.as_u16() {
+        200..=299 => GREEN,
+        300..=399 => YELLOW,
+        _ => RED,
+    };
+    let paint = |text: String, code: &str| {
+        if color {
+            format!("{code}{text}{RESET}")
+        } else {
+            text
+        }
+    };
+
+    if let Output::Status = output {
+        let status = paint(response.status.as_u16().to_string(), status_color);
+        return writeln!(stdout, "{status}");
+    }
+
+    if let Output::Include | Output::Head = output {
+        let status = paint(response.status.to_string(), status_color);
+        writeln!(stdout, "{} {status}", response.version)?;
+    }
+
+    if let Output::Include | Output::Head | Output::Headers = output {
+        for (k, v) in &response.headers {
+            let name = paint(k.to_string(), CYAN);
+            writeln!(stdout, "{name}: {}", String::from_utf8_lossy(v.as_bytes()))?;
+        }
+    }
+
+    if let Output::Include = output {
+        writeln!(stdout)?;
+    }
+
+    if let Output::Include | Output::Body = output {
+        match &response.spilled {
+            // Only its head is in the payload
+            Some(spilled) => {
+                std::io::copy(&mut std::fs::File::open(&spilled.file.0)?, &mut stdout)?;
+            }
+            None => stdout.write_all(response.payload.bytes())?,
+        }
+    }
+
+    Ok(())
+}
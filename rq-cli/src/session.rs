@@ -0,0 +1,121 @@
+//! UI state restored when a workspace is reopened, stored per workspace in
+//! `$XDG_STATE_HOME/rq/sessions` (`~/.local/state/rq/sessions` by default).
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    // Index of the selected request
+    pub selected: usize,
+    // First row of the list shown
+    pub scroll: usize,
+    pub sort: String,
+    pub group: String,
+    pub only_favorites: bool,
+    pub vars_visible: bool,
+    // Variables edited at runtime, as typed, but the secret ones
+    pub variables: HashMap<String, String>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            selected: 0,
+            scroll: 0,
+            sort: String::new(),
+            group: String::new(),
+            only_favorites: false,
+            vars_visible: true,
+            variables: HashMap::new(),
+        }
+    }
+}
+
+impl Session {
+    /// Reads the session of the workspace at `workspace`, if any.
+    pub fn load(workspace: &Path) -> anyhow::Result<Option<Self>> {
+        let path = path(workspace)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+
+        toml::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn save(&self, workspace: &Path) -> anyhow::Result<()> {
+        let path = path(workspace)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        }
+
+        fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+// Parts of the names of the variables not saved, i.e. `api_token`
+const SECRET_NAMES: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "key",
+    "auth",
+    "cookie",
+    "credential",
+];
+
+/// Whether the variable `name` likely holds a secret, not to be written to
+/// the session.
+pub fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_NAMES.iter().any(|part| name.contains(part))
+}
+
+/// Directory of the files rq keeps between runs, i.e. `~/.local/state/rq`.
+pub fn state_dir() -> anyhow::Result<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .map(|dir| dir.join("rq"))
+        .ok_or_else(|| anyhow!("no state directory, set XDG_STATE_HOME"))
+}
+
+// i.e. `~/.local/state/rq/sessions/%home%me%api.toml`
+fn path(workspace: &Path) -> anyhow::Result<PathBuf> {
+    let workspace = workspace
+        .canonicalize()
+        .with_context(|| format!("resolving {}", workspace.display()))?;
+    let name = workspace.to_string_lossy().replace(['/', '\\', ':'], "%");
+
+    Ok(state_dir()?.join("sessions").join(name + ".toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_secret;
+
+    #[test]
+    fn test_is_secret() {
+        assert!(is_secret("api_token"));
+        assert!(is_secret("API_KEY"));
+        assert!(is_secret("clientSecret"));
+        assert!(is_secret("db_password"));
+        assert!(!is_secret("base_url"));
+        assert!(!is_secret("user_id"));
+    }
+}
@@ -0,0 +1,123 @@
+//! Skeletons of requests, added to the list with `n` or written by `rq new`.
+
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+use anyhow::{anyhow, Context};
+use rq_core::parser::parse_request;
+
+use crate::workspace;
+
+const USAGE: &str = "usage: rq new --template <name> [file]";
+
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "json-post",
+        r#"POST https://example.com/items
+Content-Type: application/json
+
+{
+    "name": "value"
+}"#,
+    ),
+    (
+        "graphql",
+        r#"POST https://example.com/graphql
+Content-Type: application/json
+
+{
+    "query": "query($id: ID!) { node(id: $id) { id } }",
+    "variables": { "id": "1" }
+}"#,
+    ),
+    (
+        "form-upload",
+        r#"POST https://example.com/upload
+Content-Type: multipart/form-data; boundary=rq-boundary
+
+--rq-boundary
+Content-Disposition: form-data; name="file"; filename="data.txt"
+Content-Type: text/plain
+
+content of the file
+--rq-boundary--"#,
+    ),
+    (
+        "bearer-get",
+        r#"GET https://example.com/me
+Authorization: Bearer {{token}}
+Accept: application/json"#,
+    ),
+];
+
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub name: String,
+    pub text: String,
+}
+
+/// The snippets of the manifest, by name, followed by the built-in ones they
+/// don't replace. Each is checked to be a valid request.
+pub fn with_builtin(
+    custom: impl IntoIterator<Item = (String, String)>,
+) -> anyhow::Result<Vec<Snippet>> {
+    // Later entries, i.e. from `rq.local.toml`, win
+    let custom = custom.into_iter().collect::<BTreeMap<_, _>>();
+    for (name, text) in &custom {
+        parse_request(text)
+            .map_err(|e| anyhow::Error::new(*e))
+            .with_context(|| format!("snippet '{name}'"))?;
+    }
+
+    let builtin = BUILTIN
+        .iter()
+        .filter(|(name, _)| !custom.contains_key(*name))
+        .map(|(name, text)| (name.to_string(), text.to_string()))
+        .collect::<Vec<_>>();
+
+    Ok(custom
+        .into_iter()
+        .chain(builtin)
+        .map(|(name, text)| Snippet { name, text })
+        .collect())
+}
+
+/// `rq new` subcommand, appending a snippet to a `.http` file, created if
+/// missing, or printing it. Snippets come from the `rq.toml` of the current
+/// directory and the built-in ones, listed without `--template`.
+pub fn run(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let snippets = workspace::read_snippets(Path::new("."))?;
+
+    let name = match args.next().as_deref() {
+        Some("--template") => args.next().ok_or_else(|| anyhow!(USAGE))?,
+        Some(_) => return Err(anyhow!(USAGE)),
+        None => {
+            for snippet in &snippets {
+                println!("{}", snippet.name);
+            }
+            return Ok(());
+        }
+    };
+    let snippet = snippets
+        .iter()
+        .find(|snippet| snippet.name == name)
+        .ok_or_else(|| anyhow!("no snippet named '{name}', see `rq new`"))?;
+
+    let Some(path) = args.next() else {
+        println!("{}", snippet.text);
+        return Ok(());
+    };
+
+    let separator = match std::fs::metadata(&path) {
+        Ok(metadata) if metadata.len() > 0 => "\n###\n\n",
+        _ => "",
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("opening {path}"))?;
+    writeln!(file, "{separator}{}", snippet.text).with_context(|| format!("writing {path}"))?;
+    eprintln!("added {name} to {path}");
+
+    Ok(())
+}
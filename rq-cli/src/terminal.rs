@@ -1,56 +1,153 @@
+use std::io::Write;
+
 use crossterm::{
-    event, execute,
+    event::EventStream,
+    execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::time::Duration;
+use futures_util::StreamExt;
+use ratatui::{backend::CrosstermBackend, Terminal, TerminalOptions, Viewport};
+
+use tokio::sync::mpsc::UnboundedReceiver;
 
 use crate::{app::App, event::Event};
 
-pub type Frame<'a> = ratatui::Frame<'a, CrosstermBackend<std::io::Stderr>>;
+pub type Frame<'a> = ratatui::Frame<'a, CrosstermBackend<Output>>;
+
+/// Lines taken by the UI with `--inline` and no height.
+pub const INLINE_HEIGHT: u16 = 20;
+
+/// Where the UI is drawn.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Screen {
+    // Alternate screen, restored on exit
+    #[default]
+    Full,
+    // The whole terminal, without switching to the alternate screen
+    Main,
+    // This many lines below the prompt
+    Inline(u16),
+}
+
+/// Stream the UI is written to: stderr by default, for stdout to be
+/// redirected, i.e. `rq api.http > log`.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Output {
+    #[default]
+    Stderr,
+    Stdout,
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Stderr => std::io::stderr().write(buf),
+            Output::Stdout => std::io::stdout().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Stderr => std::io::stderr().flush(),
+            Output::Stdout => std::io::stdout().flush(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub screen: Screen,
+    pub output: Output,
+}
 
-fn startup() -> std::io::Result<()> {
+fn startup(options: Options) -> std::io::Result<()> {
+    let mut output = options.output;
     enable_raw_mode()?;
-    execute!(std::io::stderr(), EnterAlternateScreen)?;
+    if let Screen::Full = options.screen {
+        execute!(output, EnterAlternateScreen)?;
+    }
     Ok(())
 }
 
-fn shutdown() -> std::io::Result<()> {
-    execute!(std::io::stderr(), LeaveAlternateScreen)?;
+fn shutdown(options: Options) -> std::io::Result<()> {
+    let mut output = options.output;
+    match options.screen {
+        Screen::Full => execute!(output, LeaveAlternateScreen)?,
+        // The prompt goes below the last frame, left on screen
+        Screen::Main | Screen::Inline(_) => writeln!(output)?,
+    }
     disable_raw_mode()?;
     Ok(())
 }
 
-async fn main_loop(app: &mut App) -> anyhow::Result<()> {
-    let mut t = Terminal::new(CrosstermBackend::new(std::io::stderr()))?;
+#[tracing::instrument(skip_all)]
+async fn main_loop(
+    app: &mut App,
+    events: &mut UnboundedReceiver<Event>,
+    options: Options,
+) -> anyhow::Result<()> {
+    let backend = CrosstermBackend::new(options.output);
+    let mut t = match options.screen {
+        Screen::Full | Screen::Main => Terminal::new(backend)?,
+        Screen::Inline(height) => Terminal::with_options(
+            backend,
+            TerminalOptions {
+                viewport: Viewport::Inline(height),
+            },
+        )?,
+    };
 
-    loop {
-        app.update();
+    let mut terminal_events = EventStream::new();
 
-        if event::poll(Duration::from_millis(250))? {
-            app.on_event(Event::parse(event::read()?)).await;
+    // Redraws only after something happened: a terminal event, an internal
+    // event or a response
+    loop {
+        while let Ok(event) = events.try_recv() {
+            app.on_event(event).await;
         }
 
-        if let Some(event) = Event::poll() {
-            app.on_event(event).await;
+        if app.should_exit() {
+            if !matches!(options.screen, Screen::Full) {
+                let area = t.get_frame().size();
+                t.set_cursor(0, area.bottom().saturating_sub(1))?;
+                t.show_cursor()?;
+            }
+            break;
         }
 
         t.draw(|f| {
             app.draw(f);
         })?;
 
-        if app.should_exit() {
-            break;
+        tokio::select! {
+            Some(response) = app.next_response() => {
+                tracing::trace!("response");
+                app.on_response(response).await;
+            }
+            Some(event) = terminal_events.next() => {
+                tracing::trace!("terminal event");
+                if let Some(event) = Event::parse(event?) {
+                    app.on_event(event).await;
+                }
+            }
+            Some(event) = events.recv() => {
+                tracing::trace!("internal event");
+                app.on_event(event).await;
+            }
         }
     }
 
     Ok(())
 }
 
-pub async fn run(mut app: App) -> anyhow::Result<()> {
-    startup()?;
-    let res = main_loop(&mut app).await;
-    shutdown()?;
+pub async fn run(
+    app: &mut App,
+    mut events: UnboundedReceiver<Event>,
+    options: Options,
+) -> anyhow::Result<()> {
+    startup(options)?;
+    let res = main_loop(app, &mut events, options).await;
+    shutdown(options)?;
 
     res?;
 
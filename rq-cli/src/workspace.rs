@@ -0,0 +1,926 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use rq_core::{
+    auth::{
+        oauth2::{Flow, OAuth2Template},
+        CredentialsTemplate,
+    },
+    parser::{
+        parse, target, variables::TemplateString, HttpFile, HttpRequest, IpVersion, TemplateRequest,
+    },
+    request::{
+        self,
+        header::{HeaderName, HeaderValue, AUTHORIZATION},
+        pin::CertPin,
+        redact::Redaction,
+        ClientConfig, ProxyConfig,
+    },
+};
+use serde::Deserialize;
+
+use crate::snippets::{self, Snippet};
+
+/// `rq.toml` project manifest.
+///
+/// ```toml
+/// files = ["users.http", "auth.http"]
+/// # Headers redacted from saves, along with `redact::DEFAULT_HEADERS`
+/// redact = ["x-api-key", "x-*-token"]
+///
+/// [variables]
+/// host = "example.com"
+///
+/// [environments.dev]
+/// host = "localhost:8080"
+///
+/// [defaults]
+/// environment = "dev"
+///
+/// [oauth2.api]
+/// flow = "client_credentials"
+/// token_url = "https://{{host}}/oauth/token"
+/// client_id = "{{client_id}}"
+/// client_secret = "{{client_secret}}"
+///
+/// [proxy]
+/// url = "http://proxy.corp:3128"
+/// no_proxy = "localhost,.internal"
+///
+/// # Durations in seconds, a `timeout` of 0 waiting forever
+/// [client]
+/// timeout = 30
+/// connect_timeout = 5
+/// tcp_keepalive = 60
+/// tcp_nodelay = false
+/// user_agent = "my-team/1.0"
+/// max_body = 16 # MiB kept in memory, 0 for no limit
+///
+/// # SHA-256 fingerprints of the certificate, or `sha256//<base64>` of its public key
+/// [pins]
+/// "staging.example.com" = ["AB:CD:..."]
+///
+/// # Defaults of the requests to the matching hosts, the first rule setting
+/// # a header winning
+/// [[hosts]]
+/// match = "*.internal.corp"
+/// headers = { "X-Team" = "platform" }
+/// auth = "basic {{user}} {{password}}"
+/// proxy = "http://proxy.corp:3128"
+///
+/// [request_id]
+/// header = "X-Correlation-Id"
+/// format = "rq-{{$random.alphanumeric 12}}"
+///
+/// # Requests offered by `n` and `rq new`, along with the built-in ones
+/// [snippets]
+/// "admin-get" = "GET {{base_url}}/admin\nX-Admin: true"
+///
+/// # Commands given saved responses by `:open` and `:reveal`, instead of the
+/// # system ones, usually set in `rq.local.toml`
+/// [open]
+/// file = ["code", "--reuse-window"]
+/// folder = ["nautilus", "--select"]
+/// ```
+///
+/// An optional, git-ignored `rq.local.toml` next to it accepts the same
+/// `[variables]`, `[environments]`, `[proxy]`, `[client]`, `[snippets]` and `[open]` tables,
+/// which take precedence over the committed ones, and `[pins]` and `[[hosts]]`
+/// adding to them.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Manifest {
+    files: Vec<PathBuf>,
+    variables: HashMap<String, String>,
+    environments: HashMap<String, HashMap<String, String>>,
+    defaults: Defaults,
+    oauth2: HashMap<String, OAuth2Entry>,
+    proxy: Option<ProxyEntry>,
+    client: Option<ClientEntry>,
+    pins: HashMap<String, Vec<String>>,
+    hosts: Vec<HostEntry>,
+    request_id: Option<RequestIdEntry>,
+    redact: Option<Vec<String>>,
+    snippets: HashMap<String, String>,
+    open: Openers,
+}
+
+/// Commands opening saved files, the path appended, or `None` for the
+/// system ones.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct Openers {
+    pub file: Option<Vec<String>>,
+    // Showing the file in the file manager
+    pub folder: Option<Vec<String>>,
+}
+
+// Overrides the proxy environment variables, an empty `url` disabling proxies
+#[derive(Debug, Deserialize)]
+struct ProxyEntry {
+    url: String,
+    no_proxy: Option<String>,
+}
+
+impl From<ProxyEntry> for ProxyConfig {
+    fn from(value: ProxyEntry) -> Self {
+        if value.url.is_empty() {
+            return ProxyConfig::Disabled;
+        }
+
+        ProxyConfig::Url {
+            url: value.url,
+            no_proxy: value.no_proxy,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ClientEntry {
+    timeout: Option<f64>,
+    connect_timeout: Option<f64>,
+    tcp_keepalive: Option<f64>,
+    tcp_nodelay: Option<bool>,
+    user_agent: Option<String>,
+    // In MiB, 0 keeping bodies whole
+    max_body: Option<u64>,
+}
+
+impl TryFrom<ClientEntry> for ClientConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ClientEntry) -> Result<Self, Self::Error> {
+        let default = ClientConfig::default();
+        let seconds = |name: &str, value: f64| {
+            Duration::try_from_secs_f64(value).map_err(|_| anyhow!("invalid {name} '{value}'"))
+        };
+
+        Ok(ClientConfig {
+            timeout: match value.timeout {
+                Some(0.0) => None,
+                Some(timeout) => Some(seconds("timeout", timeout)?),
+                None => default.timeout,
+            },
+            connect_timeout: value
+                .connect_timeout
+                .map(|timeout| seconds("connect_timeout", timeout))
+                .transpose()?,
+            tcp_keepalive: value
+                .tcp_keepalive
+                .map(|interval| seconds("tcp_keepalive", interval))
+                .transpose()?,
+            tcp_nodelay: value.tcp_nodelay.unwrap_or(default.tcp_nodelay),
+            ip_version: default.ip_version,
+            pins: default.pins,
+            user_agent: match value.user_agent {
+                Some(user_agent) => {
+                    HeaderValue::from_str(&user_agent)
+                        .map_err(|_| anyhow!("invalid user_agent '{user_agent}'"))?;
+                    user_agent
+                }
+                None => default.user_agent,
+            },
+            max_body: match value.max_body {
+                Some(0) => None,
+                Some(mib) => Some(
+                    mib.checked_mul(1024 * 1024)
+                        .ok_or_else(|| anyhow!("invalid max_body '{mib}'"))?,
+                ),
+                None => default.max_body,
+            },
+            hooks: default.hooks,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HostEntry {
+    #[serde(rename = "match")]
+    pattern: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    // Like the `# @auth` directive
+    auth: Option<String>,
+    proxy: Option<String>,
+}
+
+impl TryFrom<HostEntry> for HostRule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: HostEntry) -> Result<Self, Self::Error> {
+        let mut headers = value
+            .headers
+            .into_iter()
+            .map(|(name, value)| {
+                let name = name
+                    .parse()
+                    .map_err(|_| anyhow!("invalid header name '{name}'"))?;
+                Ok((
+                    name,
+                    value.parse::<TemplateString>().map_err(|e| anyhow!(e))?,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<(HeaderName, TemplateString)>>>()?;
+        headers.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+        Ok(HostRule {
+            pattern: value.pattern,
+            headers,
+            credentials: value
+                .auth
+                .map(|auth| auth.parse().map_err(|e| anyhow!("auth: {e}")))
+                .transpose()?,
+            proxy: value.proxy,
+        })
+    }
+}
+
+/// Defaults of the requests to the hosts matching `pattern`.
+#[derive(Debug, Clone)]
+pub struct HostRule {
+    // `*` matching any characters, i.e. `*.internal.corp`
+    pub pattern: String,
+    pub headers: Vec<(HeaderName, TemplateString)>,
+    pub credentials: Option<CredentialsTemplate>,
+    pub proxy: Option<String>,
+}
+
+/// Adds the headers of the rules matching the host of `request` that it
+/// doesn't set, and their credentials if it has none.
+pub fn apply_host_rules(
+    rules: &[HostRule],
+    request: &mut HttpRequest,
+    vars: &HashMap<String, TemplateString>,
+) -> anyhow::Result<()> {
+    for rule in rules {
+        if !target::host_matches(&rule.pattern, &request.url) {
+            continue;
+        }
+
+        for (name, value) in &rule.headers {
+            if !request.headers.contains_key(name) {
+                let value = HeaderValue::from_str(&value.fill(vars)?)?;
+                request.headers.insert(name.clone(), value);
+            }
+        }
+        if request.credentials.is_none() && !request.headers.contains_key(AUTHORIZATION) {
+            if let Some(credentials) = &rule.credentials {
+                request.credentials = Some(credentials.fill(vars)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RequestIdEntry {
+    header: Option<String>,
+    format: Option<String>,
+}
+
+impl TryFrom<RequestIdEntry> for RequestId {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RequestIdEntry) -> Result<Self, Self::Error> {
+        let default = RequestId::default();
+
+        Ok(RequestId {
+            header: match value.header {
+                Some(header) => header
+                    .parse()
+                    .map_err(|_| anyhow!("invalid header name '{header}'"))?,
+                None => default.header,
+            },
+            format: match value.format {
+                Some(format) => format.parse::<TemplateString>().map_err(|e| anyhow!(e))?,
+                None => default.format,
+            },
+        })
+    }
+}
+
+static REQUEST_ID: AtomicBool = AtomicBool::new(false);
+
+/// Generates the default request id when no `[request_id]` table is set, with
+/// `--request-id`.
+pub fn set_request_id(value: bool) {
+    REQUEST_ID.store(value, Ordering::Relaxed);
+}
+
+// The configured request id, or the default one if enabled
+fn request_id(entry: Option<RequestIdEntry>) -> anyhow::Result<Option<RequestId>> {
+    match entry {
+        Some(entry) => Ok(Some(RequestId::try_from(entry).context("request_id")?)),
+        None => Ok(REQUEST_ID.load(Ordering::Relaxed).then(RequestId::default)),
+    }
+}
+
+static IP_VERSION: Mutex<Option<IpVersion>> = Mutex::new(None);
+
+/// Connects to the addresses of a single family whatever the workspace, with
+/// `-4` or `-6`.
+pub fn set_ip_version(value: Option<IpVersion>) {
+    *IP_VERSION.lock().unwrap() = value;
+}
+
+static REDACT: AtomicBool = AtomicBool::new(false);
+
+/// Redacts the default headers when no `redact` list is set, with `--redact`.
+pub fn set_redact(value: bool) {
+    REDACT.store(value, Ordering::Relaxed);
+}
+
+// The configured redaction, or the default one if enabled
+fn redaction(patterns: Option<Vec<String>>) -> Option<Redaction> {
+    match patterns {
+        Some(patterns) => Some(Redaction::default().with_patterns(patterns)),
+        None => REDACT.load(Ordering::Relaxed).then(Redaction::default),
+    }
+}
+
+/// Header generated for every request, to find it in the server logs.
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    pub header: HeaderName,
+    pub format: TemplateString,
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self {
+            header: HeaderName::from_static("x-request-id"),
+            format: "{{$random.uuid}}".parse().unwrap(),
+        }
+    }
+}
+
+impl RequestId {
+    /// Sets the header of `request`, unless it's already set, and returns its
+    /// value.
+    pub fn apply(
+        &self,
+        request: &mut HttpRequest,
+        vars: &HashMap<String, TemplateString>,
+    ) -> anyhow::Result<String> {
+        if let Some(value) = request.headers.get(&self.header) {
+            return Ok(value.to_str()?.to_owned());
+        }
+
+        let value = self.format.fill(vars)?;
+        request
+            .headers
+            .insert(self.header.clone(), HeaderValue::from_str(&value)?);
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Defaults {
+    environment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OAuth2Flow {
+    ClientCredentials,
+    AuthorizationCode,
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuth2Entry {
+    flow: OAuth2Flow,
+    token_url: String,
+    auth_url: Option<String>,
+    client_id: String,
+    client_secret: Option<String>,
+    #[serde(default)]
+    scopes: Vec<String>,
+    // 0 picks a random free port
+    #[serde(default)]
+    redirect_port: u16,
+}
+
+impl TryFrom<OAuth2Entry> for OAuth2Template {
+    type Error = anyhow::Error;
+
+    fn try_from(value: OAuth2Entry) -> Result<Self, Self::Error> {
+        let parse = |s: String| s.parse::<TemplateString>().map_err(|e| anyhow!(e));
+
+        Ok(OAuth2Template {
+            flow: match value.flow {
+                OAuth2Flow::ClientCredentials => Flow::ClientCredentials,
+                OAuth2Flow::AuthorizationCode => Flow::AuthorizationCode,
+            },
+            token_url: parse(value.token_url)?,
+            auth_url: value.auth_url.map(parse).transpose()?,
+            client_id: parse(value.client_id)?,
+            client_secret: value.client_secret.map(parse).transpose()?,
+            scopes: value.scopes,
+            redirect_port: value.redirect_port,
+        })
+    }
+}
+
+pub struct Workspace {
+    pub name: String,
+    // The `.http` file or the directory of the manifest
+    pub path: PathBuf,
+    pub http_file: HttpFile,
+    // File each request comes from
+    pub sources: Vec<PathBuf>,
+    pub environment: Option<String>,
+    pub oauth2: HashMap<String, OAuth2Template>,
+    pub proxy: Option<ProxyConfig>,
+    pub client: Option<ClientConfig>,
+    pub hosts: Vec<HostRule>,
+    pub request_id: Option<RequestId>,
+    // Of the headers of saved responses
+    pub redaction: Option<Redaction>,
+    pub snippets: Vec<Snippet>,
+    pub openers: Openers,
+}
+
+impl Workspace {
+    pub const MANIFEST: &'static str = "rq.toml";
+    pub const LOCAL_MANIFEST: &'static str = "rq.local.toml";
+
+    /// Loads the manifest of `path` if it's a directory, or the single file.
+    pub fn load(path: &Path, environment: Option<&str>) -> anyhow::Result<Self> {
+        if path.is_dir() {
+            Self::from_manifest(path, environment)
+        } else {
+            Self::from_file(path)
+        }
+    }
+
+    /// Loads a single `.http` file, along with its `.http.local` override.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let http_file = read_http_file(path)?;
+
+        Ok(Self {
+            name: path.display().to_string(),
+            path: path.to_owned(),
+            sources: vec![path.to_owned(); http_file.requests.len()],
+            http_file,
+            environment: None,
+            oauth2: HashMap::new(),
+            proxy: None,
+            client: None,
+            hosts: Vec::new(),
+            request_id: request_id(None)?,
+            redaction: redaction(None),
+            snippets: snippets::with_builtin([])?,
+            openers: Openers::default(),
+        })
+    }
+
+    /// Loads every file declared in the `rq.toml` manifest found in `dir`.
+    ///
+    /// Variables are resolved with increasing priority: manifest `[variables]`,
+    /// variables defined in the `.http` files (and their `.http.local`
+    /// overrides), the selected environment, then `rq.local.toml`.
+    pub fn from_manifest(dir: &Path, environment: Option<&str>) -> anyhow::Result<Self> {
+        let manifest_path = dir.join(Self::MANIFEST);
+        let manifest = read_manifest(&manifest_path)?;
+        let local_path = dir.join(Self::LOCAL_MANIFEST);
+        let local = if local_path.is_file() {
+            read_manifest(&local_path)?
+        } else {
+            Manifest::default()
+        };
+
+        if manifest.files.is_empty() {
+            return Err(anyhow!("{}: no files declared", manifest_path.display()));
+        }
+
+        let mut http_file = HttpFile {
+            requests: Vec::new(),
+            variables: parse_variables(manifest.variables)?,
+        };
+
+        let mut sources = Vec::new();
+        for path in &manifest.files {
+            let file = read_http_file(&dir.join(path))?;
+            sources.extend(std::iter::repeat_n(path.clone(), file.requests.len()));
+            http_file.requests.extend(file.requests);
+            http_file.variables.extend(file.variables);
+        }
+
+        let local_variables = parse_variables(local.variables)?;
+        let environment = environment
+            .or(local.defaults.environment.as_deref())
+            .or(manifest.defaults.environment.as_deref());
+
+        if let Some(name) = environment {
+            let committed = manifest.environments.get(name);
+            let local_env = local.environments.get(name);
+            if committed.is_none() && local_env.is_none() {
+                return Err(anyhow!("environment '{name}' not found"));
+            }
+
+            if let Some(vars) = committed {
+                http_file.variables.extend(parse_variables(vars.clone())?);
+            }
+            http_file.variables.extend(local_variables);
+            if let Some(vars) = local_env {
+                http_file.variables.extend(parse_variables(vars.clone())?);
+            }
+        } else {
+            http_file.variables.extend(local_variables);
+        }
+
+        let name = dir
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|s| s.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| Self::MANIFEST.into());
+
+        let oauth2 = manifest
+            .oauth2
+            .into_iter()
+            .map(|(name, entry)| {
+                let template = entry
+                    .try_into()
+                    .with_context(|| format!("oauth2 '{name}'"))?;
+                Ok((name, template))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let proxy = local.proxy.or(manifest.proxy).map(ProxyConfig::from);
+        let client = local
+            .client
+            .or(manifest.client)
+            .map(ClientConfig::try_from)
+            .transpose()
+            .context("client")?;
+        let client = with_pins(client, manifest.pins.into_iter().chain(local.pins))?;
+        let hosts = manifest
+            .hosts
+            .into_iter()
+            .chain(local.hosts)
+            .map(|entry| {
+                let pattern = entry.pattern.clone();
+                HostRule::try_from(entry).with_context(|| format!("hosts '{pattern}'"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let request_id = request_id(manifest.request_id)?;
+        let snippets = snippets::with_builtin(manifest.snippets.into_iter().chain(local.snippets))?;
+        let openers = Openers {
+            file: local.open.file.or(manifest.open.file),
+            folder: local.open.folder.or(manifest.open.folder),
+        };
+
+        Ok(Self {
+            name,
+            path: dir.to_owned(),
+            http_file,
+            sources,
+            environment: environment.map(str::to_owned),
+            oauth2,
+            proxy,
+            client,
+            hosts,
+            request_id,
+            redaction: redaction(manifest.redact),
+            snippets,
+            openers,
+        })
+    }
+}
+
+impl Workspace {
+    /// Applies its `[client]`, `[proxy]` and `hosts` proxies to the requests,
+    /// returning the settings that keep their previous value until rq is
+    /// started again, see `request::stale_settings`.
+    pub fn configure_client(&self) -> anyhow::Result<Vec<&'static str>> {
+        request::set_proxy(self.proxy.clone()).context("proxy")?;
+        request::set_host_proxies(self.hosts.iter().filter_map(|rule| {
+            let proxy = rule.proxy.as_deref()?;
+            Some((rule.pattern.as_str(), proxy))
+        }))
+        .map_err(|e| anyhow!(e))?;
+
+        let mut config = self.client.clone().unwrap_or_default();
+        config.ip_version = IP_VERSION.lock().unwrap().or(config.ip_version);
+        request::set_client_config(config);
+
+        Ok(request::stale_settings())
+    }
+}
+
+/// Snippets of the manifests of `dir`, if any, and the built-in ones.
+pub fn read_snippets(dir: &Path) -> anyhow::Result<Vec<Snippet>> {
+    let mut custom = Vec::new();
+    for name in [Workspace::MANIFEST, Workspace::LOCAL_MANIFEST] {
+        let path = dir.join(name);
+        if path.is_file() {
+            custom.extend(read_manifest(&path)?.snippets);
+        }
+    }
+
+    snippets::with_builtin(custom)
+}
+
+fn with_pins(
+    client: Option<ClientConfig>,
+    pins: impl IntoIterator<Item = (String, Vec<String>)>,
+) -> anyhow::Result<Option<ClientConfig>> {
+    let mut pins = pins.into_iter().peekable();
+    if pins.peek().is_none() {
+        return Ok(client);
+    }
+
+    let mut client = client.unwrap_or_default();
+    for (host, host_pins) in pins {
+        let host_pins = host_pins
+            .iter()
+            .map(|pin| pin.parse::<CertPin>().map_err(|e| anyhow!(e)))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .with_context(|| format!("pins of '{host}'"))?;
+        client
+            .pins
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .extend(host_pins);
+    }
+
+    Ok(Some(client))
+}
+
+fn read_manifest(path: &Path) -> anyhow::Result<Manifest> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+
+    toml::from_str(&content).with_context(|| format!("parsing {}", path.display()))
+}
+
+fn read_http_file(path: &Path) -> anyhow::Result<HttpFile> {
+    let mut file = parse_http_file(path)?;
+
+    let mut local_path = path.as_os_str().to_owned();
+    local_path.push(".local");
+    let local_path = PathBuf::from(local_path);
+
+    if local_path.is_file() {
+        let local = parse_http_file(&local_path)?;
+        file.variables.extend(local.variables);
+    }
+
+    for request in &mut file.requests {
+        resolve_protos(request, path);
+    }
+
+    Ok(file)
+}
+
+/// Makes the `@proto` files of `request`, relative to `source` as written,
+/// relative to the working directory.
+pub fn resolve_protos(request: &mut TemplateRequest, source: &Path) {
+    let dir = source.parent().unwrap_or(Path::new(""));
+    for proto in &mut request.protos {
+        *proto = dir.join(&*proto).to_string_lossy().into_owned();
+    }
+}
+
+fn parse_http_file(path: &Path) -> anyhow::Result<HttpFile> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+
+    // The parse error is kept as source, to be told apart from I/O errors
+    parse(&content)
+        .map_err(|e| anyhow::Error::new(*e))
+        .with_context(|| format!("parsing {}", path.display()))
+}
+
+fn parse_variables(
+    variables: HashMap<String, String>,
+) -> anyhow::Result<HashMap<String, TemplateString>> {
+    variables
+        .into_iter()
+        .map(|(name, value)| {
+            let value = value
+                .parse()
+                .map_err(|e| anyhow!("variable '{name}': {e}"))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, time::Duration};
+
+    use rq_core::{
+        parser::parse_request,
+        request::{ClientConfig, ProxyConfig},
+    };
+
+    use super::{resolve_protos, ClientEntry, Manifest, ProxyEntry, Workspace};
+
+    // A directory with the given files, emptied first
+    fn workspace(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("rq-workspace-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (path, content) in files {
+            fs::write(dir.join(path), content).unwrap();
+        }
+
+        dir
+    }
+
+    fn variable(workspace: &Workspace, name: &str) -> String {
+        workspace.http_file.variables[name].to_string()
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+            files = ["api.http"]
+
+            [variables]
+            base_url = "http://localhost"
+
+            [environments.prod]
+            base_url = "https://test.dev"
+
+            [defaults]
+            environment = "prod"
+
+            [proxy]
+            url = ""
+
+            [client]
+            timeout = 0
+            max_body = 2
+
+            [[hosts]]
+            match = "*.test.dev"
+            headers = { x-team = "core" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.files, [Path::new("api.http")]);
+        assert_eq!(manifest.variables["base_url"], "http://localhost");
+        assert_eq!(
+            manifest.environments["prod"]["base_url"],
+            "https://test.dev"
+        );
+        assert_eq!(manifest.defaults.environment.as_deref(), Some("prod"));
+        assert_eq!(manifest.hosts[0].pattern, "*.test.dev");
+
+        let proxy = ProxyConfig::from(manifest.proxy.unwrap());
+        assert_eq!(proxy, ProxyConfig::Disabled);
+
+        let client = ClientConfig::try_from(manifest.client.unwrap()).unwrap();
+        assert_eq!(client.timeout, None);
+        assert_eq!(client.max_body, Some(2 * 1024 * 1024));
+
+        assert!(toml::from_str::<Manifest>("files = \"api.http\"").is_err());
+    }
+
+    #[test]
+    fn test_client_entry() {
+        let client = ClientConfig::try_from(ClientEntry::default()).unwrap();
+        assert_eq!(client, ClientConfig::default());
+
+        let client = ClientConfig::try_from(ClientEntry {
+            timeout: Some(1.5),
+            max_body: Some(0),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(client.timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(client.max_body, None);
+
+        assert!(ClientConfig::try_from(ClientEntry {
+            timeout: Some(-1.0),
+            ..Default::default()
+        })
+        .is_err());
+
+        let proxy = ProxyConfig::from(ProxyEntry {
+            url: "http://proxy:8080".into(),
+            no_proxy: None,
+        });
+        assert!(matches!(proxy, ProxyConfig::Url { url, .. } if url == "http://proxy:8080"));
+    }
+
+    #[test]
+    fn test_from_manifest() {
+        let dir = workspace(
+            "merge",
+            &[
+                (
+                    "rq.toml",
+                    r#"
+                    files = ["a.http", "b.http"]
+
+                    [variables]
+                    manifest = "manifest"
+                    file = "manifest"
+                    env = "manifest"
+                    local = "manifest"
+                    local_env = "manifest"
+
+                    [environments.dev]
+                    env = "dev"
+                    local = "dev"
+                    local_env = "dev"
+
+                    [proxy]
+                    url = "http://committed:8080"
+                    "#,
+                ),
+                (
+                    "rq.local.toml",
+                    r#"
+                    [defaults]
+                    environment = "dev"
+
+                    [variables]
+                    local = "local"
+                    local_env = "local"
+
+                    [environments.dev]
+                    local_env = "local_dev"
+
+                    [proxy]
+                    url = ""
+                    "#,
+                ),
+                (
+                    "a.http",
+                    "@file = a\n@env = a\n\n###\n\nGET http://a.dev\n\n",
+                ),
+                (
+                    "b.http",
+                    "GET http://b.dev\n\n###\n\nGET http://b.dev/2\n\n",
+                ),
+            ],
+        );
+
+        let workspace = Workspace::from_manifest(&dir, None).unwrap();
+        assert_eq!(workspace.environment.as_deref(), Some("dev"));
+        assert_eq!(workspace.http_file.requests.len(), 3);
+        assert_eq!(
+            workspace.sources,
+            ["a.http", "b.http", "b.http"].map(Path::new)
+        );
+        assert_eq!(variable(&workspace, "manifest"), "manifest");
+        assert_eq!(variable(&workspace, "file"), "a");
+        assert_eq!(variable(&workspace, "env"), "dev");
+        assert_eq!(variable(&workspace, "local"), "local");
+        assert_eq!(variable(&workspace, "local_env"), "local_dev");
+        assert_eq!(workspace.proxy, Some(ProxyConfig::Disabled));
+
+        let error = Workspace::from_manifest(&dir, Some("prod")).err().unwrap();
+        assert_eq!(error.to_string(), "environment 'prod' not found");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_files() {
+        let dir = workspace("empty", &[("rq.toml", "[variables]\n")]);
+
+        let error = Workspace::from_manifest(&dir, None).err().unwrap();
+        assert!(error.to_string().ends_with("no files declared"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_protos() {
+        let mut request = parse_request(
+            "# @proto greeter.proto ../common.proto /abs/x.proto\nGRPC http://localhost:50051/a.B/C",
+        )
+        .unwrap();
+
+        resolve_protos(&mut request, Path::new("api/grpc.http"));
+        assert_eq!(
+            request.protos,
+            ["api/greeter.proto", "api/../common.proto", "/abs/x.proto"]
+        );
+
+        let mut request =
+            parse_request("# @proto greeter.proto\nGRPC http://localhost/a.B/C").unwrap();
+        resolve_protos(&mut request, Path::new("grpc.http"));
+        assert_eq!(request.protos, ["greeter.proto"]);
+    }
+}
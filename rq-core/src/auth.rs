@@ -0,0 +1,147 @@
+//! Authentication helpers, injecting credentials into outgoing requests.
+
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::parser::variables::{resolver::Resolver, FillError, TemplateString};
+
+pub mod digest;
+#[cfg(feature = "client")]
+pub mod oauth2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheme {
+    Basic,
+    Digest,
+}
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub scheme: Scheme,
+    pub user: String,
+    pub password: String,
+}
+
+/// Credentials of the `# @auth <scheme> <user> <password>` directive.
+#[derive(Debug, Clone)]
+pub struct CredentialsTemplate {
+    pub scheme: Scheme,
+    pub user: TemplateString,
+    pub password: TemplateString,
+}
+
+impl CredentialsTemplate {
+    pub fn fill(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+    ) -> Result<Credentials, FillError> {
+        self.fill_with(parameters, &())
+    }
+
+    pub fn fill_with(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<Credentials, FillError> {
+        Ok(Credentials {
+            scheme: self.scheme,
+            user: self.user.fill_with(parameters, resolver)?,
+            password: self.password.fill_with(parameters, resolver)?,
+        })
+    }
+}
+
+impl FromStr for CredentialsTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s.split_whitespace().collect::<Vec<_>>();
+        let [scheme, user, password] = parts.as_slice() else {
+            return Err("expected '<scheme> <user> <password>'".into());
+        };
+
+        let scheme = match scheme.to_lowercase().as_str() {
+            "basic" => Scheme::Basic,
+            "digest" => Scheme::Digest,
+            scheme => return Err(format!("unknown scheme '{scheme}'")),
+        };
+
+        Ok(Self {
+            scheme,
+            user: user.parse()?,
+            password: password.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub access_token: String,
+    expires_at: Option<Instant>,
+}
+
+impl Token {
+    pub fn new(access_token: String, expires_in: Option<Duration>) -> Self {
+        Self {
+            access_token,
+            // Too far in the future to be represented, as never
+            expires_at: expires_in.and_then(|d| Instant::now().checked_add(d)),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| Instant::now() >= expires_at)
+    }
+}
+
+/// Tokens by key, i.e. `<environment>/<config name>`.
+#[derive(Debug, Default)]
+pub struct TokenCache {
+    tokens: HashMap<String, Token>,
+}
+
+impl TokenCache {
+    /// Returns the cached token for `key`, unless it's expired.
+    pub fn get(&self, key: &str) -> Option<&Token> {
+        self.tokens.get(key).filter(|token| !token.is_expired())
+    }
+
+    pub fn insert(&mut self, key: String, token: Token) {
+        self.tokens.insert(key, token);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.tokens.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Token, TokenCache};
+
+    #[test]
+    fn test_cache_expiry() {
+        let mut cache = TokenCache::default();
+        cache.insert("dev/api".into(), Token::new("a".into(), None));
+        cache.insert(
+            "prod/api".into(),
+            Token::new("b".into(), Some(Duration::ZERO)),
+        );
+
+        assert_eq!(cache.get("dev/api").unwrap().access_token, "a");
+        assert!(cache.get("prod/api").is_none());
+        assert!(cache.get("foo").is_none());
+    }
+
+    #[test]
+    fn test_huge_expiry() {
+        let token = Token::new("a".into(), Some(Duration::from_secs(u64::MAX)));
+        assert!(!token.is_expired());
+    }
+}
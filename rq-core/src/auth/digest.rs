@@ -0,0 +1,153 @@
+//! HTTP Digest authentication (RFC 7616), answering a `WWW-Authenticate:
+//! Digest ...` challenge.
+
+use std::collections::HashMap;
+
+use http::Method;
+use md5::Md5;
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
+use sha2::{Digest, Sha256};
+
+/// Returns the `Authorization` header value answering `challenge`, `None` if
+/// it isn't a supported digest challenge.
+pub fn authorization(
+    challenge: &str,
+    method: &Method,
+    uri: &str,
+    user: &str,
+    password: &str,
+) -> Option<String> {
+    let cnonce: String = StdRng::from_entropy()
+        .sample_iter(Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+
+    authorization_with_cnonce(challenge, method, uri, user, password, &cnonce)
+}
+
+fn authorization_with_cnonce(
+    challenge: &str,
+    method: &Method,
+    uri: &str,
+    user: &str,
+    password: &str,
+    cnonce: &str,
+) -> Option<String> {
+    let params = parse_challenge(challenge)?;
+    let realm = params.get("realm")?;
+    let nonce = params.get("nonce")?;
+    let algorithm = params.get("algorithm").map_or("MD5", String::as_str);
+
+    let hash: fn(&str) -> String = match algorithm.to_uppercase().as_str() {
+        "MD5" => |s| hex(&Md5::digest(s)),
+        "SHA-256" => |s| hex(&Sha256::digest(s)),
+        _ => return None,
+    };
+
+    // Only `auth` is supported, `auth-int` would require hashing the body
+    let qop = params
+        .get("qop")
+        .map(|qop| qop.split(',').map(str::trim).any(|qop| qop == "auth"));
+    if qop == Some(false) {
+        return None;
+    }
+
+    let ha1 = hash(&format!("{user}:{realm}:{password}"));
+    let ha2 = hash(&format!("{method}:{uri}"));
+    let nc = "00000001";
+
+    let mut header = format!(
+        r#"Digest username="{user}", realm="{realm}", nonce="{nonce}", uri="{uri}", algorithm={algorithm}"#
+    );
+
+    let response = if qop.is_some() {
+        header += &format!(r#", qop=auth, nc={nc}, cnonce="{cnonce}""#);
+        hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}"))
+    } else {
+        hash(&format!("{ha1}:{nonce}:{ha2}"))
+    };
+    header += &format!(r#", response="{response}""#);
+
+    if let Some(opaque) = params.get("opaque") {
+        header += &format!(r#", opaque="{opaque}""#);
+    }
+
+    Some(header)
+}
+
+fn parse_challenge(challenge: &str) -> Option<HashMap<String, String>> {
+    let (scheme, rest) = challenge.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("digest") {
+        return None;
+    }
+
+    let mut params = HashMap::new();
+    let mut rest = rest.trim_start();
+
+    while !rest.is_empty() {
+        let (key, after_key) = rest.split_once('=')?;
+        let after_key = after_key.trim_start();
+
+        let (value, after_value) = match after_key.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => {
+                let end = after_key.find(',').unwrap_or(after_key.len());
+                (after_key[..end].trim(), &after_key[end..])
+            }
+        };
+
+        params.insert(key.trim().to_lowercase(), value.to_owned());
+        rest = after_value
+            .trim_start()
+            .trim_start_matches(',')
+            .trim_start();
+    }
+
+    Some(params)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+
+    use super::{authorization_with_cnonce, parse_challenge};
+
+    #[test]
+    fn test_parse_challenge() {
+        let params = parse_challenge(
+            r#"Digest realm="test@host", qop="auth,auth-int", nonce="abc", opaque=xyz"#,
+        )
+        .unwrap();
+
+        assert_eq!(params["realm"], "test@host");
+        assert_eq!(params["qop"], "auth,auth-int");
+        assert_eq!(params["nonce"], "abc");
+        assert_eq!(params["opaque"], "xyz");
+        assert!(parse_challenge("Basic realm=\"foo\"").is_none());
+    }
+
+    #[test]
+    fn test_authorization() {
+        // RFC 2617, section 3.5
+        let header = authorization_with_cnonce(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+            &Method::GET,
+            "/dir/index.html",
+            "Mufasa",
+            "Circle Of Life",
+            "0a4f113b",
+        )
+        .unwrap();
+
+        assert!(header.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+        assert!(header.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+    }
+}
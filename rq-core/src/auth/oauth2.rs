@@ -0,0 +1,252 @@
+//! OAuth2 token acquisition, supporting the client credentials and the
+//! authorization code (with PKCE) flows.
+
+use std::{collections::HashMap, time::Duration};
+
+use base64::Engine;
+use rand::{distributions::Alphanumeric, rngs::StdRng, Rng, SeedableRng};
+use reqwest::Url;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    parser::variables::{FillError, TemplateString},
+    request::CLIENT,
+};
+
+use super::Token;
+
+// For the user to log in, once the browser is opened
+const AUTHORIZE_TIMEOUT: Duration = Duration::from_secs(300);
+// For the browser to send the redirect, once connected
+const REDIRECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Of the head of the redirect, the only part read
+const MAX_REDIRECT_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    ClientCredentials,
+    // Authorization code with PKCE, receiving the code on a localhost redirect
+    AuthorizationCode,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuth2Template {
+    pub flow: Flow,
+    pub token_url: TemplateString,
+    pub auth_url: Option<TemplateString>,
+    pub client_id: TemplateString,
+    pub client_secret: Option<TemplateString>,
+    pub scopes: Vec<String>,
+    pub redirect_port: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct OAuth2Config {
+    pub flow: Flow,
+    pub token_url: String,
+    pub auth_url: Option<String>,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+    pub redirect_port: u16,
+}
+
+#[derive(Debug, Error)]
+pub enum OAuth2Error {
+    #[error("missing auth_url for the authorization code flow")]
+    MissingAuthUrl,
+    #[error("invalid url: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error("redirect listener: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("authorization failed: {0}")]
+    Authorization(String),
+    #[error("token request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("invalid token response: {0}")]
+    InvalidResponse(String),
+}
+
+impl OAuth2Template {
+    pub fn fill(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+    ) -> Result<OAuth2Config, FillError> {
+        Ok(OAuth2Config {
+            flow: self.flow,
+            token_url: self.token_url.fill(parameters)?,
+            auth_url: self
+                .auth_url
+                .as_ref()
+                .map(|url| url.fill(parameters))
+                .transpose()?,
+            client_id: self.client_id.fill(parameters)?,
+            client_secret: self
+                .client_secret
+                .as_ref()
+                .map(|secret| secret.fill(parameters))
+                .transpose()?,
+            scopes: self.scopes.clone(),
+            redirect_port: self.redirect_port,
+        })
+    }
+}
+
+impl OAuth2Config {
+    /// Acquires a new token. For the authorization code flow, `open_url` is
+    /// called with the URL the user has to visit to grant access.
+    pub async fn acquire<F>(&self, open_url: F) -> Result<Token, OAuth2Error>
+    where
+        F: FnOnce(&str),
+    {
+        let mut form = vec![("client_id", self.client_id.clone())];
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret.clone()));
+        }
+
+        match self.flow {
+            Flow::ClientCredentials => {
+                form.push(("grant_type", "client_credentials".into()));
+                if !self.scopes.is_empty() {
+                    form.push(("scope", self.scopes.join(" ")));
+                }
+            }
+            Flow::AuthorizationCode => {
+                let (code, redirect_uri, verifier) = self.authorize(open_url).await?;
+
+                form.extend([
+                    ("grant_type", "authorization_code".into()),
+                    ("code", code),
+                    ("redirect_uri", redirect_uri),
+                    ("code_verifier", verifier),
+                ]);
+            }
+        }
+
+        let response = CLIENT
+            .post(&self.token_url)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+
+        let access_token = response
+            .get("access_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| OAuth2Error::InvalidResponse("missing access_token".into()))?;
+        let expires_in = response
+            .get("expires_in")
+            .and_then(Value::as_u64)
+            .map(Duration::from_secs);
+
+        Ok(Token::new(access_token.to_owned(), expires_in))
+    }
+
+    // Returns the authorization code, the redirect uri and the PKCE verifier
+    async fn authorize<F>(&self, open_url: F) -> Result<(String, String, String), OAuth2Error>
+    where
+        F: FnOnce(&str),
+    {
+        let auth_url = self.auth_url.as_ref().ok_or(OAuth2Error::MissingAuthUrl)?;
+
+        let listener = TcpListener::bind(("127.0.0.1", self.redirect_port)).await?;
+        let redirect_uri = format!(
+            "http://127.0.0.1:{}/callback",
+            listener.local_addr()?.port()
+        );
+
+        let verifier = random_string(64);
+        let state = random_string(16);
+        let challenge =
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(&verifier));
+
+        let mut url = Url::parse(auth_url)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("scope", &self.scopes.join(" "))
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        open_url(url.as_str());
+
+        let (mut stream, _) = tokio::time::timeout(AUTHORIZE_TIMEOUT, listener.accept())
+            .await
+            .map_err(|_| {
+                OAuth2Error::Authorization("timed out waiting for the redirect".into())
+            })??;
+        let head = tokio::time::timeout(REDIRECT_TIMEOUT, read_head(&mut stream))
+            .await
+            .map_err(|_| OAuth2Error::Authorization("timed out reading the redirect".into()))??;
+        let request = String::from_utf8_lossy(&head);
+
+        let body = "Authorization complete, you can go back to rq.";
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        // i.e. `GET /callback?code=...&state=... HTTP/1.1`
+        let target = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .ok_or_else(|| OAuth2Error::Authorization("malformed redirect".into()))?;
+        let params: HashMap<_, _> = Url::parse("http://127.0.0.1")?
+            .join(target)?
+            .query_pairs()
+            .into_owned()
+            .collect();
+
+        if let Some(error) = params.get("error") {
+            return Err(OAuth2Error::Authorization(error.clone()));
+        }
+        if params.get("state") != Some(&state) {
+            return Err(OAuth2Error::Authorization("state mismatch".into()));
+        }
+        let code = params
+            .get("code")
+            .ok_or_else(|| OAuth2Error::Authorization("missing code".into()))?;
+
+        Ok((code.clone(), redirect_uri, verifier))
+    }
+}
+
+// Reads up to the empty line ending the head of an HTTP request, which may
+// take several reads
+async fn read_head(stream: &mut TcpStream) -> Result<Vec<u8>, OAuth2Error> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 || head.len() + n > MAX_REDIRECT_SIZE {
+            return Err(OAuth2Error::Authorization("malformed redirect".into()));
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+
+    Ok(head)
+}
+
+fn random_string(len: usize) -> String {
+    StdRng::from_entropy()
+        .sample_iter(Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
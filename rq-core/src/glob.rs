@@ -0,0 +1,39 @@
+//! Matching of names with `*` wildcards, i.e. headers or hosts.
+
+/// Whether `pattern`, `*` matching any characters, matches the whole `name`.
+pub(crate) fn glob(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // Without `*`, the only part has to match entirely
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob;
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("*.internal.corp", "api.internal.corp"));
+        assert!(glob("*.internal.corp", "a.b.internal.corp"));
+        assert!(!glob("*.internal.corp", "internal.corp"));
+        assert!(glob("api.*.corp", "api.internal.corp"));
+        assert!(glob("localhost", "localhost"));
+        assert!(!glob("localhost", "localhost.corp"));
+    }
+}
@@ -0,0 +1,18 @@
+//! Secrets stored in the OS keyring, available in templates as
+//! `{{$keyring.<service>.<user>}}`.
+
+pub use keyring::Error;
+
+use keyring::Entry;
+
+pub fn get(service: &str, user: &str) -> Result<String, Error> {
+    Entry::new(service, user)?.get_password()
+}
+
+pub fn set(service: &str, user: &str, secret: &str) -> Result<(), Error> {
+    Entry::new(service, user)?.set_password(secret)
+}
+
+pub fn delete(service: &str, user: &str) -> Result<(), Error> {
+    Entry::new(service, user)?.delete_credential()
+}
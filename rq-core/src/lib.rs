@@ -1,5 +1,9 @@
 #[macro_use]
 extern crate pest_derive;
 
+pub mod auth;
+mod glob;
+#[cfg(feature = "keyring")]
+pub mod keyring;
 pub mod parser;
 pub mod request;
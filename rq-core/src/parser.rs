@@ -2,45 +2,354 @@ use pest::error::Error;
 use pest::iterators::Pair;
 use pest::Parser;
 
-use reqwest::{header::HeaderMap, Method, Version};
-use std::collections::HashMap;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::{
+    header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, HOST},
+    Method, Version,
+};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
+use std::net::IpAddr;
+use std::ops::Range;
 use std::result::Result;
 
-use self::variables::{FillError, HashTemplateMap, TemplateString};
+use thiserror::Error;
 
+use url::Url;
+
+use crate::auth::{Credentials, CredentialsTemplate, Scheme};
+
+use self::capture::Capture;
+use self::session::SessionAuth;
+use self::variables::{resolver::Resolver, FillError, TemplateMap, TemplateString, Variable};
+
+pub mod capture;
+pub mod scenario;
+pub mod session;
+pub mod target;
 mod values;
 pub mod variables;
 
+/// Variable prefixed to relative urls, i.e. `GET /users/1`, usually
+/// defined by each environment.
+pub const BASE_URL: &str = "base_url";
+
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 struct HttpParser;
 
+/// Part of a request, where filling it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestPart {
+    Url,
+    // Name of the param or header
+    Query(String),
+    Header(String),
+    Body,
+    Credentials,
+}
+
+impl Display for RequestPart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestPart::Url => write!(f, "url"),
+            RequestPart::Query(name) => write!(f, "query param '{name}'"),
+            RequestPart::Header(name) => write!(f, "header '{name}'"),
+            RequestPart::Body => write!(f, "body"),
+            RequestPart::Credentials => write!(f, "credentials"),
+        }
+    }
+}
+
+/// [`FillError`] of a request, with where it happened.
+#[derive(Debug, Clone, Error, PartialEq)]
+#[error("{request} (line {line}), {part}: {source}")]
+pub struct RequestFillError {
+    // `'name'`, or the method and url of unnamed requests
+    pub request: String,
+    pub line: usize,
+    pub part: RequestPart,
+    // Of the offending variable or command in the template of `part`, as
+    // displayed, see `TemplateString::span_of`
+    pub span: Option<Range<usize>>,
+    pub source: FillError,
+}
+
 #[derive(Debug)]
 pub struct TemplateRequest {
+    // Of the request line in its file, from 1
+    pub line: usize,
+    pub name: Option<String>,
+    pub depends: Vec<String>,
+    // Labels to group requests by, i.e. `# @tag users admin`
+    pub tags: Vec<String>,
+    // Usage hints, one per `# @note` line
+    pub notes: Vec<String>,
+    pub auth: Option<SessionAuth>,
+    pub credentials: Option<CredentialsTemplate>,
+    // Name of the OAuth2 configuration providing the bearer token
+    pub oauth2: Option<String>,
     pub method: Method,
     pub url: TemplateString,
-    pub query: HashTemplateMap,
-    pub version: Version,
-    pub headers: HashTemplateMap,
+    pub query: TemplateMap,
+    // Indexes of the `query` params marked as already encoded, i.e. `?q=={{value}}`
+    pub encoded_query: HashSet<usize>,
+    // Indexes of the `query` params left out until reloaded, see `toggle_query`
+    pub disabled_query: HashSet<usize>,
+    // As written in the request line, HTTP/1.1 being sent otherwise
+    pub version: Option<Version>,
+    pub headers: TemplateMap,
+    // Indexes of the `headers` commented out, i.e. `# Cache-Control: no-cache`
+    pub disabled_headers: HashSet<usize>,
     pub body: TemplateString,
+    pub captures: Vec<Capture>,
+    // `.proto` files describing a gRPC service
+    pub protos: Vec<String>,
+    // `# @ipv4` or `# @ipv6`
+    pub ip_version: Option<IpVersion>,
+    // Why the last `fill` failed, kept by the caller to point at it
+    pub fill_error: Option<Box<RequestFillError>>,
 }
 
 impl TemplateRequest {
     pub fn fill(
         &self,
         parameters: &HashMap<String, TemplateString>,
-    ) -> Result<HttpRequest, FillError> {
+    ) -> Result<HttpRequest, Box<RequestFillError>> {
+        self.fill_with(parameters, &())
+    }
+
+    /// Like `fill`, resolving custom functions and filters with `resolver`.
+    #[tracing::instrument(skip_all, fields(name = self.name.as_deref(), url = %self.url))]
+    pub fn fill_with(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<HttpRequest, Box<RequestFillError>> {
+        let fill = |part: RequestPart, template: &TemplateString| {
+            template
+                .fill_with(parameters, resolver)
+                .map_err(|e| self.fill_error(part, Some(template), e))
+        };
+
+        let url = self
+            .fill_url(parameters, resolver)
+            .and_then(|url| target::normalize(&url))
+            .map_err(|e| self.fill_error(RequestPart::Url, Some(&self.url), e))?;
+
+        let query = self
+            .query
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.disabled_query.contains(i))
+            .map(|(i, (k, v))| {
+                let v = fill(RequestPart::Query(k.clone()), v)?;
+                let v = if self.encoded_query.contains(&i) {
+                    v
+                } else {
+                    encode_query_component(&v)
+                };
+
+                Ok((encode_query_component(k), v))
+            })
+            .collect::<Result<_, Box<RequestFillError>>>()?;
+
+        let headers = self
+            .headers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.disabled_headers.contains(i))
+            .map(|(_, (k, v))| self.fill_header(k, v, parameters, resolver))
+            .collect::<Result<_, Box<RequestFillError>>>()?;
+
         let req = HttpRequest {
             method: self.method.clone(),
-            url: self.url.fill(parameters)?,
-            query: self.query.fill(parameters)?,
-            version: self.version,
-            headers: (&self.headers.fill(parameters)?).try_into().unwrap(),
-            body: self.body.fill(parameters)?,
+            url,
+            query,
+            version: self.version.unwrap_or_default(),
+            headers: header_map(headers),
+            body: fill(RequestPart::Body, &self.body)?,
+            credentials: self
+                .credentials
+                .as_ref()
+                .map(|credentials| credentials.fill_with(parameters, resolver))
+                .transpose()
+                .map_err(|e| self.fill_error(RequestPart::Credentials, None, e))?,
+            protos: self.protos.clone(),
+            ip_version: self.ip_version,
         };
 
         Ok(req)
     }
+
+    // Checked before sending, as reqwest would refuse it with an opaque error
+    fn fill_header(
+        &self,
+        name: &str,
+        template: &TemplateString,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<(HeaderName, HeaderValue), Box<RequestFillError>> {
+        let part = RequestPart::Header(name.to_owned());
+        let invalid = |reason: String| FillError::InvalidHeader { reason };
+
+        let name = HeaderName::try_from(name).map_err(|_| {
+            let reason = match name.chars().find(|c| !is_token_char(*c)) {
+                Some(c) => format!("the name contains {}", describe_char(c)),
+                None => "the name is not a valid token".to_owned(),
+            };
+            self.fill_error(part.clone(), None, invalid(reason))
+        })?;
+
+        let fragments = template
+            .fill_fragments_with(parameters, resolver)
+            .map_err(|e| self.fill_error(part.clone(), Some(template), e))?;
+        let value = fragments.concat();
+
+        // Non-ASCII is refused too, as servers disagree on how to decode it
+        let offending = value
+            .char_indices()
+            .find(|(_, c)| (c.is_ascii_control() && *c != '\t') || !c.is_ascii());
+        if let Some((at, c)) = offending {
+            // Fragment the character comes from, i.e. a variable ending with a newline
+            let mut end = 0;
+            let fragment = fragments.iter().position(|fragment| {
+                end += fragment.len();
+                at < end
+            });
+
+            let reason = format!("the value contains {}", describe_char(c));
+            let mut error = self.fill_error(part, None, invalid(reason));
+            error.span = fragment.and_then(|idx| template.fragment_span(idx));
+            return Err(error);
+        }
+
+        let value = HeaderValue::try_from(value)
+            .map_err(|e| self.fill_error(part, Some(template), invalid(e.to_string())))?;
+
+        Ok((name, value))
+    }
+
+    fn fill_error(
+        &self,
+        part: RequestPart,
+        template: Option<&TemplateString>,
+        source: FillError,
+    ) -> Box<RequestFillError> {
+        let request = match &self.name {
+            Some(name) => format!("'{name}'"),
+            None => format!("{} {}", self.method, self.url),
+        };
+
+        Box::new(RequestFillError {
+            request,
+            line: self.line,
+            part,
+            span: template.and_then(|template| template.span_of(&source)),
+            source,
+        })
+    }
+
+    fn fill_url(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<String, FillError> {
+        let url = self.url.fill_with(parameters, resolver)?;
+        if !url.starts_with('/') {
+            return Ok(url);
+        }
+
+        let base_url = parameters
+            .get(BASE_URL)
+            .ok_or_else(|| Variable::new(BASE_URL))?
+            .fill_with(parameters, resolver)?;
+
+        Ok(format!("{}{url}", base_url.trim_end_matches('/')))
+    }
+
+    /// Disables the header at `idx` in `headers`, or enables it back.
+    pub fn toggle_header(&mut self, idx: usize) {
+        if !self.disabled_headers.remove(&idx) {
+            self.disabled_headers.insert(idx);
+        }
+    }
+
+    /// Leaves out the query param at `idx` in `query`, or puts it back. Unlike
+    /// headers, params can't be commented out in the file, so this only lasts
+    /// until the file is parsed again.
+    pub fn toggle_query(&mut self, idx: usize) {
+        if !self.disabled_query.remove(&idx) {
+            self.disabled_query.insert(idx);
+        }
+    }
+
+    /// Replaces the value of the query param at `idx`, i.e. to try another one
+    /// without editing the file.
+    pub fn set_query_value(&mut self, idx: usize, value: TemplateString) {
+        self.query.set_value(idx, value);
+    }
+
+    /// Query params filled with `parameters`, the ones marked as encoded
+    /// being percent-decoded: the values as read by the server.
+    pub fn decoded_query(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+    ) -> Vec<(String, Result<String, FillError>)> {
+        self.query
+            .iter()
+            .enumerate()
+            .map(|(i, (name, value))| {
+                let value = value.fill(parameters).map(|value| {
+                    if self.encoded_query.contains(&i) {
+                        percent_decode_str(&value).decode_utf8_lossy().into_owned()
+                    } else {
+                        value
+                    }
+                });
+
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Returns the names of the variables this request references that
+    /// neither `parameters` nor `resolver` define, which would make
+    /// `fill_with` fail.
+    pub fn missing_variables(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> HashSet<String> {
+        let mut missing = self.url.missing_variables(parameters, resolver);
+        if self.url.to_string().starts_with('/') {
+            match parameters.get(BASE_URL) {
+                Some(base_url) => missing.extend(base_url.missing_variables(parameters, resolver)),
+                None => {
+                    missing.insert(BASE_URL.to_owned());
+                }
+            }
+        }
+        for (i, (_, value)) in self.query.iter().enumerate() {
+            if !self.disabled_query.contains(&i) {
+                missing.extend(value.missing_variables(parameters, resolver));
+            }
+        }
+        for (i, (_, value)) in self.headers.iter().enumerate() {
+            if !self.disabled_headers.contains(&i) {
+                missing.extend(value.missing_variables(parameters, resolver));
+            }
+        }
+        missing.extend(self.body.missing_variables(parameters, resolver));
+
+        if let Some(credentials) = &self.credentials {
+            missing.extend(credentials.user.missing_variables(parameters, resolver));
+            missing.extend(credentials.password.missing_variables(parameters, resolver));
+        }
+
+        missing
+    }
 }
 
 fn http_version_from_str(input: &str) -> Version {
@@ -54,51 +363,275 @@ fn http_version_from_str(input: &str) -> Version {
     }
 }
 
+// Everything but RFC 3986 unreserved characters is percent-encoded, so `&`,
+// `=`, `+` and spaces (`%20`) inside values are preserved
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+fn encode_query_component(s: &str) -> String {
+    utf8_percent_encode(s, QUERY_ENCODE_SET).to_string()
+}
+
+// See RFC 9110, section 5.6.2
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn describe_char(c: char) -> String {
+    match c {
+        ' ' => "a space".to_owned(),
+        '\n' | '\r' => "a line break".to_owned(),
+        c if c.is_control() => format!("the control character {:#04x}", c as u32),
+        c if !c.is_ascii() => format!("the non-ASCII character '{c}'"),
+        c => format!("'{c}'"),
+    }
+}
+
+fn header_map(headers: Vec<(HeaderName, HeaderValue)>) -> HeaderMap {
+    headers
+        .into_iter()
+        .fold(HeaderMap::new(), |mut map, (k, v)| {
+            map.append(k, v);
+            map
+        })
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct HttpRequest {
     pub method: Method,
     pub url: String,
-    pub query: HashMap<String, String>,
+    // Already percent-encoded
+    pub query: Vec<(String, String)>,
     pub version: Version,
     pub headers: HeaderMap,
     pub body: String,
+    pub credentials: Option<Credentials>,
+    pub protos: Vec<String>,
+    // Address family the host is resolved to, any by default
+    pub ip_version: Option<IpVersion>,
+}
+
+/// Address family of the connections, like curl's `-4` and `-6`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    V4,
+    V6,
+}
+
+impl IpVersion {
+    pub fn matches(self, ip: IpAddr) -> bool {
+        match self {
+            IpVersion::V4 => ip.is_ipv4(),
+            IpVersion::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl Display for IpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpVersion::V4 => write!(f, "IPv4"),
+            IpVersion::V6 => write!(f, "IPv6"),
+        }
+    }
+}
+
+impl HttpRequest {
+    /// Returns the url with the query params appended.
+    pub fn full_url(&self) -> String {
+        if self.query.is_empty() {
+            return self.url.clone();
+        }
+
+        let query = self
+            .query
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+
+        format!("{}{separator}{query}", self.url)
+    }
+
+    /// The request as an HTTP/1.1 message, with `\n` line endings: request
+    /// line, headers, empty line and body. `Host`, `Content-Length` and basic
+    /// credentials are added like the client does, but not the headers it
+    /// adds by default, i.e. `User-Agent`.
+    pub fn to_http1(&self) -> String {
+        let url = Url::parse(&self.full_url()).ok();
+        let target = url.as_ref().map_or_else(
+            || self.full_url(),
+            |url| match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_owned(),
+            },
+        );
+        let version = match self.version {
+            Version::HTTP_10 => "HTTP/1.0",
+            _ => "HTTP/1.1",
+        };
+
+        let mut message = format!("{} {target} {version}\n", self.method);
+        let mut header = |name: &str, value: &str| {
+            let _ = writeln!(message, "{name}: {value}");
+        };
+
+        if let Some(url) = &url {
+            if !self.headers.contains_key(HOST) {
+                match (url.host_str(), url.port()) {
+                    (Some(host), Some(port)) => header("host", &format!("{host}:{port}")),
+                    (Some(host), None) => header("host", host),
+                    _ => (),
+                }
+            }
+        }
+        for (name, value) in &self.headers {
+            header(name.as_str(), &String::from_utf8_lossy(value.as_bytes()));
+        }
+
+        let userinfo = url
+            .as_ref()
+            .filter(|url| !url.username().is_empty())
+            .map(|url| {
+                let decode = |s: &str| percent_decode_str(s).decode_utf8_lossy().into_owned();
+                (
+                    decode(url.username()),
+                    decode(url.password().unwrap_or_default()),
+                )
+            });
+        let basic = match &self.credentials {
+            Some(credentials) if credentials.scheme == Scheme::Basic => {
+                Some((credentials.user.clone(), credentials.password.clone()))
+            }
+            _ => userinfo,
+        };
+        if let (Some((user, password)), false) = (basic, self.headers.contains_key(AUTHORIZATION)) {
+            header(
+                "authorization",
+                &format!("Basic {}", STANDARD.encode(format!("{user}:{password}"))),
+            );
+        }
+
+        if !self.body.is_empty() && !self.headers.contains_key(CONTENT_LENGTH) {
+            header("content-length", &self.body.len().to_string());
+        }
+
+        message.push('\n');
+        message.push_str(&self.body);
+        message
+    }
 }
 
 impl<'i> From<Pair<'i, Rule>> for TemplateRequest {
     fn from(request: Pair<'i, Rule>) -> Self {
         let mut pairs = request.into_inner().peekable();
 
+        let mut name = None;
+        let mut depends = Vec::new();
+        let mut tags = Vec::new();
+        let mut notes = Vec::new();
+        let mut auth = None;
+        let mut credentials = None;
+        let mut oauth2 = None;
+        let mut captures = Vec::new();
+        let mut protos = Vec::new();
+        let mut ip_version = None;
+        while let Some(directive) = pairs.next_if(|pair| pair.as_rule() == Rule::directive) {
+            let mut inner = directive.into_inner();
+            let directive_name = inner.next().unwrap().as_str();
+            let value = inner
+                .next()
+                .map(|pair| pair.as_str().trim())
+                .unwrap_or_default();
+
+            // Unknown or malformed directives are treated as plain comments
+            match directive_name {
+                "name" if !value.is_empty() => name = Some(value.to_owned()),
+                "depends" => depends.extend(value.split_whitespace().map(str::to_owned)),
+                "tag" => tags.extend(value.split_whitespace().map(str::to_owned)),
+                "note" => notes.push(value.to_owned()),
+                "capture" => captures.extend(value.parse::<Capture>()),
+                // Either `@auth <scheme> <user> <password>` or the session `@auth [expiry]`
+                "auth" => match value.parse() {
+                    Ok(value) => credentials = Some(value),
+                    Err(_) => auth = value.parse().ok(),
+                },
+                "oauth2" if !value.is_empty() => oauth2 = Some(value.to_owned()),
+                "proto" => protos.extend(value.split_whitespace().map(str::to_owned)),
+                "ipv4" => ip_version = Some(IpVersion::V4),
+                "ipv6" => ip_version = Some(IpVersion::V6),
+                _ => (),
+            }
+        }
+
         let method: Method = pairs
             .next_if(|pair| pair.as_rule() == Rule::method)
-            .map(|pair| pair.as_str().try_into().unwrap())
+            .map(|pair| Method::from_bytes(pair.as_str().as_bytes()).unwrap())
             .unwrap_or_default();
 
-        let url = pairs.next().unwrap().into();
-
-        let query: HashTemplateMap = pairs
-            .next_if(|pair| pair.as_rule() == Rule::query)
-            .map(|pair| pair.into())
-            .unwrap_or_default();
+        let url_pair = pairs.next().unwrap();
+        let line = url_pair.as_span().start_pos().line_col().0;
+        let url = url_pair.into();
+
+        let query_pair = pairs.next_if(|pair| pair.as_rule() == Rule::query);
+        let encoded_query = query_pair
+            .iter()
+            .flat_map(|pair| pair.clone().into_inner().enumerate())
+            .filter(|(_, item)| {
+                item.clone()
+                    .into_inner()
+                    .any(|pair| pair.as_rule() == Rule::query_encoded)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let query: TemplateMap = query_pair.map(|pair| pair.into()).unwrap_or_default();
 
         let version = pairs
             .next_if(|pair| pair.as_rule() == Rule::version)
-            .map(|pair| http_version_from_str(pair.as_str()))
-            .unwrap_or_default();
-
-        let headers: HashTemplateMap = pairs
-            .next_if(|pair| pair.as_rule() == Rule::headers)
-            .map(|pair| pair.into())
-            .unwrap_or_default();
+            .map(|pair| http_version_from_str(pair.as_str()));
+
+        let headers_pair = pairs.next_if(|pair| pair.as_rule() == Rule::headers);
+        let disabled_headers = headers_pair
+            .iter()
+            .flat_map(|pair| pair.clone().into_inner().enumerate())
+            .filter(|(_, header)| {
+                header
+                    .clone()
+                    .into_inner()
+                    .any(|pair| pair.as_rule() == Rule::header_disabled)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let headers: TemplateMap = headers_pair.map(|pair| pair.into()).unwrap_or_default();
 
         let body = pairs.next().map(Pair::into).unwrap_or_default();
 
         Self {
+            line,
+            name,
+            depends,
+            tags,
+            notes,
+            auth,
+            credentials,
+            oauth2,
             method,
             url,
             query,
+            encoded_query,
+            disabled_query: HashSet::new(),
             version,
             headers,
+            disabled_headers,
             body,
+            captures,
+            protos,
+            ip_version,
+            fill_error: None,
         }
     }
 }
@@ -109,6 +642,26 @@ pub struct HttpFile {
     pub variables: HashMap<String, TemplateString>,
 }
 
+impl HttpFile {
+    /// Returns the names of the variables referenced by the requests that are
+    /// neither defined in the file nor captured by one of its requests.
+    /// Variables coming from elsewhere, i.e. the workspace, are not considered.
+    pub fn undefined_variables(&self, resolver: &dyn Resolver) -> HashSet<String> {
+        let captured: HashSet<&str> = self
+            .requests
+            .iter()
+            .flat_map(|request| &request.captures)
+            .map(|capture| capture.name.as_str())
+            .collect();
+
+        self.requests
+            .iter()
+            .flat_map(|request| request.missing_variables(&self.variables, resolver))
+            .filter(|name| !captured.contains(name.as_str()))
+            .collect()
+    }
+}
+
 impl<'i> From<Pair<'i, Rule>> for HttpFile {
     fn from(pair: Pair<Rule>) -> Self {
         let mut requests = Vec::new();
@@ -132,19 +685,65 @@ impl<'i> From<Pair<'i, Rule>> for HttpFile {
     }
 }
 
+pub type ParseError = Error<Rule>;
+
+#[tracing::instrument(skip_all, fields(len = input.len()))]
 pub fn parse(input: &str) -> Result<HttpFile, Box<Error<Rule>>> {
     let pair = HttpParser::parse(Rule::file, input)?.next().unwrap();
-    Ok(HttpFile::from(pair))
+    let file = HttpFile::from(pair);
+
+    tracing::debug!(
+        requests = file.requests.len(),
+        variables = file.variables.len(),
+        "parsed"
+    );
+
+    Ok(file)
+}
+
+/// Parses a single request, i.e. pasted by the user, without `###`
+/// delimiters or variable definitions. Fill it to get an [`HttpRequest`].
+pub fn parse_request(input: &str) -> Result<TemplateRequest, Box<Error<Rule>>> {
+    // The headers need to be terminated by an empty line, often missing in
+    // snippets without a body
+    let input = input.trim_start_matches(['\r', '\n']);
+    let input = if input.contains("\n\n") || input.contains("\n\r\n") {
+        input.to_owned()
+    } else {
+        format!("{}\n\n", input.trim_end())
+    };
+
+    let pair = HttpParser::parse(Rule::request_snippet, &input)?
+        .next()
+        .unwrap()
+        .into_inner()
+        .next()
+        .unwrap();
+
+    Ok(pair.into())
+}
+
+/// Parses a request body, i.e. edited by the user, which can't contain `###`.
+pub fn parse_body(input: &str) -> Result<TemplateString, Box<Error<Rule>>> {
+    let body = HttpParser::parse(Rule::body_snippet, input)?
+        .next()
+        .unwrap()
+        .into_inner()
+        .find(|pair| pair.as_rule() == Rule::body);
+
+    Ok(body.map(Pair::into).unwrap_or_default())
 }
 
 #[cfg(test)]
 mod tests {
     use core::panic;
 
-    use crate::parser::variables::{Fragment, TemplateString};
+    use crate::parser::variables::{FillError, Fragment, TemplateString};
+
+    use std::collections::{HashMap, HashSet};
 
-    use super::{parse, HttpFile};
-    use reqwest::{Method, Version};
+    use super::{parse, parse_body, parse_request, HttpFile, IpVersion, RequestPart};
+    use http::{Method, Version};
 
     fn assert_parses(input: &str) -> HttpFile {
         let parsed = parse(input);
@@ -170,7 +769,7 @@ GET foo.bar HTTP/1.1
         assert_eq!(file.requests.len(), 1);
         assert_eq!(file.requests[0].method, Method::GET);
         assert_eq!(file.requests[0].url.to_string(), "foo.bar");
-        assert_eq!(file.requests[0].version, Version::HTTP_11);
+        assert_eq!(file.requests[0].version, Some(Version::HTTP_11));
     }
 
     #[test]
@@ -192,7 +791,7 @@ GET foo.bar
 "#;
         let file = assert_parses(input);
         assert_eq!(file.requests.len(), 1);
-        assert_eq!(file.requests[0].version, Version::default());
+        assert_eq!(file.requests[0].version, None);
     }
 
     #[test]
@@ -249,6 +848,115 @@ aabb: {{value}}{{barbar}}
         );
     }
 
+    #[test]
+    fn test_folded_headers() {
+        let input = "
+POST test.dev HTTP/1.0
+X-List: a,
+  b,
+\tc
+Authorization: Bearer abc\\
+    def.{{sig}}
+Accept: */*
+
+body
+";
+        let file = assert_parses(input);
+        let request = &file.requests[0];
+        assert_eq!(request.headers.len(), 3);
+
+        let filled = request
+            .fill(&HashMap::from([("sig".into(), "ghi".parse().unwrap())]))
+            .unwrap();
+        assert_eq!(filled.headers.get("x-list").unwrap(), "a, b, c");
+        assert_eq!(
+            filled.headers.get("authorization").unwrap(),
+            "Bearer abcdef.ghi"
+        );
+        assert_eq!(filled.headers.get("accept").unwrap(), "*/*");
+        assert_eq!(filled.body, "body\n");
+    }
+
+    #[test]
+    fn test_undefined_variables() {
+        let input = r#"
+@host = {{scheme}}://test.dev
+@token = {{$random.uuid}}
+###
+
+# @capture id = $.id
+POST {{host}}/items
+Authorization: Bearer {{token}}
+
+{"owner": "{{owner}}"}
+###
+
+GET {{host}}/items/{{id}}?v={{version}}
+
+"#;
+
+        let file = assert_parses(input);
+        assert_eq!(
+            file.undefined_variables(&()),
+            HashSet::from(["scheme".into(), "owner".into(), "version".into()])
+        );
+        assert_eq!(
+            file.requests[1].missing_variables(&file.variables, &()),
+            HashSet::from(["scheme".into(), "id".into(), "version".into()])
+        );
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let request = parse_request("GET test.dev?a=1\nAccept: */*").unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.query.len(), 1);
+        assert_eq!(request.headers.len(), 1);
+
+        let request = parse_request("\nPOST test.dev\n\n{\"a\": {{b}}}").unwrap();
+        let filled = request
+            .fill(&HashMap::from([("b".into(), "1".parse().unwrap())]))
+            .unwrap();
+        assert_eq!(filled.body, "{\"a\": 1}");
+
+        assert!(parse_request("GET a.dev\n\n###\nGET b.dev").is_err());
+    }
+
+    #[test]
+    fn test_parse_body() {
+        let body = parse_body("{\n  \"id\": {{id}}\n}").unwrap();
+        let filled = body
+            .fill(&HashMap::from([("id".into(), "1".parse().unwrap())]))
+            .unwrap();
+        assert_eq!(filled, "{\n  \"id\": 1\n}");
+
+        assert!(parse_body("").unwrap().is_empty());
+        assert!(parse_body("a\n###\nb").is_err());
+    }
+
+    #[test]
+    fn test_base_url() {
+        let request = parse_request("GET /users/{{id}}").unwrap();
+        let params = HashMap::from([("id".into(), "1".parse().unwrap())]);
+        assert_eq!(
+            request.missing_variables(&params, &()),
+            HashSet::from(["base_url".into()])
+        );
+        assert!(request.fill(&params).is_err());
+
+        let mut params = params;
+        params.insert("base_url".into(), "http://{{host}}/api/".parse().unwrap());
+        params.insert("host".into(), "localhost:8080".parse().unwrap());
+        assert!(request.missing_variables(&params, &()).is_empty());
+        assert_eq!(
+            request.fill(&params).unwrap().url,
+            "http://localhost:8080/api/users/1"
+        );
+
+        let request = parse_request("GET test.dev/users").unwrap();
+        assert_eq!(request.fill(&params).unwrap().url, "https://test.dev/users");
+    }
+
     #[test]
     fn test_body() {
         let input = r#"
@@ -428,4 +1136,361 @@ authorization: token
             ]))
         );
     }
+
+    #[test]
+    fn test_command_file_var() {
+        let input = r#"
+@token = $(pass show api/token)
+@name = foo
+
+###
+
+GET test.dev
+authorization: Bearer {{token}}
+
+"#;
+        let file = assert_parses(input);
+        assert_eq!(file.variables.len(), 2);
+        assert_eq!(
+            file.variables.get("token"),
+            Some(&TemplateString::new(vec![Fragment::command(
+                "pass show api/token"
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_grpc_request() {
+        let input = r#"
+# @proto protos/greeter.proto protos/common.proto
+GRPC http://localhost:50051/test.Greeter/Greet
+
+{"name": "rq"}"#;
+
+        let file = assert_parses(input);
+        let request = &file.requests[0];
+        assert_eq!(request.method.as_str(), "GRPC");
+        assert_eq!(
+            request.protos,
+            vec!["protos/greeter.proto", "protos/common.proto"]
+        );
+    }
+
+    #[test]
+    fn test_tag_directive() {
+        let input = r#"
+# @tag users admin
+# @tag slow
+GET test.dev/users
+
+###
+
+GET test.dev/health
+
+"#;
+        let file = assert_parses(input);
+        assert_eq!(file.requests[0].tags, vec!["users", "admin", "slow"]);
+        assert!(file.requests[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_ip_version_directive() {
+        let input = r#"
+# @ipv6
+GET test.dev/users
+
+###
+
+GET test.dev/health
+
+"#;
+        let file = assert_parses(input);
+        assert_eq!(file.requests[0].ip_version, Some(IpVersion::V6));
+        assert_eq!(file.requests[1].ip_version, None);
+    }
+
+    #[test]
+    fn test_note_directive() {
+        let input = r#"
+# @note requires an admin token
+// @note
+# @note   deletes every session
+DELETE test.dev/sessions
+
+"#;
+        let file = assert_parses(input);
+        assert_eq!(
+            file.requests[0].notes,
+            vec!["requires an admin token", "", "deletes every session"]
+        );
+    }
+
+    #[test]
+    fn test_capture_directive() {
+        let input = r#"
+# @capture token = $.access_token
+// @capture location = header Location
+# @foo bar
+POST test.dev/login HTTP/1.1
+
+"#;
+        let file = assert_parses(input);
+        assert_eq!(file.requests.len(), 1);
+        assert_eq!(file.requests[0].url.to_string(), "test.dev/login");
+        assert_eq!(
+            file.requests[0]
+                .captures
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["token = $.access_token", "location = header Location"]
+        );
+    }
+
+    #[test]
+    fn test_auth_directives() {
+        let input = r#"
+# @auth digest {{user}} secret
+GET test.dev/digest
+
+###
+
+# @auth 30m
+POST test.dev/login
+
+"#;
+        let file = assert_parses(input);
+        let credentials = file.requests[0].credentials.as_ref().unwrap();
+        assert_eq!(
+            credentials.user,
+            TemplateString::new(vec![Fragment::var("user")])
+        );
+        assert_eq!(credentials.password, TemplateString::raw("secret"));
+        assert!(file.requests[0].auth.is_none());
+        assert!(file.requests[1].credentials.is_none());
+        assert!(file.requests[1].auth.is_some());
+    }
+
+    #[test]
+    fn test_extension_methods() {
+        for method in ["PATCH", "PURGE", "PROPFIND", "REPORT", "LIST", "M-SEARCH"] {
+            let file = assert_parses(&format!("{method} test.dev HTTP/1.1\n\n"));
+            assert_eq!(file.requests[0].method.as_str(), method);
+        }
+
+        let file = assert_parses("FOO.BAR HTTP/1.1\n\n");
+        assert_eq!(file.requests[0].method, Method::default());
+        assert_eq!(file.requests[0].url.to_string(), "FOO.BAR");
+    }
+
+    #[test]
+    fn test_duplicate_headers_and_query() {
+        let input = r#"
+GET test.dev?tag=a&tag=b&id=1
+Cookie: a=1
+Accept: text/html
+Cookie: b=2
+
+"#;
+        let file = assert_parses(input);
+        let request = &file.requests[0];
+        assert_eq!(
+            request
+                .query
+                .iter()
+                .map(|(k, _)| k.as_str())
+                .collect::<Vec<_>>(),
+            ["tag", "tag", "id"]
+        );
+        assert_eq!(
+            request
+                .headers
+                .get_all("Cookie")
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["a=1", "b=2"]
+        );
+
+        let filled = request.fill(&HashMap::new()).unwrap();
+        assert_eq!(
+            filled.query,
+            [
+                ("tag".into(), "a".into()),
+                ("tag".into(), "b".into()),
+                ("id".into(), "1".into())
+            ]
+        );
+        assert_eq!(
+            filled.headers.get_all("cookie").iter().collect::<Vec<_>>(),
+            ["a=1", "b=2"]
+        );
+    }
+
+    #[test]
+    fn test_disabled_headers() {
+        let input = r#"
+GET test.dev
+Accept: */*
+# Cache-Control: no-cache
+#X-Debug: {{debug}}
+
+"#;
+        let mut file = assert_parses(input);
+        let request = &mut file.requests[0];
+        assert_eq!(request.line, 2);
+        assert_eq!(request.headers.len(), 3);
+        assert_eq!(request.headers[1].0, "Cache-Control");
+        assert_eq!(request.disabled_headers, HashSet::from([1, 2]));
+        assert!(request.missing_variables(&HashMap::new(), &()).is_empty());
+
+        let filled = request.fill(&HashMap::new()).unwrap();
+        assert_eq!(filled.headers.len(), 1);
+
+        request.toggle_header(1);
+        let filled = request.fill(&HashMap::new()).unwrap();
+        assert_eq!(filled.headers["cache-control"], "no-cache");
+    }
+
+    #[test]
+    fn test_to_http1() {
+        let input = r#"
+# @auth basic user pass
+POST http://test.dev:8080/items?page=2
+Content-Type: application/json
+
+{"a": 1}"#;
+        let file = assert_parses(input);
+        let request = file.requests[0].fill(&HashMap::new()).unwrap();
+
+        assert_eq!(
+            request.to_http1(),
+            "POST /items?page=2 HTTP/1.1
+host: test.dev:8080
+content-type: application/json
+authorization: Basic dXNlcjpwYXNz
+content-length: 8
+
+{\"a\": 1}"
+        );
+    }
+
+    #[test]
+    fn test_query_encoding() {
+        let input = r#"
+GET test.dev?q="a&b=c d"&raw==a%20b&plus=+&filter=={{filter}}
+
+"#;
+        let file = assert_parses(input);
+        let request = &file.requests[0];
+        assert_eq!(request.query.len(), 4);
+        assert_eq!(request.encoded_query, HashSet::from([1, 3]));
+
+        let params = HashMap::from([("filter".into(), TemplateString::raw("x%3Dy"))]);
+        assert_eq!(
+            request.fill(&params).unwrap().query,
+            [
+                ("q".into(), "a%26b%3Dc%20d".into()),
+                ("raw".into(), "a%20b".into()),
+                ("plus".into(), "%2B".into()),
+                ("filter".into(), "x%3Dy".into())
+            ]
+        );
+
+        let decoded = request
+            .decoded_query(&HashMap::new())
+            .into_iter()
+            .map(|(name, value)| (name, value.ok()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            [
+                ("q".into(), Some("a&b=c d".into())),
+                ("raw".into(), Some("a b".into())),
+                ("plus".into(), Some("+".into())),
+                ("filter".into(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toggle_query() {
+        let input = r#"
+GET test.dev?page=1&debug={{debug}}
+
+"#;
+        let mut file = assert_parses(input);
+        let request = &mut file.requests[0];
+        assert_eq!(request.missing_variables(&HashMap::new(), &()).len(), 1);
+
+        request.toggle_query(1);
+        request.set_query_value(0, TemplateString::raw("2"));
+        assert!(request.missing_variables(&HashMap::new(), &()).is_empty());
+        assert_eq!(
+            request.fill(&HashMap::new()).unwrap().query,
+            [("page".into(), "2".into())]
+        );
+
+        request.toggle_query(1);
+        let params = HashMap::from([("debug".into(), TemplateString::raw("on"))]);
+        assert_eq!(request.fill(&params).unwrap().query.len(), 2);
+    }
+
+    #[test]
+    fn test_fill_error() {
+        let input = r#"
+# @name profile
+GET test.dev/users?id={{id}}
+Authorization: Bearer {{token}}
+
+"#;
+        let file = assert_parses(input);
+        let params = HashMap::from([("id".into(), TemplateString::raw("1"))]);
+
+        let error = file.requests[0].fill(&params).unwrap_err();
+        assert_eq!(error.part, RequestPart::Header("Authorization".into()));
+        assert_eq!(error.line, 3);
+        assert_eq!(error.span, Some(7..16));
+        assert_eq!(
+            error.to_string(),
+            "'profile' (line 3), header 'Authorization': missing field 'token'"
+        );
+
+        let error = file.requests[0].fill(&HashMap::new()).unwrap_err();
+        assert_eq!(error.part, RequestPart::Query("id".into()));
+        assert_eq!(error.span, Some(0..6));
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let input = r#"
+GET test.dev
+Authorization: Bearer {{token}}
+X-Émoji: yes
+
+"#;
+        let mut file = assert_parses(input);
+        let mut request = file.requests.remove(0);
+        let params = HashMap::from([("token".into(), TemplateString::raw("abc\n"))]);
+
+        let error = request.fill(&params).unwrap_err();
+        assert_eq!(error.part, RequestPart::Header("Authorization".into()));
+        assert_eq!(error.span, Some(7..16));
+        assert_eq!(
+            error.source,
+            FillError::InvalidHeader {
+                reason: "the value contains a line break".into()
+            }
+        );
+
+        let params = HashMap::from([("token".into(), TemplateString::raw("abc"))]);
+        let error = request.fill(&params).unwrap_err();
+        assert_eq!(error.part, RequestPart::Header("X-Émoji".into()));
+        assert_eq!(error.span, None);
+        assert!(error.to_string().ends_with(
+            "header 'X-Émoji': invalid header, the name contains the non-ASCII character 'É'"
+        ));
+
+        request.toggle_header(1);
+        assert!(request.fill(&params).is_ok());
+    }
 }
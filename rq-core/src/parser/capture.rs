@@ -0,0 +1,253 @@
+//! Response captures, i.e. `# @capture token = $.access_token`, storing a
+//! value extracted from the response into a variable.
+//!
+//! Supported sources:
+//! - `$.path.to[0].field`: JSONPath (subset) into the response body
+//! - `header <name>`: value of a response header
+//! - `regex <pattern>`: first match (or first capture group) in the response body
+
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::request::Response;
+
+use super::values;
+
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub name: String,
+    pub source: CaptureSource,
+}
+
+#[derive(Debug, Clone)]
+pub enum CaptureSource {
+    JsonPath(Vec<PathSegment>),
+    Header(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CaptureError {
+    #[error("invalid capture '{0}'")]
+    Invalid(String),
+    #[error("capture '{0}': no match")]
+    NotFound(String),
+}
+
+impl Capture {
+    pub fn extract(&self, response: &Response) -> Result<String, CaptureError> {
+        self.source
+            .extract(response)
+            .ok_or_else(|| CaptureError::NotFound(self.name.clone()))
+    }
+}
+
+impl CaptureSource {
+    pub fn extract(&self, response: &Response) -> Option<String> {
+        match self {
+            CaptureSource::Header(name) => response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned),
+            CaptureSource::Regex(regex) => {
+                let body = response.payload.as_text_lossy();
+                let captures = regex.captures(&body)?;
+
+                Some(
+                    captures
+                        .get(1)
+                        .or_else(|| captures.get(0))
+                        .unwrap()
+                        .as_str()
+                        .to_owned(),
+                )
+            }
+            CaptureSource::JsonPath(path) => {
+                let json = response.payload.json().ok()?;
+
+                let value = path
+                    .iter()
+                    .try_fold(&json, |value, segment| match segment {
+                        PathSegment::Key(key) => value.get(key),
+                        PathSegment::Index(idx) => value.get(idx),
+                    })?;
+
+                Some(match value {
+                    Value::String(s) => s.to_owned(),
+                    value => value.to_string(),
+                })
+            }
+        }
+    }
+}
+
+impl FromStr for Capture {
+    type Err = CaptureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CaptureError::Invalid(s.to_owned());
+
+        let (name, source) = s.split_once('=').ok_or_else(invalid)?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            source: source.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl FromStr for CaptureSource {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        let source = if let Some(path) = s.strip_prefix('$') {
+            CaptureSource::JsonPath(parse_json_path(path).ok_or(())?)
+        } else if let Some(header) = s.strip_prefix("header ") {
+            CaptureSource::Header(header.trim().to_owned())
+        } else if let Some(pattern) = s.strip_prefix("regex ") {
+            let pattern = values::unquote(pattern.trim());
+            CaptureSource::Regex(Regex::new(pattern).map_err(|_| ())?)
+        } else {
+            return Err(());
+        };
+
+        Ok(source)
+    }
+}
+
+impl Display for Capture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = ", self.name)?;
+
+        match &self.source {
+            CaptureSource::Header(name) => write!(f, "header {name}"),
+            CaptureSource::Regex(regex) => write!(f, "regex \"{regex}\""),
+            CaptureSource::JsonPath(path) => {
+                write!(f, "$")?;
+                for segment in path {
+                    match segment {
+                        PathSegment::Key(key) => write!(f, ".{key}")?,
+                        PathSegment::Index(idx) => write!(f, "[{idx}]")?,
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Parses the subset `.key`, `['key']` and `[0]` of JSONPath, after the leading `$`
+fn parse_json_path(mut path: &str) -> Option<Vec<PathSegment>> {
+    let mut segments = Vec::new();
+
+    while !path.is_empty() {
+        if let Some(rest) = path.strip_prefix('.') {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            segments.push(PathSegment::Key(rest[..end].to_owned()));
+            path = &rest[end..];
+        } else if let Some(rest) = path.strip_prefix('[') {
+            let end = rest.find(']')?;
+            let inner = &rest[..end];
+            let unquoted = values::unquote(inner);
+
+            segments.push(if unquoted != inner {
+                PathSegment::Key(unquoted.to_owned())
+            } else {
+                PathSegment::Index(inner.parse().ok()?)
+            });
+            path = &rest[end + 1..];
+        } else {
+            return None;
+        }
+    }
+
+    Some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{header::HeaderMap, StatusCode};
+
+    use crate::request::{
+        mime::{Payload, TextPayload},
+        Response,
+    };
+
+    use super::{parse_json_path, Capture, CaptureError, PathSegment};
+
+    fn response(body: &str) -> Response {
+        let mut headers = HeaderMap::new();
+        headers.insert("location", "/users/42".parse().unwrap());
+
+        Response {
+            status: StatusCode::OK,
+            version: "HTTP/1.1".into(),
+            headers,
+            payload: Payload::Text(TextPayload {
+                extension: None,
+                charset: "utf-8".into(),
+                text: body.into(),
+                warning: None,
+                bytes: body.to_owned().into(),
+            }),
+            url: "https://test.dev/users".into(),
+            remote_addr: None,
+            protocol: None,
+            redirects: Vec::new(),
+            timings: Default::default(),
+            spilled: None,
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(
+            parse_json_path(".data[0]['access token'].value"),
+            Some(vec![
+                PathSegment::Key("data".into()),
+                PathSegment::Index(0),
+                PathSegment::Key("access token".into()),
+                PathSegment::Key("value".into()),
+            ])
+        );
+        assert_eq!(parse_json_path(""), Some(vec![]));
+        assert_eq!(parse_json_path("..foo"), None);
+        assert_eq!(parse_json_path("[foo]"), None);
+    }
+
+    #[test]
+    fn test_extract() {
+        let response = response(r#"{ "data": [{ "token": "abc", "id": 7 }] }"#);
+        let extract = |s: &str| s.parse::<Capture>().unwrap().extract(&response);
+
+        assert_eq!(extract("t = $.data[0].token"), Ok("abc".into()));
+        assert_eq!(extract("t = $.data[0].id"), Ok("7".into()));
+        assert_eq!(extract("l = header Location"), Ok("/users/42".into()));
+        assert_eq!(extract(r#"i = regex "\"id\": (\d+)""#), Ok("7".into()));
+        assert_eq!(
+            extract("t = $.data[1]"),
+            Err(CaptureError::NotFound("t".into()))
+        );
+        assert!("t = foo".parse::<Capture>().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Request dependencies, i.e. `# @depends login`, referencing requests by
+//! their `# @name`.
+
+use thiserror::Error;
+
+use super::TemplateRequest;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ScenarioError {
+    #[error("unknown dependency '{0}'")]
+    UnknownDependency(String),
+    #[error("dependency cycle involving '{0}'")]
+    Cycle(String),
+}
+
+/// Returns the indexes of the requests to execute, dependencies first, in
+/// order to run `requests[target]`.
+pub fn execution_order(
+    requests: &[TemplateRequest],
+    target: usize,
+) -> Result<Vec<usize>, ScenarioError> {
+    let mut order = Vec::new();
+    let mut visiting = Vec::new();
+
+    visit(requests, target, &mut visiting, &mut order)?;
+
+    Ok(order)
+}
+
+fn visit(
+    requests: &[TemplateRequest],
+    idx: usize,
+    visiting: &mut Vec<usize>,
+    order: &mut Vec<usize>,
+) -> Result<(), ScenarioError> {
+    if order.contains(&idx) {
+        return Ok(());
+    }
+
+    let request = &requests[idx];
+    if visiting.contains(&idx) {
+        let name = request
+            .name
+            .clone()
+            .unwrap_or_else(|| request.url.to_string());
+        return Err(ScenarioError::Cycle(name));
+    }
+
+    visiting.push(idx);
+    for dependency in &request.depends {
+        let dependency_idx = requests
+            .iter()
+            .position(|r| r.name.as_ref() == Some(dependency))
+            .ok_or_else(|| ScenarioError::UnknownDependency(dependency.clone()))?;
+
+        visit(requests, dependency_idx, visiting, order)?;
+    }
+    visiting.pop();
+
+    order.push(idx);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+
+    use super::{execution_order, ScenarioError};
+
+    #[test]
+    fn test_execution_order() {
+        let input = r#"
+# @name login
+POST test.dev/login
+
+###
+
+# @name user
+# @depends login
+GET test.dev/user
+
+###
+
+# @depends user login
+GET test.dev/user/posts
+
+"#;
+        let file = parse(input).unwrap();
+
+        assert_eq!(execution_order(&file.requests, 0), Ok(vec![0]));
+        assert_eq!(execution_order(&file.requests, 1), Ok(vec![0, 1]));
+        assert_eq!(execution_order(&file.requests, 2), Ok(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn test_invalid_dependencies() {
+        let input = r#"
+# @name a
+# @depends b
+GET test.dev/a
+
+###
+
+# @name b
+# @depends a
+GET test.dev/b
+
+###
+
+# @depends missing
+GET test.dev/c
+
+"#;
+        let file = parse(input).unwrap();
+
+        assert_eq!(
+            execution_order(&file.requests, 0),
+            Err(ScenarioError::Cycle("a".into()))
+        );
+        assert_eq!(
+            execution_order(&file.requests, 2),
+            Err(ScenarioError::UnknownDependency("missing".into()))
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Session auth request, marked with `# @auth [expiry]`.
+//!
+//! Its captures hold the session token; it's sent again whenever the token is
+//! expired or another request is rejected with `401 Unauthorized`. `expiry` is
+//! either a fixed duration (i.e. `30m`) or a capture source holding the number
+//! of seconds the token is valid for (i.e. `$.expires_in`).
+
+use std::{str::FromStr, time::Duration};
+
+use crate::request::Response;
+
+use super::capture::CaptureSource;
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionAuth {
+    pub expiry: Option<Expiry>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expiry {
+    After(Duration),
+    Field(CaptureSource),
+}
+
+impl SessionAuth {
+    /// How long the session obtained with `response` is valid for, `None` if
+    /// it doesn't expire.
+    pub fn expires_in(&self, response: &Response) -> Option<Duration> {
+        match self.expiry.as_ref()? {
+            Expiry::After(duration) => Some(*duration),
+            Expiry::Field(source) => source
+                .extract(response)
+                .and_then(|seconds| seconds.parse().ok())
+                .map(Duration::from_secs),
+        }
+    }
+}
+
+impl FromStr for SessionAuth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let expiry = match parse_duration(s) {
+            Some(duration) => Expiry::After(duration),
+            None => Expiry::Field(s.parse().map_err(|_| format!("invalid expiry '{s}'"))?),
+        };
+
+        Ok(Self {
+            expiry: Some(expiry),
+        })
+    }
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let unit_idx = s.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = s.split_at(unit_idx);
+    let amount: u64 = amount.parse().ok()?;
+
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        _ => return None,
+    };
+
+    // Out of range amounts are rejected along with the invalid ones
+    amount.checked_mul(unit).map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{parse_duration, Expiry, SessionAuth};
+
+    #[test]
+    fn test_parse() {
+        assert!("".parse::<SessionAuth>().unwrap().expiry.is_none());
+        assert!(matches!(
+            "30m".parse::<SessionAuth>().unwrap().expiry,
+            Some(Expiry::After(d)) if d == Duration::from_secs(30 * 60)
+        ));
+        assert!(matches!(
+            "$.expires_in".parse::<SessionAuth>().unwrap().expiry,
+            Some(Expiry::Field(_))
+        ));
+        assert!("foo".parse::<SessionAuth>().is_err());
+        assert_eq!(parse_duration("1x"), None);
+        assert_eq!(parse_duration("999999999999999999d"), None);
+        assert!("999999999999999999d".parse::<SessionAuth>().is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Request target validation and normalization.
+
+use std::sync::RwLock;
+
+use url::Url;
+
+use crate::glob::glob;
+
+use super::variables::FillError;
+
+static DEFAULT_SCHEME: RwLock<String> = RwLock::new(String::new());
+
+/// Sets the scheme of urls without one, `https` if unset.
+pub fn set_default_scheme(scheme: &str) {
+    *DEFAULT_SCHEME.write().unwrap() = scheme.trim_end_matches("://").to_owned();
+}
+
+fn default_scheme() -> String {
+    let scheme = DEFAULT_SCHEME.read().unwrap();
+
+    if scheme.is_empty() {
+        "https".into()
+    } else {
+        scheme.clone()
+    }
+}
+
+/// Validates `url`, adding the default scheme if missing and converting
+/// international domain names to punycode.
+pub fn normalize(url: &str) -> Result<String, FillError> {
+    let url = url.trim();
+
+    let with_scheme = if url.contains("://") {
+        url.to_owned()
+    } else {
+        format!("{}://{url}", default_scheme())
+    };
+
+    let parsed = Url::parse(&with_scheme).map_err(|e| FillError::InvalidUrl {
+        url: url.to_owned(),
+        reason: e.to_string(),
+    })?;
+
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(FillError::InvalidUrl {
+            url: url.to_owned(),
+            reason: format!("unsupported scheme '{}'", parsed.scheme()),
+        });
+    }
+
+    Ok(parsed.into())
+}
+
+/// Host of the normalized `url`, lowercase.
+pub fn host(url: &str) -> Option<String> {
+    Url::parse(url).ok()?.host_str().map(str::to_owned)
+}
+
+/// Whether the host of `url` matches `pattern`, `*` matching any characters,
+/// i.e. `*.internal.corp`.
+pub fn host_matches(pattern: &str, url: &str) -> bool {
+    host(url).is_some_and(|host| glob(&pattern.to_ascii_lowercase(), &host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("foo.bar").unwrap(), "https://foo.bar/");
+        assert_eq!(
+            normalize("localhost:8080/path?q=1").unwrap(),
+            "https://localhost:8080/path?q=1"
+        );
+        assert_eq!(normalize("http://foo.bar/a").unwrap(), "http://foo.bar/a");
+        assert_eq!(
+            normalize("https://bücher.example/").unwrap(),
+            "https://xn--bcher-kva.example/"
+        );
+        assert!(normalize("ftp://foo.bar").is_err());
+        assert!(normalize("http://foo bar").is_err());
+    }
+}
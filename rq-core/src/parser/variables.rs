@@ -1,27 +1,68 @@
-use std::{collections::HashMap, fmt::Display, hash::Hash, ops::Deref, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+    ops::{Deref, Range},
+    str::FromStr,
+};
 
 use pest::{iterators::Pair, Parser};
 use thiserror::Error;
 
 use super::{values, HttpParser, Rule};
 
+use self::resolver::Resolver;
+
+pub mod command;
+pub mod dynamic;
+pub mod filters;
+pub mod resolver;
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Variable {
     name: String,
+    args: Vec<String>,
+    filters: Vec<String>,
 }
 
 impl Variable {
     pub fn new(name: &str) -> Self {
         Variable {
             name: name.to_owned(),
+            args: Vec::new(),
+            filters: Vec::new(),
         }
     }
+
+    pub fn with_args(self, args: Vec<String>) -> Self {
+        Self { args, ..self }
+    }
+
+    pub fn with_filters(self, filters: Vec<String>) -> Self {
+        Self { filters, ..self }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 impl Display for Variable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // i.e. if self.name = 'foo', this outputs '{{foo}}'
-        write!(f, "{{{{{}}}}}", self.name)
+        // i.e. if self.name = 'foo', self.args = ['bar'] and self.filters = ['upper'],
+        // this outputs '{{foo bar | upper}}'
+        write!(f, "{{{{{}", self.name)?;
+        for arg in &self.args {
+            if arg.contains(' ') {
+                write!(f, " \"{arg}\"")?;
+            } else {
+                write!(f, " {arg}")?;
+            }
+        }
+        for filter in &self.filters {
+            write!(f, " | {filter}")?;
+        }
+        write!(f, "}}}}")
     }
 }
 
@@ -29,6 +70,8 @@ impl Display for Variable {
 pub enum Fragment {
     Var(Variable),
     RawText(String),
+    // Shell command, i.e. `$(pass show token)`
+    Command(String),
 }
 
 impl Fragment {
@@ -39,6 +82,10 @@ impl Fragment {
     pub fn var(name: &str) -> Self {
         Fragment::Var(Variable::new(name))
     }
+
+    pub fn command(command: &str) -> Self {
+        Fragment::Command(command.into())
+    }
 }
 
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
@@ -58,15 +105,46 @@ impl TemplateString {
     }
 
     pub fn fill(&self, parameters: &HashMap<String, TemplateString>) -> Result<String, FillError> {
+        self.fill_with(parameters, &())
+    }
+
+    /// Like `fill`, looking up functions and filters in `resolver` before
+    /// the built-in ones.
+    pub fn fill_with(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<String, FillError> {
+        Ok(self.fill_fragments_with(parameters, resolver)?.concat())
+    }
+
+    /// Like `fill_with`, keeping the value of each fragment apart, i.e. to
+    /// tell which one a character of the result comes from.
+    pub fn fill_fragments_with(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<Vec<String>, FillError> {
         self.fragments
             .iter()
             .map(|fragment| {
                 let s = match fragment {
-                    Fragment::Var(v) => parameters
-                        .get(&v.name)
-                        .ok_or(FillError::from(v.clone()))
-                        .and_then(|s| s.fill(parameters))?,
+                    Fragment::Var(v) => {
+                        let value = match parameters.get(&v.name) {
+                            Some(s) => s.fill_with(parameters, resolver)?,
+                            None => match resolver.call(&v.name, &v.args) {
+                                Some(value) => value.map_err(|reason| FillError::Unresolved {
+                                    variable: v.clone(),
+                                    reason,
+                                })?,
+                                None => dynamic::resolve(v)?,
+                            },
+                        };
+
+                        filters::apply(&v.filters, value, resolver)?
+                    }
                     Fragment::RawText(s) => s.to_owned(),
+                    Fragment::Command(c) => command::run(c)?,
                 };
 
                 Ok(s)
@@ -74,10 +152,157 @@ impl TemplateString {
             .collect()
     }
 
+    /// Like `fill_with`, but variables that can't be resolved are left as
+    /// written, i.e. `{{name}}`, instead of failing. Returns the filled string
+    /// along with the names of the variables left over, in order of appearance.
+    /// Commands aren't run, even when enabled, and are left as written too.
+    pub fn fill_partial(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> (String, Vec<String>) {
+        let mut leftovers = Vec::new();
+        let filled = self.fill_partial_into(parameters, resolver, &mut leftovers);
+
+        let mut seen = HashSet::new();
+        leftovers.retain(|name| seen.insert(name.clone()));
+
+        (filled, leftovers)
+    }
+
+    fn fill_partial_into(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+        leftovers: &mut Vec<String>,
+    ) -> String {
+        self.fragments
+            .iter()
+            .map(|fragment| match fragment {
+                Fragment::Var(v) => {
+                    let value = match parameters.get(&v.name) {
+                        Some(s) => {
+                            let before = leftovers.len();
+                            let value = s.fill_partial_into(parameters, resolver, leftovers);
+                            if leftovers.len() > before {
+                                // Filtering a partial value would mangle its placeholders
+                                return if v.filters.is_empty() {
+                                    value
+                                } else {
+                                    v.to_string()
+                                };
+                            }
+
+                            value
+                        }
+                        None => match resolver
+                            .call(&v.name, &v.args)
+                            .unwrap_or_else(|| dynamic::resolve(v).map_err(|e| e.to_string()))
+                        {
+                            Ok(value) => value,
+                            Err(_) => {
+                                leftovers.push(v.name.clone());
+                                return v.to_string();
+                            }
+                        },
+                    };
+
+                    filters::apply(&v.filters, value, resolver).unwrap_or_else(|_| {
+                        leftovers.push(v.name.clone());
+                        v.to_string()
+                    })
+                }
+                Fragment::RawText(s) => s.to_owned(),
+                // A preview has no business running them
+                Fragment::Command(c) => format!("$({c})"),
+            })
+            .collect()
+    }
+
+    /// Byte range, in the template as displayed, of the variable or command
+    /// that caused `error`. `None` when it comes from elsewhere, i.e. from the
+    /// value of a variable.
+    pub fn span_of(&self, error: &FillError) -> Option<Range<usize>> {
+        let caused = |fragment: &Fragment| match (fragment, error) {
+            (
+                Fragment::Var(v),
+                FillError::MissingVariable(variable) | FillError::Unresolved { variable, .. },
+            ) => v == variable,
+            (Fragment::Var(v), FillError::Filter { filter, .. }) => v.filters.contains(filter),
+            (Fragment::Command(c), FillError::Command { command, .. }) => c == command,
+            _ => false,
+        };
+
+        self.fragments
+            .iter()
+            .position(caused)
+            .and_then(|idx| self.fragment_span(idx))
+    }
+
+    /// Byte range, in the template as displayed, of the fragment at `idx`.
+    pub fn fragment_span(&self, idx: usize) -> Option<Range<usize>> {
+        let fragment = self.fragments.get(idx)?;
+
+        // Past the opening quote of values with outer spaces, see `Display`
+        let text = self.fragments.iter().map(fragment_text).collect::<String>();
+        let quote = if text.starts_with(' ') || text.ends_with(' ') {
+            1
+        } else {
+            0
+        };
+        let start = quote
+            + self.fragments[..idx]
+                .iter()
+                .map(|fragment| fragment_text(fragment).len())
+                .sum::<usize>();
+
+        Some(start..start + fragment_text(fragment).len())
+    }
+
+    /// Returns the names of the variables that `fill_with` could not resolve
+    /// with `parameters` and `resolver`, following the variables referenced by
+    /// their values.
+    pub fn missing_variables(
+        &self,
+        parameters: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> HashSet<String> {
+        let mut missing = HashSet::new();
+        self.collect_missing(parameters, resolver, &mut HashSet::new(), &mut missing);
+
+        missing
+    }
+
+    fn collect_missing<'a>(
+        &'a self,
+        parameters: &'a HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+        visited: &mut HashSet<&'a str>,
+        missing: &mut HashSet<String>,
+    ) {
+        for fragment in &self.fragments {
+            let Fragment::Var(v) = fragment else {
+                continue;
+            };
+
+            match parameters.get(&v.name) {
+                Some(value) => {
+                    if visited.insert(&v.name) {
+                        value.collect_missing(parameters, resolver, visited, missing);
+                    }
+                }
+                None if resolver.defines(&v.name) || dynamic::defines(&v.name) => (),
+                None => {
+                    missing.insert(v.name.clone());
+                }
+            }
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.fragments.is_empty()
             || self.fragments.iter().all(|fragment| match fragment {
-                Fragment::Var(_) => false,
+                Fragment::Var(_) | Fragment::Command(_) => false,
                 Fragment::RawText(s) => s.is_empty(),
             })
     }
@@ -90,9 +315,35 @@ impl From<Pair<'_, Rule>> for TemplateString {
         let fragments = inner
             .map(|pair| match pair.as_rule() {
                 Rule::var => {
-                    let var_name = pair.into_inner().next().unwrap().as_str();
-                    Fragment::var(var_name)
+                    let mut inner = pair.into_inner().peekable();
+                    let mut var_name = inner.next().unwrap();
+
+                    let mut args = Vec::new();
+                    // Function call syntax, i.e. `{{name(arg, arg)}}`
+                    if var_name.as_rule() == Rule::var_call {
+                        let mut call = var_name.into_inner();
+                        var_name = call.next().unwrap();
+                        args.extend(call.map(|arg| values::unquote(arg.as_str()).to_owned()));
+                    }
+                    let var_name = var_name.as_str();
+
+                    while let Some(arg) = inner.next_if(|pair| pair.as_rule() == Rule::var_arg) {
+                        args.push(values::unquote(arg.as_str()).to_owned());
+                    }
+                    let filters = inner.map(|filter| filter.as_str().to_owned()).collect();
+
+                    Fragment::Var(
+                        Variable::new(var_name)
+                            .with_args(args)
+                            .with_filters(filters),
+                    )
+                }
+                Rule::var_def_command => {
+                    let command = pair.into_inner().next().unwrap().as_str();
+                    Fragment::command(command)
                 }
+                Rule::header_fold if pair.as_str().starts_with('\\') => Fragment::raw(""),
+                Rule::header_fold => Fragment::raw(" "),
                 _ => Fragment::raw(values::unquote(pair.as_str())),
             })
             .collect::<Vec<_>>();
@@ -113,30 +364,32 @@ impl FromStr for TemplateString {
     }
 }
 
-#[derive(Debug, Error, PartialEq)]
-#[error("missing field '{}'", .missing_variable.name)]
-pub struct FillError {
-    missing_variable: Variable,
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum FillError {
+    #[error("missing field '{}'", .0.name)]
+    MissingVariable(Variable),
+    #[error("cannot resolve '{}': {reason}", .variable.name)]
+    Unresolved { variable: Variable, reason: String },
+    #[error("command '{command}': {reason}")]
+    Command { command: String, reason: String },
+    #[error("filter '{filter}': {reason}")]
+    Filter { filter: String, reason: String },
+    #[error("invalid url '{url}': {reason}")]
+    InvalidUrl { url: String, reason: String },
+    // Of a filled header, which would be refused when sending it
+    #[error("invalid header, {reason}")]
+    InvalidHeader { reason: String },
 }
 
 impl From<Variable> for FillError {
     fn from(value: Variable) -> Self {
-        FillError {
-            missing_variable: value,
-        }
+        FillError::MissingVariable(value)
     }
 }
 
 impl Display for TemplateString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = self
-            .fragments
-            .iter()
-            .map(|fragment| match fragment {
-                Fragment::Var(v) => v.to_string(),
-                Fragment::RawText(s) => s.to_owned(),
-            })
-            .collect::<String>();
+        let s = self.fragments.iter().map(fragment_text).collect::<String>();
 
         if s.starts_with(' ') | s.ends_with(' ') {
             return write!(f, "\"{s}\"");
@@ -146,6 +399,14 @@ impl Display for TemplateString {
     }
 }
 
+fn fragment_text(fragment: &Fragment) -> String {
+    match fragment {
+        Fragment::Var(v) => v.to_string(),
+        Fragment::RawText(s) => s.to_owned(),
+        Fragment::Command(c) => format!("$({c})"),
+    }
+}
+
 pub fn parse_def_block(var_def_block: Pair<Rule>) -> HashMap<String, TemplateString> {
     var_def_block
         .into_inner()
@@ -160,50 +421,87 @@ pub fn parse_def_block(var_def_block: Pair<Rule>) -> HashMap<String, TemplateStr
         .collect()
 }
 
+/// Ordered multimap of templates, keeping repeated keys (i.e. multiple
+/// `Cookie` headers) in their original order.
 #[derive(Debug, Clone, Default)]
-pub struct HashTemplateMap(HashMap<String, TemplateString>);
+pub struct TemplateMap(Vec<(String, TemplateString)>);
 
-impl HashTemplateMap {
+impl TemplateMap {
     pub fn fill(
         &self,
         params: &HashMap<String, TemplateString>,
-    ) -> Result<HashMap<String, String>, FillError> {
-        let filled = self
-            .0
+    ) -> Result<Vec<(String, String)>, FillError> {
+        self.fill_with(params, &())
+    }
+
+    pub fn fill_with(
+        &self,
+        params: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> Result<Vec<(String, String)>, FillError> {
+        self.0
             .iter()
             .map(|(k, v)| {
-                let v = v.fill(params)?;
+                let v = v.fill_with(params, resolver)?;
 
                 Ok((k.to_owned(), v))
             })
-            .collect::<Result<HashMap<_, _>, FillError>>()?;
+            .collect()
+    }
+
+    pub fn missing_variables(
+        &self,
+        params: &HashMap<String, TemplateString>,
+        resolver: &dyn Resolver,
+    ) -> HashSet<String> {
+        self.0
+            .iter()
+            .flat_map(|(_, v)| v.missing_variables(params, resolver))
+            .collect()
+    }
 
-        Ok(filled)
+    /// Returns the first value for `key`.
+    pub fn get(&self, key: &str) -> Option<&TemplateString> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a TemplateString> {
+        self.0.iter().filter(move |(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Replaces the value of the entry at `idx`, keeping its key.
+    pub fn set_value(&mut self, idx: usize, value: TemplateString) {
+        if let Some((_, v)) = self.0.get_mut(idx) {
+            *v = value;
+        }
     }
 }
 
-impl Deref for HashTemplateMap {
-    type Target = HashMap<String, TemplateString>;
+impl Deref for TemplateMap {
+    type Target = [(String, TemplateString)];
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl From<Pair<'_, Rule>> for HashTemplateMap {
+impl From<Pair<'_, Rule>> for TemplateMap {
     fn from(value: Pair<'_, Rule>) -> Self {
-        let headers = value
+        let entries = value
             .into_inner()
             .map(|pair| {
-                let mut kv = pair.into_inner();
+                let mut kv = pair
+                    .into_inner()
+                    .filter(|pair| pair.as_rule() != Rule::header_disabled);
                 let key = kv.next().unwrap().as_str().to_string();
-                let value = kv.next().unwrap().into();
+                // Skip markers between key and value, i.e. `query_encoded`
+                let value = kv.last().unwrap().into();
 
                 (key, value)
             })
             .collect();
 
-        Self(headers)
+        Self(entries)
     }
 }
 
@@ -212,9 +510,32 @@ mod tests {
     mod variable {}
 
     mod template_string {
-        use std::collections::HashMap;
+        use std::collections::{HashMap, HashSet};
 
-        use crate::parser::variables::{FillError, Fragment, TemplateString, Variable};
+        use crate::parser::variables::{
+            resolver::Functions, FillError, Fragment, TemplateString, Variable,
+        };
+
+        #[test]
+        fn test_span_of() {
+            let ts = TemplateString::new(vec![
+                Fragment::raw("Bearer "),
+                Fragment::var("token"),
+                Fragment::raw(" "),
+                Fragment::command("pass show key"),
+            ]);
+            assert_eq!(ts.to_string(), "Bearer {{token}} $(pass show key)");
+
+            let missing = FillError::MissingVariable(Variable::new("token"));
+            assert_eq!(ts.span_of(&missing), Some(7..16));
+            let command = FillError::Command {
+                command: "pass show key".into(),
+                reason: "not allowed".into(),
+            };
+            assert_eq!(ts.span_of(&command), Some(17..33));
+            let other = FillError::MissingVariable(Variable::new("user"));
+            assert_eq!(ts.span_of(&other), None);
+        }
 
         #[test]
         fn test_display() {
@@ -259,6 +580,170 @@ mod tests {
             )
         }
 
+        #[test]
+        fn test_parse_command() {
+            let s = "$(pass show api/token)";
+
+            assert_eq!(
+                s.parse::<TemplateString>().unwrap(),
+                TemplateString::new(vec![Fragment::command("pass show api/token")])
+            );
+            assert_eq!(s.parse::<TemplateString>().unwrap().to_string(), s);
+        }
+
+        #[test]
+        fn test_fill_command_disabled() {
+            let ts = TemplateString::new(vec![Fragment::command("echo foo")]);
+
+            assert!(matches!(
+                ts.fill(&HashMap::new()),
+                Err(FillError::Command { .. })
+            ));
+        }
+
+        #[test]
+        fn test_parse_call() {
+            let s = "{{ $random.int(1, 10) | upper }}{{join('a b',c)}}";
+            let expected = TemplateString::new(vec![
+                Fragment::Var(
+                    Variable::new("$random.int")
+                        .with_args(vec!["1".into(), "10".into()])
+                        .with_filters(vec!["upper".into()]),
+                ),
+                Fragment::Var(Variable::new("join").with_args(vec!["a b".into(), "c".into()])),
+            ]);
+
+            assert_eq!(s.parse::<TemplateString>().unwrap(), expected);
+        }
+
+        #[test]
+        fn test_fill_with_resolver() {
+            let functions = Functions::new()
+                .with_function("join", |args| Ok(args.join("-")))
+                .with_function("fail", |_| Err("nope".into()))
+                .with_filter("upper", |s| Ok(format!("<{s}>")));
+            let values = HashMap::from([("join".into(), TemplateString::raw("var"))]);
+
+            let ts = "{{join(a, b) | upper}}/{{join}}/{{$timestamp | length}}"
+                .parse::<TemplateString>()
+                .unwrap();
+            assert!(matches!(
+                ts.fill_with(&HashMap::new(), &functions),
+                Err(FillError::Filter { filter, .. }) if filter == "length"
+            ));
+
+            let ts = "{{join(a, b) | upper}}/{{join x | lower}}"
+                .parse::<TemplateString>()
+                .unwrap();
+            assert_eq!(
+                ts.fill_with(&HashMap::new(), &functions).unwrap(),
+                "<a-b>/x"
+            );
+            assert_eq!(ts.fill_with(&values, &functions).unwrap(), "<var>/var");
+
+            let ts = "{{fail()}}".parse::<TemplateString>().unwrap();
+            assert!(matches!(
+                ts.fill_with(&values, &functions),
+                Err(FillError::Unresolved { reason, .. }) if reason == "nope"
+            ));
+        }
+
+        #[test]
+        fn test_fill_partial() {
+            let ts = "{{a}}/{{b | upper}}/{{c | upper}}/{{$nope}}/{{b}}"
+                .parse::<TemplateString>()
+                .unwrap();
+            let values = HashMap::from([
+                ("a".into(), "a{{d}}".parse::<TemplateString>().unwrap()),
+                ("b".into(), TemplateString::raw("b")),
+                ("c".into(), "{{d}}".parse::<TemplateString>().unwrap()),
+            ]);
+
+            assert_eq!(
+                ts.fill_partial(&values, &()),
+                (
+                    "a{{d}}/B/{{c | upper}}/{{$nope}}/b".into(),
+                    vec!["d".into(), "$nope".into()]
+                )
+            );
+
+            let functions = Functions::new().with_function("d", |_| Ok("d".into()));
+            assert_eq!(
+                ts.fill_partial(&values, &functions),
+                ("ad/B/D/{{$nope}}/b".into(), vec!["$nope".into()])
+            );
+
+            let ts = TemplateString::new(vec![
+                Fragment::raw("Bearer "),
+                Fragment::command("pass show key"),
+            ]);
+            assert_eq!(
+                ts.fill_partial(&values, &()),
+                ("Bearer $(pass show key)".into(), vec![])
+            );
+        }
+
+        #[test]
+        fn test_missing_variables() {
+            let ts = "{{a}}/{{b}}/{{$timestamp}}/{{$nope}}"
+                .parse::<TemplateString>()
+                .unwrap();
+            let values = HashMap::from([
+                ("a".into(), "{{c}}{{a}}".parse::<TemplateString>().unwrap()),
+                ("c".into(), TemplateString::raw("c")),
+            ]);
+
+            assert_eq!(
+                ts.missing_variables(&values, &()),
+                HashSet::from(["b".into(), "$nope".into()])
+            );
+
+            let functions = Functions::new().with_function("b", |_| Ok("b".into()));
+            assert_eq!(
+                ts.missing_variables(&values, &functions),
+                HashSet::from(["$nope".into()])
+            );
+        }
+
+        #[test]
+        fn test_parse_filters() {
+            let s = "{{ name | upper |base64}}";
+            let expected = TemplateString::new(vec![Fragment::Var(
+                Variable::new("name").with_filters(vec!["upper".into(), "base64".into()]),
+            )]);
+
+            assert_eq!(s.parse::<TemplateString>().unwrap(), expected);
+            assert_eq!(expected.to_string(), "{{name | upper | base64}}");
+        }
+
+        #[test]
+        fn test_fill_filters() {
+            let ts = "{{name | upper}}&{{name | urlencode}}&{{name | base64}}"
+                .parse::<TemplateString>()
+                .unwrap();
+            let unknown = "{{name | foo}}".parse::<TemplateString>().unwrap();
+            let values = HashMap::from([("name".into(), TemplateString::raw("a b"))]);
+
+            assert_eq!(ts.fill(&values).unwrap(), "A B&a%20b&YSBi");
+            assert!(matches!(
+                unknown.fill(&values),
+                Err(FillError::Filter { .. })
+            ));
+        }
+
+        #[test]
+        fn test_parse_args() {
+            let s = "{{$datetime \"%Y %m\" -1d | upper}}";
+            let expected = TemplateString::new(vec![Fragment::Var(
+                Variable::new("$datetime")
+                    .with_args(vec!["%Y %m".into(), "-1d".into()])
+                    .with_filters(vec!["upper".into()]),
+            )]);
+
+            assert_eq!(s.parse::<TemplateString>().unwrap(), expected);
+            assert_eq!(expected.to_string(), s);
+        }
+
         #[test]
         fn test_is_empty() {
             let ts = TemplateString::new(vec![]);
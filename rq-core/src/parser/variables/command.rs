@@ -0,0 +1,57 @@
+//! Command substitution, i.e. `@token = $(pass show api/token)`.
+//!
+//! Running arbitrary commands from a file is disabled by default and must be
+//! explicitly allowed with [`set_enabled`].
+
+use std::{
+    process::Command,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use super::FillError;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub(super) fn run(command: &str) -> Result<String, FillError> {
+    let error = |reason: String| FillError::Command {
+        command: command.to_owned(),
+        reason,
+    };
+
+    if !is_enabled() {
+        return Err(error("command substitution is disabled".into()));
+    }
+
+    let output = shell(command).output().map_err(|e| error(e.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(error(format!("{}: {}", output.status, stderr.trim())));
+    }
+
+    let stdout = String::from_utf8(output.stdout).map_err(|e| error(e.to_string()))?;
+
+    Ok(stdout.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
@@ -0,0 +1,76 @@
+//! Dynamic variables, i.e. `{{$name}}`, whose value is computed at fill time
+//! instead of being defined in the file.
+
+use super::{FillError, Variable};
+
+mod datetime;
+pub mod random;
+
+const PREFIX: char = '$';
+
+// Called with what follows the first dot of the name, if any, and the arguments
+type Generator = fn(&str, &[String]) -> Result<String, String>;
+
+pub fn resolve(variable: &Variable) -> Result<String, FillError> {
+    let Some((generate, rest)) = generator(&variable.name) else {
+        return Err(FillError::from(variable.clone()));
+    };
+
+    generate(rest, &variable.args).map_err(|reason| FillError::Unresolved {
+        variable: variable.clone(),
+        reason,
+    })
+}
+
+/// Whether `name` is the name of a dynamic variable, without evaluating it.
+pub fn defines(name: &str) -> bool {
+    generator(name).is_some()
+}
+
+fn generator(name: &str) -> Option<(Generator, &str)> {
+    let name = name.strip_prefix(PREFIX)?;
+
+    match name.split_once('.') {
+        #[cfg(feature = "keyring")]
+        Some(("keyring", args)) => Some((|args, _| keyring(args), args)),
+        Some(("random", kind)) => Some((random::generate, kind)),
+        None => match name {
+            "datetime" => Some((|_, args| datetime::datetime(args, false), "")),
+            "localDatetime" => Some((|_, args| datetime::datetime(args, true), "")),
+            "timestamp" => Some((|_, args| datetime::timestamp(args), "")),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(feature = "keyring")]
+fn keyring(args: &str) -> Result<String, String> {
+    let (service, user) = args
+        .split_once('.')
+        .ok_or_else(|| "expected '$keyring.<service>.<user>'".to_owned())?;
+
+    crate::keyring::get(service, user).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, FillError, Variable};
+
+    #[test]
+    fn test_resolve_error() {
+        let mut variable = Variable::new("$datetime");
+        variable.args = vec!["%Q".into()];
+        assert!(matches!(
+            resolve(&variable),
+            Err(FillError::Unresolved { reason, .. }) if reason == "invalid format '%Q'"
+        ));
+
+        let mut variable = Variable::new("$timestamp");
+        variable.args = vec!["+100000000d".into()];
+        assert!(matches!(
+            resolve(&variable),
+            Err(FillError::Unresolved { .. })
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+//! `{{$datetime <format> [offset]}}`, `{{$localDatetime <format> [offset]}}`
+//! and `{{$timestamp [offset]}}`.
+//!
+//! `format` is one of `rfc1123`, `iso8601` or a `strftime` format string,
+//! `offset` is a signed amount followed by a unit, i.e. `-1d` or `+2h`.
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Duration, Local, TimeZone, Utc,
+};
+
+pub fn datetime(args: &[String], local: bool) -> Result<String, String> {
+    let (format, offset) = match args {
+        [format] => (format, None),
+        [format, offset] => (format, Some(offset)),
+        _ => return Err("expected '<format> [offset]'".into()),
+    };
+
+    let offset = offset.map(|o| parse_offset(o)).transpose()?;
+
+    if local {
+        format_datetime(shift(Local::now(), offset.unwrap_or_default())?, format)
+    } else {
+        format_datetime(shift(Utc::now(), offset.unwrap_or_default())?, format)
+    }
+}
+
+pub fn timestamp(args: &[String]) -> Result<String, String> {
+    let offset = match args {
+        [] => Duration::zero(),
+        [offset] => parse_offset(offset)?,
+        _ => return Err("expected '[offset]'".into()),
+    };
+
+    Ok(shift(Utc::now(), offset)?.timestamp().to_string())
+}
+
+fn shift<Tz: TimeZone>(datetime: DateTime<Tz>, offset: Duration) -> Result<DateTime<Tz>, String> {
+    datetime
+        .checked_add_signed(offset)
+        .ok_or_else(|| "the offset is out of the supported range".into())
+}
+
+fn format_datetime<Tz>(datetime: DateTime<Tz>, format: &str) -> Result<String, String>
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    let formatted = match format {
+        "rfc1123" => datetime
+            .to_utc()
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string(),
+        "iso8601" => datetime.to_rfc3339(),
+        format => {
+            // `format` would panic while displaying them
+            if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                return Err(format!("invalid format '{format}'"));
+            }
+            datetime.format(format).to_string()
+        }
+    };
+
+    Ok(formatted)
+}
+
+fn parse_offset(offset: &str) -> Result<Duration, String> {
+    let error = || format!("invalid offset '{offset}', expected i.e. '-1d' or '+2h'");
+
+    let unit_idx = offset
+        .find(|c: char| c.is_ascii_alphabetic())
+        .ok_or_else(error)?;
+    let (amount, unit) = offset.split_at(unit_idx);
+    let amount: i64 = amount
+        .strip_prefix('+')
+        .unwrap_or(amount)
+        .parse()
+        .map_err(|_| error())?;
+
+    let duration = match unit {
+        "ms" => Duration::try_milliseconds(amount),
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => return Err(error()),
+    };
+
+    duration.ok_or_else(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{datetime, format_datetime, parse_offset, timestamp};
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("-1d"), Ok(Duration::days(-1)));
+        assert_eq!(parse_offset("+2h"), Ok(Duration::hours(2)));
+        assert_eq!(parse_offset("30m"), Ok(Duration::minutes(30)));
+        assert!(parse_offset("2y").is_err());
+        assert!(parse_offset("d").is_err());
+        assert!(parse_offset("9999999999999999d").is_err());
+        assert!(parse_offset("-9223372036854775807w").is_err());
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        assert!(datetime(&args(&["iso8601", "+100000000d"]), false).is_err());
+        assert!(datetime(&args(&["iso8601", "-100000000d"]), true).is_err());
+        assert!(timestamp(&args(&["+100000000d"])).is_err());
+        assert!(timestamp(&args(&["+1d"])).is_ok());
+    }
+
+    #[test]
+    fn test_format() {
+        let datetime = Utc.with_ymd_and_hms(2023, 11, 5, 8, 30, 0).unwrap();
+
+        assert_eq!(
+            format_datetime(datetime, "rfc1123"),
+            Ok("Sun, 05 Nov 2023 08:30:00 GMT".into())
+        );
+        assert_eq!(
+            format_datetime(datetime, "iso8601"),
+            Ok("2023-11-05T08:30:00+00:00".into())
+        );
+        assert_eq!(
+            format_datetime(datetime, "%Y-%m-%d"),
+            Ok("2023-11-05".into())
+        );
+        assert_eq!(
+            format_datetime(datetime, "%Y-%Q"),
+            Err("invalid format '%Y-%Q'".into())
+        );
+        assert!(format_datetime(datetime, "%").is_err());
+    }
+}
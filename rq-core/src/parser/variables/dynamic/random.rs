@@ -0,0 +1,121 @@
+//! `{{$random.<kind> [args]}}` generators for fake test data.
+//!
+//! The generator is seeded from entropy, use [`seed`] for reproducible values.
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use rand::{distributions::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+static RNG: Lazy<Mutex<StdRng>> = Lazy::new(|| Mutex::new(StdRng::from_entropy()));
+
+const FIRST_NAMES: &[&str] = &[
+    "Alice", "Bob", "Carla", "David", "Emma", "Frank", "Giulia", "Hiro", "Ines", "Jonas", "Karim",
+    "Laura", "Marco", "Nadia", "Oscar", "Paula",
+];
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Rossi", "Müller", "Garcia", "Tanaka", "Martin", "Bianchi", "Novak", "Silva", "Kim",
+    "Dubois", "Jensen",
+];
+const DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+
+pub fn seed(seed: u64) {
+    *RNG.lock().unwrap() = StdRng::seed_from_u64(seed);
+}
+
+pub fn generate(kind: &str, args: &[String]) -> Result<String, String> {
+    let mut rng = RNG.lock().unwrap();
+
+    let value = match (kind, args) {
+        ("uuid", []) => uuid(&mut *rng),
+        ("firstName", []) => pick(&mut *rng, FIRST_NAMES).to_owned(),
+        ("lastName", []) => pick(&mut *rng, LAST_NAMES).to_owned(),
+        ("name", []) => format!(
+            "{} {}",
+            pick(&mut *rng, FIRST_NAMES),
+            pick(&mut *rng, LAST_NAMES)
+        ),
+        ("email", []) => format!(
+            "{}.{}{}@{}",
+            pick(&mut *rng, FIRST_NAMES).to_lowercase(),
+            pick(&mut *rng, LAST_NAMES).to_lowercase(),
+            rng.gen_range(1..100),
+            pick(&mut *rng, DOMAINS)
+        ),
+        ("alphanumeric", [len]) => {
+            let len: usize = len.parse().map_err(|_| format!("invalid length '{len}'"))?;
+            (&mut *rng)
+                .sample_iter(Alphanumeric)
+                .take(len)
+                .map(char::from)
+                .collect()
+        }
+        ("int", [min, max]) => {
+            let min: i64 = min.parse().map_err(|_| format!("invalid number '{min}'"))?;
+            let max: i64 = max.parse().map_err(|_| format!("invalid number '{max}'"))?;
+            if min >= max {
+                return Err(format!("empty range {min}..{max}"));
+            }
+            rng.gen_range(min..max).to_string()
+        }
+        ("uuid" | "firstName" | "lastName" | "name" | "email", _) => {
+            return Err("unexpected arguments".into())
+        }
+        ("alphanumeric", _) => return Err("expected '<length>'".into()),
+        ("int", _) => return Err("expected '<min> <max>'".into()),
+        _ => return Err(format!("unknown generator '{kind}'")),
+    };
+
+    Ok(value)
+}
+
+fn pick<'a>(rng: &mut impl Rng, values: &[&'a str]) -> &'a str {
+    values.choose(rng).unwrap()
+}
+
+fn uuid(rng: &mut impl Rng) -> String {
+    let mut bytes: [u8; 16] = rng.gen();
+    // Version 4, variant RFC 4122
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{generate, uuid};
+
+    #[test]
+    fn test_uuid() {
+        let uuid = uuid(&mut StdRng::seed_from_u64(0));
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.chars().nth(14), Some('4'));
+        assert!(matches!(uuid.chars().nth(19), Some('8' | '9' | 'a' | 'b')));
+    }
+
+    #[test]
+    fn test_generate() {
+        let alphanumeric = generate("alphanumeric", &["16".into()]).unwrap();
+        let int = generate("int", &["1".into(), "3".into()]).unwrap();
+
+        assert_eq!(alphanumeric.len(), 16);
+        assert!(alphanumeric.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert!(["1", "2"].contains(&int.as_str()));
+        assert!(generate("email", &[]).unwrap().contains('@'));
+        assert!(generate("int", &["3".into(), "1".into()]).is_err());
+        assert!(generate("foo", &[]).is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! Filters applied to variables, i.e. `{{secret | base64}}`.
+//!
+//! Besides the built-in ones, library users can provide their own with a
+//! [`Resolver`], which takes precedence.
+
+use base64::Engine;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use sha2::{Digest, Sha256};
+
+use super::{resolver::Resolver, FillError};
+
+pub(super) fn apply(
+    filters: &[String],
+    value: String,
+    resolver: &dyn Resolver,
+) -> Result<String, FillError> {
+    filters.iter().try_fold(value, |value, name| {
+        let error = |reason: String| FillError::Filter {
+            filter: name.to_owned(),
+            reason,
+        };
+
+        resolver
+            .filter(name, &value)
+            .map(|filtered| filtered.map_err(error))
+            .or_else(|| builtin(name, &value).map(Ok))
+            .unwrap_or_else(|| Err(error("unknown filter".into())))
+    })
+}
+
+fn builtin(name: &str, value: &str) -> Option<String> {
+    let filtered = match name {
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        "trim" => value.trim().to_owned(),
+        "base64" => base64::engine::general_purpose::STANDARD.encode(value),
+        "urlencode" => utf8_percent_encode(value, NON_ALPHANUMERIC).to_string(),
+        "sha256" => hex(&Sha256::digest(value)),
+        _ => return None,
+    };
+
+    Some(filtered)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,94 @@
+//! Custom template functions and filters, provided by library users when
+//! filling a template with [`TemplateString::fill_with`](super::TemplateString::fill_with).
+//!
+//! Functions are called like variables, i.e. `{{greet(world)}}` or
+//! `{{greet world}}`, and are only looked up when no variable with that name
+//! is defined. Anything a [`Resolver`] doesn't know falls back to the
+//! built-in dynamic variables and filters.
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use rq_core::parser::variables::{resolver::Functions, TemplateString};
+//!
+//! let functions = Functions::new()
+//!     .with_function("greet", |args| Ok(format!("hello {}", args.join(" "))))
+//!     .with_filter("shout", |value| Ok(format!("{value}!")));
+//!
+//! let template: TemplateString = "{{greet(world) | shout}}".parse().unwrap();
+//! let filled = template.fill_with(&HashMap::new(), &functions).unwrap();
+//!
+//! assert_eq!(filled, "hello world!");
+//! ```
+
+use std::collections::HashMap;
+
+pub trait Resolver {
+    /// Calls the function `name`, returning `None` if it's unknown.
+    fn call(&self, name: &str, args: &[String]) -> Option<Result<String, String>>;
+
+    /// Whether `call` knows the function `name`, without calling it.
+    fn defines(&self, name: &str) -> bool;
+
+    /// Applies the filter `name` to `value`, returning `None` if it's unknown.
+    fn filter(&self, _name: &str, _value: &str) -> Option<Result<String, String>> {
+        None
+    }
+}
+
+/// Resolves nothing, leaving everything to the built-ins.
+impl Resolver for () {
+    fn call(&self, _name: &str, _args: &[String]) -> Option<Result<String, String>> {
+        None
+    }
+
+    fn defines(&self, _name: &str) -> bool {
+        false
+    }
+}
+
+type Function = Box<dyn Fn(&[String]) -> Result<String, String> + Send + Sync>;
+type Filter = Box<dyn Fn(&str) -> Result<String, String> + Send + Sync>;
+
+/// Registry of named functions and filters.
+#[derive(Default)]
+pub struct Functions {
+    functions: HashMap<String, Function>,
+    filters: HashMap<String, Filter>,
+}
+
+impl Functions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_function<F>(mut self, name: &str, function: F) -> Self
+    where
+        F: Fn(&[String]) -> Result<String, String> + Send + Sync + 'static,
+    {
+        self.functions.insert(name.to_owned(), Box::new(function));
+        self
+    }
+
+    pub fn with_filter<F>(mut self, name: &str, filter: F) -> Self
+    where
+        F: Fn(&str) -> Result<String, String> + Send + Sync + 'static,
+    {
+        self.filters.insert(name.to_owned(), Box::new(filter));
+        self
+    }
+}
+
+impl Resolver for Functions {
+    fn call(&self, name: &str, args: &[String]) -> Option<Result<String, String>> {
+        self.functions.get(name).map(|function| function(args))
+    }
+
+    fn defines(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    fn filter(&self, name: &str, value: &str) -> Option<Result<String, String>> {
+        self.filters.get(name).map(|filter| filter(value))
+    }
+}
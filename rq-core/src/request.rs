@@ -1,24 +1,40 @@
-extern crate reqwest;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
-use once_cell::sync::Lazy;
-pub use reqwest::StatusCode;
-use reqwest::{header::HeaderMap, Client};
+use bytes::Bytes;
 
-use crate::parser::HttpRequest;
-use std::time::Duration;
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+pub use http::{header, StatusCode};
 
 use self::mime::Payload;
 
+#[cfg(feature = "client")]
+mod client;
 mod decode;
+#[cfg(feature = "client")]
+pub mod download;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod har;
+#[cfg(feature = "client")]
+pub mod hooks;
+pub mod link;
 pub mod mime;
+pub mod pin;
+pub mod protocol;
+pub mod redact;
+pub mod token;
 
-static CLIENT: Lazy<Client> = Lazy::new(|| {
-    Client::builder()
-        .timeout(Duration::from_secs(10))
-        .no_gzip()
-        .build()
-        .unwrap()
-});
+#[cfg(feature = "blocking")]
+pub use client::execute_blocking;
+#[cfg(feature = "client")]
+pub(crate) use client::CLIENT;
+#[cfg(feature = "client")]
+pub use client::{
+    execute, execute_streaming, execute_with_progress, is_connect, is_transient, is_unreachable,
+    probe, set_client_config, set_host_proxies, set_proxy, stale_settings, user_agent,
+    ClientConfig, Progress, ProxyConfig, DEFAULT_USER_AGENT,
+};
 
 #[derive(Clone)]
 pub struct Response {
@@ -26,34 +42,64 @@ pub struct Response {
     pub version: String,
     pub headers: HeaderMap,
     pub payload: Payload,
+    // Url the response came from, after following redirects
+    pub url: String,
+    pub remote_addr: Option<SocketAddr>,
+    // ALPN protocol negotiated over TLS, i.e. `h2`
+    pub protocol: Option<String>,
+    // Redirects followed to get here, in order
+    pub redirects: Vec<Redirect>,
+    pub timings: Timings,
+    // Set when the body was bigger than `ClientConfig::max_body`, `payload`
+    // then holding its head only
+    pub spilled: Option<Spilled>,
+    // Sent after the body, i.e. `grpc-status`. Only gRPC responses have them:
+    // HTTP trailers, after chunked or HTTP/2 bodies, aren't supported, reqwest
+    // 0.11 discarding them while decoding the body
+    pub trailers: HeaderMap,
 }
 
-impl Response {
-    async fn from_reqwest(value: reqwest::Response) -> Self {
-        let status = value.status();
-        let version = format!("{:?}", value.version());
-        let headers = value.headers().clone();
-        let payload = Payload::of_response(value).await;
-
-        Self {
-            status,
-            version,
-            headers,
-            payload,
-        }
-    }
+/// Body too big to be kept in memory, written to a temporary file.
+#[derive(Debug, Clone)]
+pub struct Spilled {
+    pub file: Arc<TempFile>,
+    // Of the whole body
+    pub size: u64,
+    // Last bytes of the body, the head being in the payload
+    pub tail: Bytes,
 }
 
-type RequestResult = Result<Response, Box<dyn std::error::Error + Send + Sync>>;
+/// File removed once dropped, i.e. with the last response referring to it.
+#[derive(Debug)]
+pub struct TempFile(pub PathBuf);
 
-pub async fn execute(req: HttpRequest) -> RequestResult {
-    let request = CLIENT
-        .request(req.method.clone(), req.url)
-        .query(&req.query)
-        .headers(req.headers)
-        .body(req.body);
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timings {
+    pub started_at: DateTime<Utc>,
+    // Until the response head is received, redirects included
+    pub wait: Duration,
+    // Downloading the body
+    pub receive: Duration,
+}
 
-    let response = request.send().await?;
+/// Intermediate response of a redirect chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirect {
+    pub url: String,
+    pub status: StatusCode,
+}
 
-    Ok(Response::from_reqwest(response).await)
+/// Status line and headers of a response whose body is streamed.
+#[derive(Debug, Clone)]
+pub struct ResponseHead {
+    pub status: StatusCode,
+    pub version: String,
+    pub headers: HeaderMap,
+    pub content_length: Option<u64>,
 }
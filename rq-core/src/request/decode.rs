@@ -1,9 +1,83 @@
-use bytes::Bytes;
 use encoding_rs::{Encoding, UTF_8};
 
-pub fn decode_with_encoding(bytes: &Bytes, encoding_name: &str) -> (String, &'static Encoding) {
-    let encoding = Encoding::for_label(encoding_name.as_bytes()).unwrap_or(UTF_8);
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub warning: Option<String>,
+}
+
+/// Decodes `bytes` with the charset labelled `label`, unless they start with
+/// a BOM. Unknown labels fall back to UTF-8.
+pub fn decode_with_encoding(bytes: &[u8], label: &str) -> Decoded {
+    let (encoding, mut warning) = match Encoding::for_label(label.as_bytes()) {
+        Some(encoding) => (encoding, None),
+        None => (
+            UTF_8,
+            Some(format!("unknown charset '{label}', falling back to UTF-8")),
+        ),
+    };
+
+    let (text, encoding, had_errors) = encoding.decode(bytes);
+    if had_errors {
+        warning.get_or_insert_with(|| malformed(encoding));
+    }
+
+    Decoded {
+        text: text.into_owned(),
+        encoding,
+        warning,
+    }
+}
+
+/// Decodes `bytes` with `encoding` regardless of any BOM, which is only
+/// stripped if it matches `encoding`.
+pub fn decode_forced(bytes: &[u8], encoding: &'static Encoding) -> Decoded {
+    let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+
+    Decoded {
+        text: text.into_owned(),
+        encoding,
+        warning: had_errors.then(|| malformed(encoding)),
+    }
+}
+
+fn malformed(encoding: &'static Encoding) -> String {
+    format!("malformed {} sequences replaced", encoding.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{UTF_16LE, UTF_8, WINDOWS_1252};
+
+    use super::{decode_forced, decode_with_encoding};
+
+    #[test]
+    fn test_bom() {
+        let decoded = decode_with_encoding(b"\xFF\xFEh\0i\0", "iso-8859-1");
+        assert_eq!(decoded.text, "hi");
+        assert_eq!(decoded.encoding, UTF_16LE);
+        assert!(decoded.warning.is_none());
+
+        let decoded = decode_with_encoding(b"\xEF\xBB\xBFhi", "utf-8");
+        assert_eq!(decoded.text, "hi");
+    }
+
+    #[test]
+    fn test_fallback() {
+        let decoded = decode_with_encoding("héllo".as_bytes(), "made-up");
+        assert_eq!(decoded.text, "héllo");
+        assert_eq!(decoded.encoding, UTF_8);
+        assert!(decoded.warning.unwrap().contains("made-up"));
+
+        let decoded = decode_with_encoding(b"h\xE9llo", "utf-8");
+        assert_eq!(decoded.text, "h\u{FFFD}llo");
+        assert!(decoded.warning.is_some());
+    }
 
-    let (text, encoding, _) = encoding.decode(bytes);
-    (text.into_owned(), encoding)
+    #[test]
+    fn test_forced() {
+        let decoded = decode_forced(b"h\xE9llo", WINDOWS_1252);
+        assert_eq!(decoded.text, "héllo");
+        assert!(decoded.warning.is_none());
+    }
 }
@@ -0,0 +1,195 @@
+//! Streaming of response bodies to disk, resuming partial downloads with a
+//! `Range` request.
+
+use std::{future::poll_fn, path::Path, pin::pin};
+
+use futures_core::Stream;
+use http::{
+    header::{ACCEPT_RANGES, CONTENT_RANGE, RANGE},
+    HeaderValue, StatusCode,
+};
+use thiserror::Error;
+use tokio::{
+    fs::{self, OpenOptions},
+    io::AsyncWriteExt,
+};
+
+use crate::parser::HttpRequest;
+
+use super::{execute_streaming, ResponseHead};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Status of a response whose body isn't saved, not to replace a partial
+/// download with an error page.
+#[derive(Debug, Clone, Error)]
+#[error("server responded with {0}, nothing saved")]
+pub struct StatusError(pub StatusCode);
+
+/// Outcome of [`download`].
+#[derive(Debug, Clone)]
+pub struct Download {
+    pub head: ResponseHead,
+    // Bytes already saved, that weren't downloaded again
+    pub resumed_from: u64,
+    pub written: u64,
+}
+
+/// Streams the body of `req` to `path`.
+///
+/// With `resume`, a partially saved file is completed with a `Range` request,
+/// if the server answers with `206 Partial Content`: otherwise it's
+/// downloaded again from the start on a `2xx`. Other statuses are a
+/// [`StatusError`], leaving the file untouched. When the download fails
+/// midway, what was received is kept on disk.
+pub async fn download(mut req: HttpRequest, path: &Path, resume: bool) -> Result<Download, Error> {
+    let saved = match fs::metadata(path).await {
+        Ok(metadata) if resume => metadata.len(),
+        _ => 0,
+    };
+    if saved > 0 {
+        req.headers
+            .insert(RANGE, HeaderValue::from_str(&format!("bytes={saved}-"))?);
+    }
+
+    let (head, stream) = execute_streaming(req).await?;
+
+    let resumed_from = match head.status {
+        // Nothing left to download
+        StatusCode::RANGE_NOT_SATISFIABLE if saved > 0 => {
+            return Ok(Download {
+                head,
+                resumed_from: saved,
+                written: 0,
+            });
+        }
+        StatusCode::PARTIAL_CONTENT if saved > 0 => {
+            let start = head
+                .headers
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(content_range_start);
+            if start != Some(saved) {
+                return Err(format!("the server didn't resume from byte {saved}").into());
+            }
+            saved
+        }
+        status if status.is_success() => 0,
+        status => return Err(StatusError(status).into()),
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed_from > 0)
+        .truncate(resumed_from == 0)
+        .open(path)
+        .await?;
+
+    let mut stream = pin!(stream);
+    let mut written = 0;
+    while let Some(chunk) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                file.flush().await?;
+                let total = resumed_from + written;
+                let hint = if accepts_ranges(&head) {
+                    ", resume to complete it"
+                } else {
+                    ""
+                };
+                return Err(format!("{e}: {total} bytes saved{hint}").into());
+            }
+        };
+
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+    file.flush().await?;
+
+    Ok(Download {
+        head,
+        resumed_from,
+        written,
+    })
+}
+
+/// Whether the server advertised `Accept-Ranges: bytes`.
+pub fn accepts_ranges(head: &ResponseHead) -> bool {
+    head.headers
+        .get(ACCEPT_RANGES)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("bytes"))
+}
+
+// `bytes 100-199/200`
+fn content_range_start(value: &str) -> Option<u64> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (start, _) = range.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::content_range_start;
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_error_status_keeps_file() {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            net::TcpListener,
+        };
+
+        use crate::{parser::HttpRequest, request::StatusCode};
+
+        use super::{download, StatusError};
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/export", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            let mut reader = BufReader::new(&stream);
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 4\r\n\r\noops")
+                .unwrap();
+        });
+
+        let path = std::env::temp_dir().join(format!("rq-download-{}", std::process::id()));
+        std::fs::write(&path, "partial").unwrap();
+
+        let request = HttpRequest {
+            url,
+            ..Default::default()
+        };
+        let error = runtime
+            .block_on(download(request, &path, true))
+            .unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<StatusError>(),
+            Some(StatusError(StatusCode::INTERNAL_SERVER_ERROR))
+        ));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "partial");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_content_range_start() {
+        assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(content_range_start("bytes 0-9/*"), Some(0));
+        assert_eq!(content_range_start("bytes */200"), None);
+        assert_eq!(content_range_start("items 1-2/3"), None);
+    }
+}
@@ -0,0 +1,310 @@
+//! gRPC requests, i.e. `GRPC localhost:50051/package.Service/Method` with a
+//! JSON message as body.
+//!
+//! Messages are encoded with the `.proto` files given with `# @proto`, or
+//! with the descriptors served by the server reflection service. Only unary
+//! methods are supported.
+
+use std::{collections::HashSet, path::Path, time::Instant};
+
+use chrono::Utc;
+use mime::APPLICATION_JSON;
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor};
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use tonic::{
+    client::Grpc,
+    codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+    codegen::{
+        http::{uri::PathAndQuery, Uri},
+        tokio_stream,
+    },
+    metadata::MetadataMap,
+    transport::{Channel, ClientTlsConfig, Endpoint},
+    Status,
+};
+use tonic_reflection::pb::{
+    server_reflection_client::ServerReflectionClient, server_reflection_request::MessageRequest,
+    server_reflection_response::MessageResponse, ServerReflectionRequest,
+};
+use tower::service_fn;
+use url::{Position, Url};
+
+use crate::parser::HttpRequest;
+
+use super::{client, mime::Payload, Response, StatusCode, Timings};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub(super) async fn execute(req: &HttpRequest) -> Result<Response, Error> {
+    let (started_at, start) = (Utc::now(), Instant::now());
+
+    let url = Url::parse(&req.url)?;
+    let (service, method) = url
+        .path()
+        .trim_start_matches('/')
+        .split_once('/')
+        .ok_or("expected `/<package>.<Service>/<Method>` as path")?;
+
+    let channel = connect(&url).await?;
+    let pool = if req.protos.is_empty() {
+        reflect(channel.clone(), service).await?
+    } else {
+        compile(&req.protos)?
+    };
+    let method = find_method(&pool, service, method)?;
+
+    let body = match req.body.trim() {
+        "" => "{}",
+        body => body,
+    };
+    let mut deserializer = serde_json::Deserializer::from_str(body);
+    let message = DynamicMessage::deserialize(method.input(), &mut deserializer)?;
+    deserializer.end()?;
+
+    let mut request = tonic::Request::new(message);
+    *request.metadata_mut() = MetadataMap::from_headers(req.headers.clone());
+
+    let path = PathAndQuery::try_from(format!("/{service}/{}", method.name()))?;
+    let mut client = Grpc::new(channel);
+    client.ready().await?;
+    let status = |status: Status| format!("{:?}: {}", status.code(), status.message());
+    // Sent as a stream of one, `unary` merging the trailers into the headers
+    let response = client
+        .server_streaming(request, path, DynamicCodec(method.output()))
+        .await
+        .map_err(status)?;
+    let wait = start.elapsed();
+
+    let (metadata, mut stream, _) = response.into_parts();
+    let message = stream
+        .message()
+        .await
+        .map_err(status)?
+        .ok_or("no message in the response")?;
+    let trailers = stream.trailers().await.map_err(status)?;
+    let json = serde_json::to_vec_pretty(&message)?;
+
+    Ok(Response {
+        status: StatusCode::OK,
+        version: "HTTP/2.0".into(),
+        headers: metadata.into_headers(),
+        payload: Payload::new(Some(APPLICATION_JSON), json.into()),
+        url: req.url.clone(),
+        remote_addr: None,
+        protocol: (url.scheme() == "https").then(|| "h2".into()),
+        redirects: Vec::new(),
+        timings: Timings {
+            started_at,
+            wait,
+            receive: start.elapsed() - wait,
+        },
+        spilled: None,
+        trailers: trailers.map(MetadataMap::into_headers).unwrap_or_default(),
+    })
+}
+
+// Through the proxy the other requests to `url` would go through, which has
+// to be an HTTP one
+async fn connect(url: &Url) -> Result<Channel, Error> {
+    let mut endpoint = Endpoint::from_shared(url[..Position::AfterPort].to_owned())?;
+    if url.scheme() == "https" {
+        endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+    }
+
+    let Some(proxy) = client::proxy_for(url) else {
+        return Ok(endpoint.connect().await?);
+    };
+    if proxy.scheme() != "http" {
+        return Err(
+            format!("gRPC requests can't go through the proxy {proxy}, only http ones").into(),
+        );
+    }
+
+    let target = client::address(url)?;
+    let connector = service_fn(move |_: Uri| {
+        let (proxy, target) = (proxy.clone(), target.clone());
+        async move { client::tunnel(&proxy, &target).await }
+    });
+
+    Ok(endpoint.connect_with_connector(connector).await?)
+}
+
+fn find_method(
+    pool: &DescriptorPool,
+    service: &str,
+    method: &str,
+) -> Result<MethodDescriptor, Error> {
+    let method = pool
+        .get_service_by_name(service)
+        .ok_or_else(|| format!("unknown service '{service}'"))?
+        .methods()
+        .find(|m| m.name() == method)
+        .ok_or_else(|| format!("unknown method '{method}' of '{service}'"))?;
+
+    if method.is_client_streaming() || method.is_server_streaming() {
+        return Err(format!("'{}' is a streaming method", method.full_name()).into());
+    }
+
+    Ok(method)
+}
+
+// Imports are resolved relative to the directory of each file
+fn compile(protos: &[String]) -> Result<DescriptorPool, Error> {
+    let mut includes = Vec::new();
+    for proto in protos {
+        let dir = match Path::new(proto).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !includes.contains(&dir) {
+            includes.push(dir);
+        }
+    }
+
+    let files = protox::compile(protos, includes)?;
+
+    Ok(DescriptorPool::from_file_descriptor_set(files)?)
+}
+
+// Fetches the file declaring `symbol` and its dependencies
+async fn reflect(channel: Channel, symbol: &str) -> Result<DescriptorPool, Error> {
+    let mut client = ServerReflectionClient::new(channel);
+
+    let mut files = Vec::new();
+    let mut requested = HashSet::new();
+    let mut pending = vec![MessageRequest::FileContainingSymbol(symbol.to_owned())];
+
+    while let Some(request) = pending.pop() {
+        for file in reflection_request(&mut client, request).await? {
+            let file = FileDescriptorProto::decode(file.as_slice())?;
+            requested.insert(file.name().to_owned());
+
+            for dependency in &file.dependency {
+                if requested.insert(dependency.clone()) {
+                    pending.push(MessageRequest::FileByFilename(dependency.clone()));
+                }
+            }
+            files.push(file);
+        }
+    }
+
+    // The same file can be sent more than once
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.name().to_owned()));
+
+    Ok(DescriptorPool::from_file_descriptor_set(
+        FileDescriptorSet { file: files },
+    )?)
+}
+
+async fn reflection_request(
+    client: &mut ServerReflectionClient<Channel>,
+    request: MessageRequest,
+) -> Result<Vec<Vec<u8>>, Error> {
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(request),
+    };
+
+    let response = client
+        .server_reflection_info(tokio_stream::once(request))
+        .await?
+        .into_inner()
+        .message()
+        .await?
+        .ok_or("reflection: no response")?;
+
+    match response.message_response {
+        Some(MessageResponse::FileDescriptorResponse(response)) => {
+            Ok(response.file_descriptor_proto)
+        }
+        Some(MessageResponse::ErrorResponse(e)) => {
+            Err(format!("reflection: {}", e.error_message).into())
+        }
+        _ => Err("reflection: unexpected response".into()),
+    }
+}
+
+// Encodes and decodes messages known only at runtime
+#[derive(Clone)]
+struct DynamicCodec(MessageDescriptor);
+
+impl Codec for DynamicCodec {
+    type Encode = DynamicMessage;
+    type Decode = DynamicMessage;
+    type Encoder = Self;
+    type Decoder = Self;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        self.clone()
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        self.clone()
+    }
+}
+
+impl Encoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, dst: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(dst)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+impl Decoder for DynamicCodec {
+    type Item = DynamicMessage;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        DynamicMessage::decode(self.0.clone(), src)
+            .map(Some)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+    use prost_reflect::DynamicMessage;
+
+    use super::{compile, find_method};
+
+    #[test]
+    fn test_compile() {
+        let dir = std::env::temp_dir().join(format!("rq-grpc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let proto = dir.join("greeter.proto");
+        std::fs::write(
+            &proto,
+            r#"
+syntax = "proto3";
+package test;
+
+service Greeter {
+    rpc Greet (Request) returns (Reply);
+    rpc Stream (Request) returns (stream Reply);
+}
+
+message Request { string name = 1; }
+message Reply { string greeting = 1; }
+"#,
+        )
+        .unwrap();
+
+        let pool = compile(&[proto.to_string_lossy().into_owned()]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let method = find_method(&pool, "test.Greeter", "Greet").unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(r#"{"name": "rq"}"#);
+        let message = DynamicMessage::deserialize(method.input(), &mut deserializer).unwrap();
+        assert_eq!(message.encode_to_vec(), b"\x0a\x02rq");
+
+        assert!(find_method(&pool, "test.Greeter", "Stream").is_err());
+        assert!(find_method(&pool, "test.Greeter", "Missing").is_err());
+        assert!(find_method(&pool, "test.Missing", "Greet").is_err());
+    }
+}
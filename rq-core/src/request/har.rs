@@ -0,0 +1,229 @@
+//! Conversion to [HAR](http://www.softwareishard.com/blog/har-12-spec/) entries.
+
+use std::time::Duration;
+
+use base64::Engine;
+use chrono::SecondsFormat;
+use http::{
+    header::{HeaderName, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE},
+    HeaderMap,
+};
+use percent_encoding::percent_decode_str;
+use serde_json::{json, Value};
+
+use crate::parser::HttpRequest;
+
+use super::Response;
+
+impl Response {
+    /// Builds the HAR entry of the exchange between `request` and this
+    /// response, to be collected in the `log.entries` of a HAR file.
+    pub fn to_har_entry(&self, request: &HttpRequest) -> Value {
+        let mut entry = json!({
+            "startedDateTime": self
+                .timings
+                .started_at
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            "time": millis(self.timings.wait + self.timings.receive),
+            "request": har_request(request),
+            "response": self.har_response(),
+            "cache": {},
+            "timings": {
+                "send": 0,
+                "wait": millis(self.timings.wait),
+                "receive": millis(self.timings.receive),
+            },
+        });
+
+        if let Some(addr) = self.remote_addr {
+            entry["serverIPAddress"] = addr.ip().to_string().into();
+            entry["connection"] = addr.port().to_string().into();
+        }
+
+        entry
+    }
+
+    fn har_response(&self) -> Value {
+        let bytes = self.payload.bytes();
+        let mut content = json!({
+            "size": bytes.len(),
+            "mimeType": header(&self.headers, CONTENT_TYPE).unwrap_or_default(),
+        });
+        if self.payload.is_text() {
+            content["text"] = self.payload.as_text_lossy().into();
+        } else {
+            content["text"] = base64::engine::general_purpose::STANDARD
+                .encode(bytes)
+                .into();
+            content["encoding"] = "base64".into();
+        }
+
+        json!({
+            "status": self.status.as_u16(),
+            "statusText": self.status.canonical_reason().unwrap_or_default(),
+            "httpVersion": self.version,
+            "cookies": self
+                .headers
+                .get_all(SET_COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .filter_map(set_cookie)
+                .collect::<Vec<_>>(),
+            "headers": headers(&self.headers),
+            "content": content,
+            "redirectURL": header(&self.headers, LOCATION).unwrap_or_default(),
+            "headersSize": -1,
+            "bodySize": bytes.len(),
+        })
+    }
+}
+
+fn har_request(request: &HttpRequest) -> Value {
+    let mut har = json!({
+        "method": request.method.as_str(),
+        "url": request.full_url(),
+        "httpVersion": format!("{:?}", request.version),
+        "cookies": request
+            .headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect::<Vec<_>>(),
+        "headers": headers(&request.headers),
+        "queryString": request
+            .query
+            .iter()
+            .map(|(name, value)| json!({ "name": decode(name), "value": decode(value) }))
+            .collect::<Vec<_>>(),
+        "headersSize": -1,
+        "bodySize": request.body.len(),
+    });
+
+    if !request.body.is_empty() {
+        har["postData"] = json!({
+            "mimeType": header(&request.headers, CONTENT_TYPE).unwrap_or_default(),
+            "text": request.body,
+        });
+    }
+
+    har
+}
+
+// i.e. `id=42; Path=/; HttpOnly`
+fn set_cookie(value: &str) -> Option<Value> {
+    let mut attributes = value.split(';').map(str::trim);
+    let (name, value) = attributes.next()?.split_once('=')?;
+    let mut cookie = json!({ "name": name, "value": value });
+
+    for attribute in attributes {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        match key.to_ascii_lowercase().as_str() {
+            "path" => cookie["path"] = value.into(),
+            "domain" => cookie["domain"] = value.into(),
+            "expires" => cookie["expires"] = value.into(),
+            "httponly" => cookie["httpOnly"] = true.into(),
+            "secure" => cookie["secure"] = true.into(),
+            _ => (),
+        }
+    }
+
+    Some(cookie)
+}
+
+fn headers(headers: &HeaderMap) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            json!({
+                "name": name.as_str(),
+                "value": String::from_utf8_lossy(value.as_bytes()),
+            })
+        })
+        .collect()
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::{HeaderMap, Method, StatusCode};
+
+    use crate::{
+        parser::HttpRequest,
+        request::{mime::Payload, Response, Timings},
+    };
+
+    #[test]
+    fn test_to_har_entry() {
+        let request = HttpRequest {
+            method: Method::POST,
+            url: "https://test.dev/items".into(),
+            query: vec![("q".into(), "a%20b".into())],
+            headers: HeaderMap::from_iter([
+                ("cookie".parse().unwrap(), "a=1; b=2".parse().unwrap()),
+                (
+                    "content-type".parse().unwrap(),
+                    "text/plain".parse().unwrap(),
+                ),
+            ]),
+            body: "hello".into(),
+            ..Default::default()
+        };
+        let response = Response {
+            status: StatusCode::CREATED,
+            version: "HTTP/1.1".into(),
+            headers: HeaderMap::from_iter([(
+                "set-cookie".parse().unwrap(),
+                "id=42; Path=/; HttpOnly".parse().unwrap(),
+            )]),
+            payload: Payload::new(None, vec![0xff, 0x00].into()),
+            url: "https://test.dev/items?q=a%20b".into(),
+            remote_addr: Some("127.0.0.1:443".parse().unwrap()),
+            protocol: None,
+            redirects: Vec::new(),
+            timings: Timings {
+                wait: Duration::from_millis(20),
+                receive: Duration::from_millis(5),
+                ..Default::default()
+            },
+            spilled: None,
+            trailers: HeaderMap::new(),
+        };
+
+        let entry = response.to_har_entry(&request);
+
+        assert_eq!(entry["startedDateTime"], "1970-01-01T00:00:00.000Z");
+        assert_eq!(entry["time"], 25.0);
+        assert_eq!(entry["serverIPAddress"], "127.0.0.1");
+
+        let request = &entry["request"];
+        assert_eq!(request["url"], "https://test.dev/items?q=a%20b");
+        assert_eq!(request["queryString"][0]["value"], "a b");
+        assert_eq!(request["cookies"][1]["name"], "b");
+        assert_eq!(request["postData"]["text"], "hello");
+
+        let response = &entry["response"];
+        assert_eq!(response["status"], 201);
+        assert_eq!(response["statusText"], "Created");
+        assert_eq!(response["cookies"][0]["path"], "/");
+        assert_eq!(response["cookies"][0]["httpOnly"], true);
+        assert_eq!(response["content"]["encoding"], "base64");
+        assert_eq!(response["content"]["text"], "/wA=");
+        assert_eq!(entry["timings"]["wait"], 20.0);
+    }
+}
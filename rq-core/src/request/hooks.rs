@@ -0,0 +1,119 @@
+//! Hooks run around every request, i.e. to inject headers, log traffic or
+//! record metrics. They are part of the [`ClientConfig`](super::ClientConfig).
+//!
+//! ```no_run
+//! use rq_core::request::{header::HeaderValue, hooks::Hooks, set_client_config, ClientConfig};
+//!
+//! let hooks = Hooks::new()
+//!     .on_request(|request| {
+//!         request
+//!             .headers_mut()
+//!             .insert("x-trace", HeaderValue::from_static("on"));
+//!     })
+//!     .on_response(|head| eprintln!("{}", head.status));
+//!
+//! set_client_config(ClientConfig {
+//!     hooks,
+//!     ..Default::default()
+//! });
+//! ```
+
+use std::{fmt, sync::Arc};
+
+pub use reqwest::Request;
+
+use super::ResponseHead;
+
+pub type RequestHook = Arc<dyn Fn(&mut Request) + Send + Sync>;
+pub type ResponseHook = Arc<dyn Fn(&ResponseHead) + Send + Sync>;
+
+/// Hooks of a client, run in registration order.
+#[derive(Clone, Default)]
+pub struct Hooks {
+    request: Vec<RequestHook>,
+    response: Vec<ResponseHook>,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a hook run on every request right before it's sent.
+    pub fn on_request<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut Request) + Send + Sync + 'static,
+    {
+        self.request.push(Arc::new(hook));
+        self
+    }
+
+    /// Adds a hook run on every response as soon as its head is received.
+    pub fn on_response<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&ResponseHead) + Send + Sync + 'static,
+    {
+        self.response.push(Arc::new(hook));
+        self
+    }
+
+    pub(super) fn run_request(&self, request: &mut Request) {
+        for hook in &self.request {
+            hook(request);
+        }
+    }
+
+    pub(super) fn run_response(&self, head: &ResponseHead) {
+        for hook in &self.response {
+            hook(head);
+        }
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("request", &self.request.len())
+            .field("response", &self.response.len())
+            .finish()
+    }
+}
+
+// The same hooks, not hooks doing the same
+impl PartialEq for Hooks {
+    fn eq(&self, other: &Self) -> bool {
+        fn same<T: ?Sized>(a: &[Arc<T>], b: &[Arc<T>]) -> bool {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| Arc::ptr_eq(a, b))
+        }
+
+        same(&self.request, &other.request) && same(&self.response, &other.response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{header::HeaderValue, Method, Url};
+
+    use super::{Hooks, Request};
+
+    #[test]
+    fn test_request_hooks() {
+        let hooks = Hooks::new()
+            .on_request(|request| {
+                request
+                    .headers_mut()
+                    .insert("x-first", HeaderValue::from_static("1"));
+            })
+            .on_request(|request| {
+                let first = request.headers()["x-first"].clone();
+                request.headers_mut().insert("x-second", first);
+            });
+
+        let mut request = Request::new(Method::GET, Url::parse("https://test.dev").unwrap());
+        hooks.run_request(&mut request);
+
+        assert_eq!(request.headers()["x-second"], "1");
+        assert!(hooks == hooks.clone());
+        assert!(hooks != Hooks::new());
+    }
+}
@@ -0,0 +1,324 @@
+//! Links found in responses: `Location`, `Content-Location` and `Link`
+//! headers, links of JSON bodies (i.e. HATEOAS `_links`) and urls in text.
+
+use http::header::{CONTENT_LOCATION, LINK, LOCATION};
+use serde_json::Value;
+use url::Url;
+
+use super::Response;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    // Absolute, relative links being resolved against the response url
+    pub url: String,
+    // Relation, i.e. `next` for `Link: <...>; rel="next"` or a `"next"` key
+    pub rel: Option<String>,
+    // Header the link comes from, `None` for the body
+    pub header: Option<&'static str>,
+}
+
+// JSON keys whose relative values are links
+const LINK_KEYS: &[&str] = &[
+    "href", "url", "uri", "link", "self", "next", "prev", "previous", "first", "last",
+];
+
+impl Response {
+    /// Links of the response, headers first, without duplicates.
+    pub fn links(&self) -> Vec<Link> {
+        let base = Url::parse(&self.url).ok();
+        let resolve = |url: &str| match &base {
+            Some(base) => base.join(url).ok().map(String::from),
+            None => Url::parse(url).ok().map(String::from),
+        };
+
+        let mut links = Vec::new();
+        let mut push = |url: Option<String>, rel: Option<String>, header| {
+            if let Some(url) = url {
+                if !links.iter().any(|link: &Link| link.url == url) {
+                    links.push(Link { url, rel, header });
+                }
+            }
+        };
+
+        for (name, label) in [
+            (LOCATION, "Location"),
+            (CONTENT_LOCATION, "Content-Location"),
+        ] {
+            if let Some(value) = self.headers.get(name).and_then(|v| v.to_str().ok()) {
+                push(resolve(value), None, Some(label));
+            }
+        }
+
+        for value in self.headers.get_all(LINK) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for (url, rel) in parse_link_header(value) {
+                push(resolve(url), rel, Some("Link"));
+            }
+        }
+
+        match self.payload.json() {
+            Ok(json) => {
+                let mut found = Vec::new();
+                json_links(&json, None, &mut found);
+                for (url, rel) in found {
+                    push(resolve(url), rel, None);
+                }
+            }
+            Err(_) if self.payload.is_text() => {
+                for url in text_links(&self.payload.as_text_lossy()) {
+                    push(Some(url.to_owned()), None, None);
+                }
+            }
+            Err(_) => (),
+        }
+
+        links
+    }
+
+    /// Link to follow up on: the `Location` header, i.e. of a `201` or a
+    /// redirect, or else the `next` page.
+    pub fn follow_link(&self) -> Option<Link> {
+        let mut links = self.links();
+        let idx = links
+            .iter()
+            .position(|link| link.header == Some("Location"))
+            .or_else(|| {
+                links
+                    .iter()
+                    .position(|link| link.rel.as_deref() == Some("next"))
+            })?;
+
+        Some(links.swap_remove(idx))
+    }
+}
+
+// `<url>; rel="next", <url>; rel="last"`, as of RFC 8288
+fn parse_link_header(value: &str) -> Vec<(&str, Option<String>)> {
+    let mut links = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let url = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        // Parameters up to the next link
+        let params = rest.split_once('<').map_or(rest, |(params, _)| params);
+        let rel = params.split([';', ',']).find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            (name.trim().eq_ignore_ascii_case("rel"))
+                .then(|| value.trim().trim_matches('"').to_owned())
+        });
+
+        links.push((url, rel));
+    }
+
+    links
+}
+
+// Absolute urls, and relative ones under `LINK_KEYS`. `href` takes the
+// relation of its parent key, i.e. `"next": {"href": "..."}`
+fn json_links<'a>(
+    value: &'a Value,
+    key: Option<&'a str>,
+    found: &mut Vec<(&'a str, Option<String>)>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                let rel = match k.as_str() {
+                    "href" | "url" | "uri" | "link" => key,
+                    k => Some(k),
+                };
+                match v {
+                    Value::String(s)
+                        if is_absolute(s)
+                            || (LINK_KEYS.contains(&k.as_str()) && s.starts_with('/')) =>
+                    {
+                        found.push((s, rel.map(str::to_owned)));
+                    }
+                    v => json_links(v, Some(k), found),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for v in values {
+                json_links(v, key, found);
+            }
+        }
+        Value::String(s) if is_absolute(s) => found.push((s, None)),
+        _ => (),
+    }
+}
+
+fn is_absolute(s: &str) -> bool {
+    s.starts_with("http://") || s.starts_with("https://")
+}
+
+fn text_links(text: &str) -> Vec<&str> {
+    let mut links = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || "\"'<>`\\{}|^".contains(c))
+            .unwrap_or(rest.len());
+        // i.e. the period ending a sentence
+        let url = rest[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+
+        if url.len() > "https://".len() {
+            links.push(url);
+        }
+        rest = &rest[end..];
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderMap, StatusCode};
+
+    use crate::request::mime::Payload;
+
+    use super::*;
+
+    fn response(headers: &[(&str, &str)], content_type: &str, body: &str) -> Response {
+        Response {
+            status: StatusCode::OK,
+            version: "HTTP/1.1".into(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap()))
+                .collect::<HeaderMap>(),
+            payload: Payload::new(Some(content_type.parse().unwrap()), body.to_owned().into()),
+            url: "https://test.dev/users?page=1".into(),
+            remote_addr: None,
+            protocol: None,
+            redirects: Vec::new(),
+            timings: Default::default(),
+            spilled: None,
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_header_links() {
+        let response = response(
+            &[
+                ("location", "/users/42"),
+                (
+                    "link",
+                    r#"<https://test.dev/users?page=2>; rel="next", </users?page=9>; rel=last"#,
+                ),
+            ],
+            "text/plain",
+            "",
+        );
+
+        assert_eq!(
+            response.links(),
+            vec![
+                Link {
+                    url: "https://test.dev/users/42".into(),
+                    rel: None,
+                    header: Some("Location"),
+                },
+                Link {
+                    url: "https://test.dev/users?page=2".into(),
+                    rel: Some("next".into()),
+                    header: Some("Link"),
+                },
+                Link {
+                    url: "https://test.dev/users?page=9".into(),
+                    rel: Some("last".into()),
+                    header: Some("Link"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_follow_link() {
+        let next = response(&[], "application/json", r#"{"next": "/users?page=2"}"#);
+        assert_eq!(
+            next.follow_link().map(|link| link.url),
+            Some("https://test.dev/users?page=2".into())
+        );
+
+        let created = response(
+            &[
+                ("location", "/users/42"),
+                ("link", r#"</users?page=2>; rel="next""#),
+            ],
+            "application/json",
+            r#"{"next": "/users?page=3"}"#,
+        );
+        assert_eq!(
+            created.follow_link().map(|link| link.url),
+            Some("https://test.dev/users/42".into())
+        );
+
+        let none = response(&[], "application/json", r#"{"id": 1}"#);
+        assert_eq!(none.follow_link(), None);
+    }
+
+    #[test]
+    fn test_json_links() {
+        let response = response(
+            &[],
+            "application/json",
+            r#"{
+                "items": [{"id": 1, "avatar": "https://cdn.test.dev/1.png"}],
+                "_links": {"self": {"href": "/users?page=1"}, "next": {"href": "/users?page=2"}},
+                "path": "/not/a/link"
+            }"#,
+        );
+
+        let links = response.links();
+        let urls = links
+            .iter()
+            .map(|link| (link.url.as_str(), link.rel.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                ("https://test.dev/users?page=2", Some("next")),
+                ("https://test.dev/users?page=1", Some("self")),
+                ("https://cdn.test.dev/1.png", Some("avatar")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_links() {
+        let response = response(
+            &[],
+            "text/html",
+            r#"<a href="https://test.dev/a">a</a> see https://test.dev/b?x=1. or (http://test.dev/c)"#,
+        );
+
+        let urls = response
+            .links()
+            .into_iter()
+            .map(|link| link.url)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "https://test.dev/a",
+                "https://test.dev/b?x=1",
+                "http://test.dev/c"
+            ]
+        );
+    }
+}
@@ -1,8 +1,11 @@
+use std::{borrow::Cow, fs::File, io::Write, path::Path, sync::Arc};
+
 use bytes::Bytes;
-use mime::{Mime, Name};
-use reqwest::{header::CONTENT_TYPE, Response};
+use mime::Mime;
+
+use encoding_rs::Encoding;
 
-use super::decode::decode_with_encoding;
+use super::decode::{decode_forced, decode_with_encoding, Decoded};
 
 #[derive(Debug, Clone)]
 pub struct BytePayload {
@@ -14,7 +17,12 @@ pub struct BytePayload {
 pub struct TextPayload {
     pub extension: Option<String>,
     pub charset: String,
-    pub text: String,
+    // Shared, as payloads are cloned around
+    pub text: Arc<str>,
+    /// Set when the declared charset was unknown or the text malformed
+    pub warning: Option<String>,
+    /// Undecoded body, to decode it again with another charset
+    pub bytes: Bytes,
 }
 
 #[derive(Debug, Clone)]
@@ -24,61 +32,340 @@ pub enum Payload {
 }
 
 impl Payload {
-    pub async fn of_response(response: Response) -> Payload {
-        let mime = response
-            .headers()
-            .get(CONTENT_TYPE)
-            .and_then(|value| value.to_str().ok())
-            .and_then(|value| value.parse::<Mime>().ok());
+    /// Builds the payload from its raw bytes, sniffing the content when
+    /// `mime` is missing or generic (`application/octet-stream`).
+    pub fn new(mime: Option<Mime>, bytes: Bytes) -> Payload {
+        let mime = mime
+            .filter(|mime| *mime != mime::APPLICATION_OCTET_STREAM)
+            .or_else(|| sniff(&bytes));
 
         match mime {
             Some(mime) => {
-                let extension = mime.subtype();
-
-                match (mime.type_(), extension) {
-                    (mime::TEXT, _) | (_, mime::JSON) => {
-                        let charset = mime
-                            .get_param("charset")
-                            .map_or("utf-8".into(), |charset| charset.to_string());
-                        let (text, encoding) =
-                            decode_with_encoding(&response.bytes().await.unwrap(), &charset);
-                        Payload::Text(TextPayload {
-                            charset: encoding.name().to_owned(),
-                            text,
-                            extension: parse_extension(extension),
-                        })
-                    }
-                    (_, extension) => Payload::Bytes(BytePayload {
-                        extension: parse_extension(extension),
-                        bytes: response.bytes().await.unwrap(),
-                    }),
+                let extension = parse_extension(&mime);
+
+                if is_text(&mime) {
+                    let charset = mime
+                        .get_param("charset")
+                        .map_or("utf-8".into(), |charset| charset.to_string());
+                    let decoded = decode_with_encoding(&bytes, &charset);
+                    Payload::text(extension, decoded, bytes)
+                } else {
+                    Payload::Bytes(BytePayload { extension, bytes })
                 }
             }
             None => Payload::Bytes(BytePayload {
                 extension: None,
-                bytes: response.bytes().await.unwrap(),
+                bytes,
             }),
         }
     }
+
+    /// Decodes the body again with the charset labelled `label`, for when the
+    /// server declares the wrong one. Returns `None` if the label is unknown.
+    pub fn with_encoding(&self, label: &str) -> Option<Payload> {
+        let encoding = Encoding::for_label(label.trim().as_bytes())?;
+
+        let (extension, bytes) = match self {
+            Payload::Bytes(b) => (b.extension.clone(), b.bytes.clone()),
+            Payload::Text(t) => (t.extension.clone(), t.bytes.clone()),
+        };
+
+        Some(Payload::text(
+            extension,
+            decode_forced(&bytes, encoding),
+            bytes,
+        ))
+    }
+
+    /// Size of the undecoded body, in bytes.
+    pub fn size(&self) -> usize {
+        self.bytes().len()
+    }
+
+    /// Undecoded body.
+    pub fn bytes(&self) -> &Bytes {
+        match self {
+            Payload::Bytes(b) => &b.bytes,
+            Payload::Text(t) => &t.bytes,
+        }
+    }
+
+    /// File extension matching the content type, without the leading dot.
+    pub fn extension(&self) -> Option<&str> {
+        match self {
+            Payload::Bytes(b) => b.extension.as_deref(),
+            Payload::Text(t) => t.extension.as_deref(),
+        }
+    }
+
+    pub fn is_text(&self) -> bool {
+        matches!(self, Payload::Text(_))
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.extension() == Some("json")
+    }
+
+    /// Decoded text, or a lossy UTF-8 decode of the bytes.
+    pub fn as_text_lossy(&self) -> Cow<'_, str> {
+        match self {
+            Payload::Bytes(b) => String::from_utf8_lossy(&b.bytes),
+            Payload::Text(t) => Cow::Borrowed(&*t.text),
+        }
+    }
+
+    pub fn json(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::from_str(&self.as_text_lossy())
+    }
+
+    /// What gets saved of the body: the decoded text (as UTF-8) or the bytes.
+    pub fn content(&self) -> &[u8] {
+        match self {
+            Payload::Bytes(b) => &b.bytes[..],
+            Payload::Text(t) => t.text.as_bytes(),
+        }
+    }
+
+    /// Writes the [`content`](Self::content) of the body to `path`.
+    pub fn write_to(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.content())?;
+        file.flush()
+    }
+
+    fn text(extension: Option<String>, decoded: Decoded, bytes: Bytes) -> Payload {
+        Payload::Text(TextPayload {
+            extension,
+            charset: decoded.encoding.name().to_owned(),
+            text: decoded.text.into(),
+            warning: decoded.warning,
+            bytes,
+        })
+    }
+}
+
+// Structured syntax suffixes of textual types, i.e. `application/vnd.api+json`
+// or `image/svg+xml`, also matched as subtypes
+const TEXT_SUFFIXES: &[&str] = &["json", "xml", "yaml", "json-seq"];
+
+// Textual types outside of `text/*`
+const TEXT_APPLICATION_SUBTYPES: &[&str] = &[
+    "javascript",
+    "ecmascript",
+    "x-javascript",
+    "x-www-form-urlencoded",
+    "graphql",
+    "sql",
+    "toml",
+    "x-yaml",
+    "x-ndjson",
+    "x-sh",
+];
+
+fn is_text(mime: &Mime) -> bool {
+    let structured = mime.suffix().unwrap_or(mime.subtype());
+
+    mime.type_() == mime::TEXT
+        || TEXT_SUFFIXES.contains(&structured.as_str())
+        || (mime.type_() == mime::APPLICATION
+            && TEXT_APPLICATION_SUBTYPES.contains(&mime.subtype().as_str()))
+}
+
+// Guesses the mime type from the first bytes, for servers that don't send
+// a (meaningful) Content-Type.
+fn sniff(bytes: &[u8]) -> Option<Mime> {
+    const SNIFF_LEN: usize = 512;
+
+    let head = &bytes[..bytes.len().min(SNIFF_LEN)];
+    let head = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+
+    // Cutting at SNIFF_LEN may split a multi-byte character
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+    {
+        return None;
+    }
+
+    let trimmed = text.trim_start();
+    let lower = trimmed.get(..14).unwrap_or(trimmed).to_ascii_lowercase();
+
+    let mime = if trimmed.starts_with(['{', '[']) {
+        mime::APPLICATION_JSON
+    } else if lower.starts_with("<?xml") {
+        mime::TEXT_XML
+    } else if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        mime::TEXT_HTML
+    } else {
+        mime::TEXT_PLAIN
+    };
+
+    Some(mime)
+}
+
+// Extensions preferred over the (alphabetically first) one from `mime_guess`
+const PREFERRED_EXTENSIONS: &[(&str, &str)] = &[
+    ("text/plain", "txt"),
+    ("text/html", "html"),
+    ("text/markdown", "md"),
+    ("text/javascript", "js"),
+    ("application/javascript", "js"),
+    ("text/xml", "xml"),
+    ("application/xml", "xml"),
+    ("text/yaml", "yaml"),
+    ("application/yaml", "yaml"),
+    ("application/x-yaml", "yaml"),
+    ("image/jpeg", "jpg"),
+    ("image/vnd.microsoft.icon", "ico"),
+    ("audio/mpeg", "mp3"),
+    ("audio/ogg", "ogg"),
+    ("audio/x-wav", "wav"),
+];
+
+pub(crate) fn parse_extension(mime: &Mime) -> Option<String> {
+    let essence = mime.essence_str();
+
+    if let Some((_, extension)) = PREFERRED_EXTENSIONS.iter().find(|(m, _)| *m == essence) {
+        return Some(extension.to_string());
+    }
+
+    // i.e. `application/problem+json`
+    match mime.suffix().map(|suffix| suffix.as_str()) {
+        Some("json") => return Some("json".into()),
+        Some("xml") if mime.subtype() != mime::SVG => return Some("xml".into()),
+        Some("yaml") => return Some("yaml".into()),
+        _ => (),
+    }
+
+    if *mime == mime::APPLICATION_OCTET_STREAM {
+        return None;
+    }
+
+    let extensions = mime_guess::get_mime_extensions_str(essence)?;
+    let subtype = mime.subtype().as_str();
+
+    extensions
+        .iter()
+        .find(|&&extension| extension == subtype)
+        .or(extensions.first())
+        .map(|extension| extension.to_string())
 }
 
-fn parse_extension(name: Name) -> Option<String> {
-    match name {
-        mime::PDF => Some("pdf"),
-        mime::HTML => Some("html"),
-        mime::BMP => Some("bmp"),
-        mime::CSS => Some("css"),
-        mime::CSV => Some("csv"),
-        mime::GIF => Some("gif"),
-        mime::JAVASCRIPT => Some("js"),
-        mime::JPEG => Some("jpg"),
-        mime::JSON => Some("json"),
-        mime::MP4 => Some("mp4"),
-        mime::MPEG => Some("mpeg"),
-        mime::PNG => Some("png"),
-        mime::SVG => Some("svg"),
-        mime::XML => Some("xml"),
-        _ => None,
-    }
-    .map(str::to_string)
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{parse_extension, Payload};
+
+    fn sniffed(body: &'static [u8]) -> Option<String> {
+        match Payload::new(None, Bytes::from_static(body)) {
+            Payload::Text(t) => Some(t.extension.unwrap_or("txt".into())),
+            Payload::Bytes(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniffed(b"  {\"a\": 1}"), Some("json".into()));
+        assert_eq!(sniffed(b"[1, 2]"), Some("json".into()));
+        assert_eq!(sniffed(b"<?xml version=\"1.0\"?><a/>"), Some("xml".into()));
+        assert_eq!(
+            sniffed(b"<!DOCTYPE html><html></html>"),
+            Some("html".into())
+        );
+        assert_eq!(sniffed(b"\xEF\xBB\xBFhello"), Some("txt".into()));
+        assert_eq!(sniffed(b"\x89PNG\r\n\x1a\n\0\0"), None);
+        assert_eq!(sniffed(b"\xff\xfe\x00"), None);
+    }
+
+    #[test]
+    fn test_declared_mime() {
+        let payload = Payload::new(
+            Some(mime::IMAGE_PNG),
+            Bytes::from_static(b"{\"not\": \"sniffed\"}"),
+        );
+        assert!(matches!(payload, Payload::Bytes(b) if b.extension.as_deref() == Some("png")));
+    }
+
+    #[test]
+    fn test_structured_suffix() {
+        let payload = |mime: &str| Payload::new(Some(mime.parse().unwrap()), Bytes::new());
+
+        for (mime, extension) in [
+            ("application/vnd.api+json", "json"),
+            ("application/problem+json; charset=utf-8", "json"),
+            ("application/rss+xml", "xml"),
+            ("image/svg+xml", "svg"),
+            ("application/openapi+yaml", "yaml"),
+            ("application/javascript", "js"),
+            ("application/x-www-form-urlencoded", "txt"),
+        ] {
+            let payload = payload(mime);
+            assert!(payload.is_text(), "{mime}");
+            assert_eq!(payload.extension().unwrap_or("txt"), extension, "{mime}");
+        }
+
+        assert!(!payload("application/vnd.ms-excel").is_text());
+        assert!(!payload("image/png").is_text());
+    }
+
+    #[test]
+    fn test_with_encoding() {
+        let payload = Payload::new(Some(mime::TEXT_PLAIN_UTF_8), Bytes::from_static(b"caf\xE9"));
+        assert!(matches!(&payload, Payload::Text(t) if t.warning.is_some()));
+
+        let Some(Payload::Text(t)) = payload.with_encoding("latin1") else {
+            panic!("expected text");
+        };
+        assert_eq!(&*t.text, "café");
+        assert_eq!(t.charset, "windows-1252");
+        assert!(t.warning.is_none());
+
+        assert!(payload.with_encoding("made-up").is_none());
+    }
+
+    #[test]
+    fn test_helpers() {
+        let payload = Payload::new(
+            Some(mime::APPLICATION_JSON),
+            Bytes::from_static(b"{\"a\": [1]}"),
+        );
+        assert!(payload.is_text());
+        assert!(payload.is_json());
+        assert_eq!(payload.size(), 10);
+        assert_eq!(payload.json().unwrap()["a"][0], 1);
+
+        let payload = Payload::new(Some(mime::IMAGE_PNG), Bytes::from_static(b"\x89PNG"));
+        assert!(!payload.is_text());
+        assert_eq!(payload.extension(), Some("png"));
+        assert_eq!(payload.as_text_lossy(), "\u{FFFD}PNG");
+        assert!(payload.json().is_err());
+    }
+
+    #[test]
+    fn test_parse_extension() {
+        let extension = |s: &str| parse_extension(&s.parse().unwrap());
+
+        assert_eq!(extension("application/json"), Some("json".into()));
+        assert_eq!(extension("application/problem+json"), Some("json".into()));
+        assert_eq!(extension("application/atom+xml"), Some("xml".into()));
+        assert_eq!(extension("image/svg+xml"), Some("svg".into()));
+        assert_eq!(extension("text/csv"), Some("csv".into()));
+        assert_eq!(extension("text/tab-separated-values"), Some("tsv".into()));
+        assert_eq!(extension("text/plain; charset=utf-8"), Some("txt".into()));
+        assert_eq!(extension("image/jpeg"), Some("jpg".into()));
+        assert_eq!(extension("application/zip"), Some("zip".into()));
+        assert_eq!(extension("application/wasm"), Some("wasm".into()));
+        assert_eq!(extension("font/woff2"), Some("woff2".into()));
+        assert_eq!(extension("audio/mpeg"), Some("mp3".into()));
+        assert_eq!(extension("audio/wav"), Some("wav".into()));
+        assert_eq!(extension("application/octet-stream"), None);
+        assert_eq!(extension("application/x-unknown"), None);
+    }
 }
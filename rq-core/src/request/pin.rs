@@ -0,0 +1,164 @@
+//! Pinning of server certificates, by the SHA-256 fingerprint of the whole
+//! certificate or of its public key.
+
+use std::{fmt::Write, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+
+/// Expected certificate of a host.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CertPin {
+    // Hex, i.e. `AB:CD:...` as shown by browsers and `openssl x509 -fingerprint`
+    Certificate([u8; 32]),
+    // `sha256//<base64>`, like curl's `--pinnedpubkey`
+    PublicKey([u8; 32]),
+}
+
+impl CertPin {
+    /// Whether the DER encoded certificate `der` is the pinned one.
+    pub fn matches(&self, der: &[u8]) -> bool {
+        match self {
+            CertPin::Certificate(hash) => Sha256::digest(der).as_slice() == hash,
+            CertPin::PublicKey(hash) => {
+                public_key(der).is_some_and(|key| Sha256::digest(key).as_slice() == hash)
+            }
+        }
+    }
+}
+
+impl FromStr for CertPin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid pin '{s}', expected a SHA-256 fingerprint");
+
+        if let Some(key) = s.strip_prefix("sha256//") {
+            let bytes = STANDARD.decode(key).map_err(|_| invalid())?;
+            return bytes
+                .try_into()
+                .map(CertPin::PublicKey)
+                .map_err(|_| invalid());
+        }
+
+        let hex = s.replace(':', "");
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+
+        bytes
+            .try_into()
+            .map(CertPin::Certificate)
+            .map_err(|_| invalid())
+    }
+}
+
+/// SHA-256 fingerprint of the certificate `der`, i.e. `AB:CD:...`.
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .fold(String::new(), |mut s, byte| {
+            if !s.is_empty() {
+                s.push(':');
+            }
+            let _ = write!(s, "{byte:02X}");
+            s
+        })
+}
+
+// `SubjectPublicKeyInfo` of an X.509 certificate, tag and length included
+fn public_key(der: &[u8]) -> Option<&[u8]> {
+    let (_, certificate, _) = tlv(der)?;
+    let (_, mut fields, _) = tlv(certificate)?;
+
+    // Optional `[0]` version
+    if fields.first() == Some(&0xa0) {
+        fields = tlv(fields)?.2;
+    }
+    // Serial number, signature algorithm, issuer, validity and subject
+    for _ in 0..5 {
+        fields = tlv(fields)?.2;
+    }
+
+    let (tag, _, rest) = tlv(fields)?;
+    (tag == 0x30).then(|| &fields[..fields.len() - rest.len()])
+}
+
+// Tag, content and what follows of the DER element at the start of `input`
+fn tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&first, mut input) = input.split_first()?;
+
+    let len = match first {
+        0..=0x7f => first as usize,
+        0x81..=0x84 => {
+            let size = (first & 0x7f) as usize;
+            if input.len() < size {
+                return None;
+            }
+            let (len, rest) = input.split_at(size);
+            input = rest;
+            len.iter().fold(0, |len, &byte| len << 8 | byte as usize)
+        }
+        _ => return None,
+    };
+
+    if input.len() < len {
+        return None;
+    }
+    let (content, rest) = input.split_at(len);
+    Some((tag, content, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Just the structure walked by `public_key`, no signature
+    const CERTIFICATE: &[u8] = &[
+        0x30, 0x17, // certificate
+        0x30, 0x15, // tbsCertificate
+        0xa0, 0x03, 0x02, 0x01, 0x02, // version
+        0x02, 0x01, 0x01, // serial number
+        0x30, 0x00, // signature algorithm
+        0x30, 0x00, // issuer
+        0x30, 0x00, // validity
+        0x30, 0x00, // subject
+        0x30, 0x03, 0x03, 0x01, 0x00, // subjectPublicKeyInfo
+    ];
+
+    #[test]
+    fn test_public_key() {
+        assert_eq!(
+            public_key(CERTIFICATE),
+            Some([0x30, 0x03, 0x03, 0x01, 0x00].as_slice())
+        );
+        assert_eq!(public_key(&CERTIFICATE[..20]), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let certificate: CertPin = fingerprint(CERTIFICATE).parse().unwrap();
+        assert!(certificate.matches(CERTIFICATE));
+        assert!(!certificate.matches(&CERTIFICATE[2..]));
+
+        let key = STANDARD.encode(Sha256::digest([0x30, 0x03, 0x03, 0x01, 0x00]));
+        let key: CertPin = format!("sha256//{key}").parse().unwrap();
+        assert!(key.matches(CERTIFICATE));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!("AB:CD".parse::<CertPin>().is_err());
+        assert!("sha256//AAAA".parse::<CertPin>().is_err());
+        assert_eq!(
+            "00".repeat(32).parse::<CertPin>(),
+            Ok(CertPin::Certificate([0; 32]))
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Differences between what a request asked for and the exchange that took
+//! place: the version of its request line, or the scheme of its url.
+
+use http::{header::STRICT_TRANSPORT_SECURITY, Version};
+
+use super::Response;
+
+// As printed by `Version`'s `Debug`, oldest first
+const VERSIONS: &[&str] = &["HTTP/0.9", "HTTP/1.0", "HTTP/1.1", "HTTP/2.0", "HTTP/3.0"];
+
+impl Response {
+    /// Warnings about the protocol of the response, given the version written
+    /// in the request line, if any: the server answering with another one, or
+    /// the request changing scheme along its redirects.
+    pub fn protocol_warnings(&self, requested: Option<Version>) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // The version isn't forced on the client, which negotiates its own
+        let requested = requested.map(|version| format!("{version:?}"));
+        if let Some(requested) = requested.filter(|version| *version != self.version) {
+            let rank = |version: &str| VERSIONS.iter().position(|v| *v == version);
+            let change = match (rank(&requested), rank(&self.version)) {
+                (Some(asked), Some(got)) if got < asked => "downgraded",
+                (Some(asked), Some(got)) if got > asked => "upgraded",
+                _ => "changed",
+            };
+
+            warnings.push(format!(
+                "{requested} requested, {change} to {} by the client or the server",
+                self.version
+            ));
+        }
+
+        // The url the request was sent to, before any redirect
+        let sent = self
+            .redirects
+            .first()
+            .map_or(self.url.as_str(), |redirect| redirect.url.as_str());
+        match (scheme(sent), scheme(&self.url)) {
+            (Some("http"), Some("https")) => {
+                let hsts = if self.headers.contains_key(STRICT_TRANSPORT_SECURITY) {
+                    ", and the host sets Strict-Transport-Security"
+                } else {
+                    ""
+                };
+                warnings.push(format!(
+                    "redirected from http to https{hsts}: use https in the url to skip the redirect"
+                ));
+            }
+            (Some("https"), Some("http")) => {
+                warnings.push("redirected from https to http, the exchange isn't encrypted".into())
+            }
+            _ => (),
+        }
+
+        warnings
+    }
+}
+
+fn scheme(url: &str) -> Option<&str> {
+    url.split_once("://")
+        .map(|(scheme, _)| scheme)
+        .filter(|scheme| matches!(*scheme, "http" | "https"))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderMap, StatusCode, Version};
+
+    use crate::request::{mime::Payload, Redirect, Response};
+
+    fn response(
+        version: &str,
+        redirects: &[&str],
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Response {
+        Response {
+            status: StatusCode::OK,
+            version: version.into(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.parse().unwrap(), v.parse().unwrap()))
+                .collect::<HeaderMap>(),
+            payload: Payload::new(None, Default::default()),
+            url: url.into(),
+            remote_addr: None,
+            protocol: None,
+            redirects: redirects
+                .iter()
+                .map(|url| Redirect {
+                    url: url.to_string(),
+                    status: StatusCode::MOVED_PERMANENTLY,
+                })
+                .collect(),
+            timings: Default::default(),
+            spilled: None,
+            trailers: HeaderMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_version_warnings() {
+        let res = response("HTTP/1.1", &[], "https://test.dev/", &[]);
+        assert!(res.protocol_warnings(None).is_empty());
+        assert!(res.protocol_warnings(Some(Version::HTTP_11)).is_empty());
+        assert_eq!(
+            res.protocol_warnings(Some(Version::HTTP_2)),
+            ["HTTP/2.0 requested, downgraded to HTTP/1.1 by the client or the server"]
+        );
+
+        let res = response("HTTP/2.0", &[], "https://test.dev/", &[]);
+        assert_eq!(
+            res.protocol_warnings(Some(Version::HTTP_11)),
+            ["HTTP/1.1 requested, upgraded to HTTP/2.0 by the client or the server"]
+        );
+    }
+
+    #[test]
+    fn test_scheme_warnings() {
+        let res = response("HTTP/1.1", &["http://test.dev/"], "https://test.dev/", &[]);
+        assert_eq!(
+            res.protocol_warnings(None),
+            ["redirected from http to https: use https in the url to skip the redirect"]
+        );
+
+        let res = response(
+            "HTTP/1.1",
+            &["http://test.dev/", "https://test.dev/"],
+            "https://test.dev/home",
+            &[("strict-transport-security", "max-age=31536000")],
+        );
+        assert_eq!(
+            res.protocol_warnings(None),
+            ["redirected from http to https, and the host sets Strict-Transport-Security: use https in the url to skip the redirect"]
+        );
+
+        let res = response("HTTP/1.1", &["https://test.dev/"], "http://test.dev/", &[]);
+        assert_eq!(
+            res.protocol_warnings(None),
+            ["redirected from https to http, the exchange isn't encrypted"]
+        );
+
+        let res = response("HTTP/1.1", &[], "http://test.dev/", &[]);
+        assert!(res.protocol_warnings(None).is_empty());
+    }
+}
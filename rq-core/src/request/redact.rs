@@ -0,0 +1,115 @@
+//! Redaction of sensitive headers, to share saved or exported exchanges.
+
+use http::{HeaderMap, HeaderValue};
+
+use crate::{glob::glob, parser::HttpRequest};
+
+use super::Response;
+
+/// Replacement of the redacted values.
+pub const REDACTED: &str = "<redacted>";
+
+/// Headers redacted by default.
+pub const DEFAULT_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Header names to redact, `*` matching any characters, i.e. `x-*-token`.
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    // Lowercase
+    patterns: Vec<String>,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self {
+            patterns: DEFAULT_HEADERS
+                .iter()
+                .map(|&name| name.to_owned())
+                .collect(),
+        }
+    }
+}
+
+impl Redaction {
+    /// Redacts the headers matching `patterns` as well.
+    pub fn with_patterns(mut self, patterns: impl IntoIterator<Item = String>) -> Self {
+        self.patterns.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| pattern.to_ascii_lowercase()),
+        );
+        self
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.patterns.iter().any(|pattern| glob(pattern, &name))
+    }
+
+    /// Copy of `headers` with the values of the matching ones replaced.
+    pub fn headers(&self, headers: &HeaderMap) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                if self.matches(name.as_str()) {
+                    (name.clone(), HeaderValue::from_static(REDACTED))
+                } else {
+                    (name.clone(), value.clone())
+                }
+            })
+            .collect()
+    }
+
+    pub fn request(&self, request: &HttpRequest) -> HttpRequest {
+        HttpRequest {
+            headers: self.headers(&request.headers),
+            credentials: None,
+            ..request.clone()
+        }
+    }
+
+    pub fn response(&self, response: &Response) -> Response {
+        Response {
+            headers: self.headers(&response.headers),
+            ..response.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let redaction = Redaction::default().with_patterns(["X-*-Token".into(), "*key".into()]);
+
+        assert!(redaction.matches("Authorization"));
+        assert!(redaction.matches("set-cookie"));
+        assert!(redaction.matches("x-auth-token"));
+        assert!(redaction.matches("x-api-key"));
+        assert!(!redaction.matches("x-token"));
+        assert!(!redaction.matches("cookies"));
+        assert!(!redaction.matches("content-type"));
+    }
+
+    #[test]
+    fn test_headers() {
+        let headers = HeaderMap::from_iter([
+            (
+                "authorization".parse().unwrap(),
+                "Bearer abc".parse().unwrap(),
+            ),
+            ("accept".parse().unwrap(), "*/*".parse().unwrap()),
+        ]);
+
+        let redacted = Redaction::default().headers(&headers);
+        assert_eq!(redacted["authorization"], REDACTED);
+        assert_eq!(redacted["accept"], "*/*");
+    }
+}
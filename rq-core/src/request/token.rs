@@ -0,0 +1,180 @@
+//! Human-readable decoding of tokens found in headers and bodies: JWTs,
+//! base64, percent-encoded text and unix timestamps.
+
+use std::fmt::Write;
+
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::DateTime;
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedToken {
+    pub token: String,
+    // i.e. `jwt` or `base64`
+    pub kind: &'static str,
+    pub text: String,
+}
+
+// Claims holding a unix timestamp
+const TIME_CLAIMS: &[&str] = &["exp", "iat", "nbf", "auth_time"];
+
+// Shorter base64 tokens are too likely to be plain words
+const MIN_BASE64_LEN: usize = 8;
+
+/// Decodes the tokens of `text` that look like something, in order.
+pub fn decode_all(text: &str) -> Vec<DecodedToken> {
+    text.split(|c: char| c.is_whitespace() || "\"'`,;:<>()[]{}".contains(c))
+        .filter_map(decode)
+        .collect()
+}
+
+/// Decodes `token`, or its value when it's a `key=value` pair, i.e. a cookie.
+pub fn decode(token: &str) -> Option<DecodedToken> {
+    let value = token
+        .split_once('=')
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty() && !value.starts_with('='));
+
+    [token].into_iter().chain(value).find_map(|token| {
+        let (kind, text) = jwt(token)
+            .map(|text| ("jwt", text))
+            .or_else(|| timestamp(token).map(|text| ("timestamp", text)))
+            .or_else(|| url_encoded(token).map(|text| ("url-encoded", text)))
+            .or_else(|| base64(token).map(|text| ("base64", text)))?;
+
+        Some(DecodedToken {
+            token: token.to_owned(),
+            kind,
+            text,
+        })
+    })
+}
+
+// Header and claims, pretty-printed, followed by the time claims as dates
+fn jwt(token: &str) -> Option<String> {
+    let mut parts = token.split('.');
+    let (header, claims, _signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let json = |part: &str| -> Option<Value> {
+        let bytes = URL_SAFE_NO_PAD.decode(part.trim_end_matches('=')).ok()?;
+        serde_json::from_slice(&bytes).ok().filter(Value::is_object)
+    };
+    let (header, claims) = (json(header)?, json(claims)?);
+
+    let mut text = format!(
+        "{}\n{}",
+        serde_json::to_string_pretty(&header).ok()?,
+        serde_json::to_string_pretty(&claims).ok()?
+    );
+    for claim in TIME_CLAIMS {
+        if let Some(date) = claims[*claim].as_i64().and_then(format_seconds) {
+            let _ = write!(text, "\n{claim}: {date}");
+        }
+    }
+
+    Some(text)
+}
+
+// Seconds or milliseconds, when all digits
+fn timestamp(token: &str) -> Option<String> {
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match token.len() {
+        10 => format_seconds(token.parse().ok()?),
+        13 => DateTime::from_timestamp_millis(token.parse().ok()?)
+            .map(|date| date.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string()),
+        _ => None,
+    }
+}
+
+fn format_seconds(seconds: i64) -> Option<String> {
+    DateTime::from_timestamp(seconds, 0)
+        .map(|date| date.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+}
+
+fn url_encoded(token: &str) -> Option<String> {
+    if !token.contains('%') {
+        return None;
+    }
+
+    let text = percent_decode_str(token).decode_utf8().ok()?;
+    (text != token).then(|| text.into_owned())
+}
+
+// Any alphabet, as long as it decodes to printable text
+fn base64(token: &str) -> Option<String> {
+    if token.len() < MIN_BASE64_LEN {
+        return None;
+    }
+
+    [STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(token).ok())
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|text| {
+            text.chars()
+                .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_jwt() {
+        // {"alg":"HS256"}.{"exp":1700000000,"sub":"1"}
+        let token = "eyJhbGciOiJIUzI1NiJ9.eyJleHAiOjE3MDAwMDAwMDAsInN1YiI6IjEifQ.c2ln";
+
+        let decoded = decode(token).unwrap();
+        assert_eq!(decoded.kind, "jwt");
+        assert_eq!(
+            decoded.text,
+            "{\n  \"alg\": \"HS256\"\n}\n{\n  \"exp\": 1700000000,\n  \"sub\": \"1\"\n}\nexp: 2023-11-14 22:13:20 UTC"
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let decoded = |token| decode(token).map(|d| (d.kind, d.text));
+
+        assert_eq!(
+            decoded("1700000000"),
+            Some(("timestamp", "2023-11-14 22:13:20 UTC".into()))
+        );
+        assert_eq!(
+            decoded("1700000000123"),
+            Some(("timestamp", "2023-11-14 22:13:20.123 UTC".into()))
+        );
+        assert_eq!(decoded("a%20b%2Fc"), Some(("url-encoded", "a b/c".into())));
+        assert_eq!(
+            decoded("dXNlcjpwYXNz"),
+            Some(("base64", "user:pass".into()))
+        );
+        assert_eq!(
+            decoded("session=dXNlcjpwYXNz"),
+            Some(("base64", "user:pass".into()))
+        );
+        assert_eq!(decoded("password"), None);
+        assert_eq!(decoded("42"), None);
+    }
+
+    #[test]
+    fn test_decode_all() {
+        let decoded = decode_all(r#"{"token": "dXNlcjpwYXNz", "created": 1700000000, "ok": true}"#)
+            .into_iter()
+            .map(|d| d.token)
+            .collect::<Vec<_>>();
+
+        assert_eq!(decoded, vec!["dXNlcjpwYXNz", "1700000000"]);
+    }
+}